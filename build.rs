@@ -1,40 +1,407 @@
 use anyhow::{anyhow, Context, Result};
+use fst::MapBuilder;
 use miniz_oxide::deflate::compress_to_vec;
-use std::collections::{HashMap, HashSet};
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashMap};
 use std::io::{BufRead, Read, Write};
 use std::{env, fs, io, path};
-use trie_rs::map::TrieBuilder;
+
+/// A dictionary read from disk, as `(from, candidates)` pairs in file order.  The first candidate
+/// is the one used for conversion; any others are alternatives that make the entry ambiguous.
+type Entries = Vec<(String, Vec<String>)>;
+
+/// How to pick between several sources that map to the same target when a dictionary is reversed.
+/// Whichever policy is used, the result does not depend on hash ordering, so builds are reproducible.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    /// Prefer the entry that appears first in the dictionary file.
+    FileOrder,
+    /// Prefer the entry with the lowest code points.
+    Codepoint,
+    /// Prefer the entry that is most frequent in Jieba's dictionary, falling back to file order.
+    Frequency,
+}
+
+impl TieBreak {
+    /// Read the tie-breaking policy from `$ZTARCC_TIE_BREAK`, defaulting to file order.
+    fn from_env() -> Result<Self> {
+        println!("cargo::rerun-if-env-changed=ZTARCC_TIE_BREAK");
+        let policy = env::var("ZTARCC_TIE_BREAK");
+        match policy.as_deref() {
+            Err(env::VarError::NotPresent) | Ok("file-order") => Ok(Self::FileOrder),
+            Ok("codepoint") => Ok(Self::Codepoint),
+            Ok("frequency") => Ok(Self::Frequency),
+            Ok(other) => Err(anyhow!(format!("unknown tie-break policy {}", other))),
+            Err(err) => Err(err.clone().into()),
+        }
+    }
+}
+
+/// How to order the sources of a reversed entry: an explicit priority list, then a [TieBreak].
+struct ReverseOrder {
+    tie_break: TieBreak,
+    /// Word frequencies from Jieba's dictionary, read only for [TieBreak::Frequency].
+    frequencies: HashMap<String, u64>,
+    /// The preferred source for each target, from `$ZTARCC_REVERSE_PRIORITY`.
+    priorities: HashMap<String, String>,
+}
+
+impl ReverseOrder {
+    /// Read the tie-breaking policy, and the priority list from the file named by
+    /// `$ZTARCC_REVERSE_PRIORITY` if it is set.  Each line of the file is a target, a tab, and
+    /// the source to prefer for it when reversing.
+    fn from_env() -> Result<Self> {
+        let tie_break = TieBreak::from_env()?;
+        let frequencies = match tie_break {
+            TieBreak::Frequency => read_jieba_frequencies()?,
+            _ => HashMap::new(),
+        };
+        println!("cargo::rerun-if-env-changed=ZTARCC_REVERSE_PRIORITY");
+        let mut priorities = HashMap::new();
+        if let Some(path) = env::var_os("ZTARCC_REVERSE_PRIORITY") {
+            let path = path::PathBuf::from(path);
+            println!("cargo::rerun-if-changed={0}", path.display());
+            let text = fs::read_to_string(&path).context(format!("reading {}", path.display()))?;
+            for (index, line) in text.lines().enumerate() {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (target, source) = line.split_once('\t').ok_or(anyhow!(format!(
+                    "{}: line {} is not a target and a source separated by a tab",
+                    path.display(),
+                    index + 1
+                )))?;
+                priorities.insert(target.to_owned(), source.to_owned());
+            }
+        }
+        Ok(ReverseOrder {
+            tie_break,
+            frequencies,
+            priorities,
+        })
+    }
+
+    /// The index of the source to put first among those mapping to `target`, in file order.
+    fn winner(&self, target: &str, sources: &[&str]) -> usize {
+        let preferred = self
+            .priorities
+            .get(target)
+            .and_then(|preferred| sources.iter().position(|source| source == preferred));
+        if let Some(index) = preferred {
+            return index;
+        }
+        match self.tie_break {
+            TieBreak::FileOrder => 0,
+            TieBreak::Codepoint => (0..sources.len()).min_by_key(|i| sources[*i]).unwrap_or(0),
+            TieBreak::Frequency => (0..sources.len())
+                .max_by_key(|i| {
+                    let frequency = self.frequencies.get(sources[*i]).copied().unwrap_or(0);
+                    (frequency, std::cmp::Reverse(*i))
+                })
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Read the word frequencies from Jieba's dictionary.
+fn read_jieba_frequencies() -> Result<HashMap<String, u64>> {
+    let path = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("jieba-rs/src/data/dict.txt");
+    let text = fs::read_to_string(&path).context(format!("reading {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let word = fields.next()?;
+            let frequency = fields.next()?.parse().ok()?;
+            Some((word.to_owned(), frequency))
+        })
+        .collect())
+}
+
+/// The region feature (`dict-cn`, `dict-tw`, or `dict-hk`) that controls whether a dictionary is
+/// built, or `None` if it is always built.
+fn dict_feature(out_name: &str) -> Option<&'static str> {
+    if out_name.ends_with("China") {
+        Some("dict-cn")
+    } else if out_name.ends_with("Taiwan") {
+        Some("dict-tw")
+    } else if out_name.ends_with("HongKong") {
+        Some("dict-hk")
+    } else {
+        None
+    }
+}
+
+/// Whether a dictionary is enabled by the crate's features.
+fn dict_enabled(out_name: &str) -> bool {
+    dict_feature(out_name).is_none_or(|feature| {
+        let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+        env::var_os(var).is_some()
+    })
+}
+
+/// The directory holding OpenCC's text dictionaries: `$ZTARCC_OPENCC_DATA` if set, for building
+/// against a system or pinned copy, or else the `opencc` submodule, which is also included in the
+/// published crate.
+fn opencc_data_dir() -> Result<path::PathBuf> {
+    println!("cargo::rerun-if-env-changed=ZTARCC_OPENCC_DATA");
+    let dir = match env::var_os("ZTARCC_OPENCC_DATA") {
+        Some(dir) => path::PathBuf::from(dir),
+        None => path::Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("opencc/data/dictionary"),
+    };
+    fs::canonicalize(&dir).context(format!(
+        "could not find the OpenCC dictionaries in {}; check out the opencc submodule or set \
+         ZTARCC_OPENCC_DATA",
+        dir.display()
+    ))
+}
+
+/// Read the extra dictionaries in `$ZTARCC_EXTRA_DICTS`, if set: each `NAME.txt` there, in
+/// OpenCC's text format, is merged into the dictionary `NAME` (such as `ToTaiwan`) ahead of
+/// OpenCC's own entries.  Other files are an error, to catch misspelled names.
+fn read_extra_dicts(definitions: &BTreeMap<&str, Vec<&str>>) -> Result<HashMap<String, Entries>> {
+    println!("cargo::rerun-if-env-changed=ZTARCC_EXTRA_DICTS");
+    let Some(dir) = env::var_os("ZTARCC_EXTRA_DICTS") else {
+        return Ok(HashMap::new());
+    };
+    let dir = path::PathBuf::from(dir);
+    println!("cargo::rerun-if-changed={0}", dir.display());
+    let mut extra_dicts = HashMap::new();
+    for entry in
+        fs::read_dir(&dir).context(format!("reading extra dictionaries {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let name = match (path.file_stem(), path.extension()) {
+            (Some(name), Some(extension)) if extension == "txt" => name.to_string_lossy(),
+            _ => {
+                return Err(anyhow!(format!(
+                    "{} is not a .txt dictionary",
+                    path.display()
+                )))
+            }
+        };
+        if !definitions.contains_key(name.as_ref()) {
+            return Err(anyhow!(format!(
+                "{} does not name a dictionary; use one of {:?}",
+                path.display(),
+                definitions.keys().collect::<Vec<_>>()
+            )));
+        }
+        extra_dicts.insert(name.to_string(), read_dict(&dir, &name)?);
+    }
+    Ok(extra_dicts)
+}
+
+/// Compress data to embed in the library, or leave it as it is with the `uncompressed` feature,
+/// which trades binary size for startup time.  See `decompress` in the library.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    match env::var_os("CARGO_FEATURE_UNCOMPRESSED") {
+        Some(_) => bytes.to_vec(),
+        None => compress_to_vec(bytes, 6),
+    }
+}
 
 /// Read a dictionary from disk.
-fn read_dict(in_dir: &path::Path, name: &str) -> Result<HashMap<String, String>> {
+fn read_dict(in_dir: &path::Path, name: &str) -> Result<Entries> {
     let mut in_path = in_dir.join(name);
     in_path.set_extension("txt");
     let in_file = fs::File::open(in_path).context(format!("reading dictionary {0}", name))?;
 
-    let mut map = HashMap::<String, String>::new();
+    let mut entries = Entries::new();
     for maybe_line in io::BufReader::new(in_file).lines() {
         let line = maybe_line?;
         let (from, rest) = line
             .split_once('\t')
             .ok_or(anyhow!("could not split line"))?;
-        if let Some(first_token) = rest.split_ascii_whitespace().next() {
-            map.insert(from.to_owned(), first_token.to_owned());
+        let candidates: Vec<_> = rest.split_ascii_whitespace().map(str::to_owned).collect();
+        if !candidates.is_empty() {
+            entries.push((from.to_owned(), candidates));
+        }
+    }
+    Ok(entries)
+}
+
+/// Reverse a dictionary on each entry's first candidate.  When several entries share a target,
+/// `order` picks the first candidate of the reversed entry, and the rest follow in file order.
+fn reverse_dict(in_dict: &Entries, order: &ReverseOrder) -> Entries {
+    let mut reversed = BTreeMap::<&str, Vec<&str>>::new();
+    for (from, candidates) in in_dict {
+        reversed.entry(&candidates[0]).or_default().push(from);
+    }
+    reversed
+        .into_iter()
+        .map(|(k, mut sources)| {
+            let winner = sources.remove(order.winner(k, &sources));
+            sources.insert(0, winner);
+            (
+                k.to_owned(),
+                sources.into_iter().map(str::to_owned).collect(),
+            )
+        })
+        .collect()
+}
+
+/// A dictionary chain merged into a single map.
+type Merged<'a> = BTreeMap<&'a str, String>;
+
+/// The candidates of the ambiguous entries in a [Merged] dictionary.
+type Ambiguities<'a> = BTreeMap<&'a str, Vec<String>>;
+
+/// Dictionaries converting directly between two regions, each composed at build time from the
+/// conversion to OpenCC Standard and the conversion from it, as `(name, first, second)`.
+/// Converting in a single pass keeps the second dictionary from re-converting the output of the
+/// first, which otherwise causes artifacts between the two traditional scripts.
+const COMPOSED_DICTS: [(&str, &str, &str); 2] = [
+    ("HongKongToTaiwan", "FromHongKong", "ToTaiwan"),
+    ("TaiwanToHongKong", "FromTaiwan", "ToHongKong"),
+];
+
+/// Convert text with a merged dictionary, replacing the longest matches first.
+fn convert_with(dict: &Merged, text: &str) -> String {
+    let max_len = dict.keys().map(|k| k.len()).max().unwrap_or(0);
+    let mut output = String::with_capacity(text.len());
+    let mut offset = 0;
+    while let Some(ch) = text[offset..].chars().next() {
+        let rest = &text[offset..];
+        let matched = rest
+            .char_indices()
+            .map(|(i, ch)| i + ch.len_utf8())
+            .take_while(|len| *len <= max_len)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .find_map(|len| dict.get(&rest[..len]).map(|v| (len, v)));
+        match matched {
+            Some((len, value)) => {
+                output.push_str(value);
+                offset += len;
+            }
+            None => {
+                output.push(ch);
+                offset += ch.len_utf8();
+            }
+        }
+    }
+    output
+}
+
+/// Compose two merged dictionaries into one that has the effect of applying `first` and then
+/// `second`.  Entries of `second` are only kept where `first` would leave the key alone.
+fn compose_dicts<'a>(first: &Merged<'a>, second: &Merged<'a>) -> Merged<'a> {
+    let mut composed: Merged = first
+        .iter()
+        .map(|(k, v)| (*k, convert_with(second, v)))
+        .collect();
+    for (k, v) in second {
+        if !composed.contains_key(k) && convert_with(first, k) == *k {
+            composed.insert(*k, v.to_owned());
         }
     }
-    Ok(map)
+    composed
+}
+
+/// Write a merged dictionary as an FST mapping each key to the location of its value in
+/// `$OUT_DIR/NAME.fst`, and the concatenated values in `$OUT_DIR/NAME.values`, so that the
+/// library can use them in place.  Each location is packed as the value's byte offset in the
+/// upper 32 bits and its length in the lower 32 bits.  The ambiguous entries are serialized and
+/// compressed into `$OUT_DIR/NAME.ambiguous.zpostcard`, since they are rarely needed.  Given an
+/// asset directory, all of it is also written there as `NAME.bin` (see [write_asset]).
+fn write_dict(
+    out_dir: &path::Path,
+    asset_dir: Option<&path::Path>,
+    out_name: &str,
+    merged: &Merged,
+    ambiguities: &Ambiguities,
+) -> Result<()> {
+    let serialized_ambiguities = postcard::to_stdvec(ambiguities)
+        .context(format!("serializing ambiguities for {}", out_name))?;
+    fs::write(
+        out_dir.join(format!("{}.ambiguous.zpostcard", out_name)),
+        compress(&serialized_ambiguities),
+    )
+    .context(format!("writing ambiguities for {}", out_name))?;
+
+    let fst_file = fs::File::create(out_dir.join(format!("{}.fst", out_name))).context(format!(
+        "could not open dictionary output for {0}",
+        out_name
+    ))?;
+    let mut builder = MapBuilder::new(io::BufWriter::new(fst_file))?;
+    let mut values = String::new();
+    for (k, v) in merged {
+        let packed = ((values.len() as u64) << 32) | v.len() as u64;
+        builder
+            .insert(k, packed)
+            .context(format!("building dictionary {}", out_name))?;
+        values.push_str(v);
+    }
+    builder
+        .finish()
+        .context(format!("writing dictionary {}", out_name))?;
+    fs::write(out_dir.join(format!("{}.values", out_name)), &values)
+        .context(format!("writing dictionary values {}", out_name))?;
+    match asset_dir {
+        Some(asset_dir) => write_asset(out_dir, asset_dir, out_name, &values, ambiguities),
+        None => Ok(()),
+    }
+}
+
+/// Write a dictionary as a single asset, `ASSET_DIR/NAME.bin`, for the library to register at
+/// runtime with the `external-dicts` feature: its name, map, values, and ambiguous entries,
+/// serialized together with postcard.
+fn write_asset(
+    out_dir: &path::Path,
+    asset_dir: &path::Path,
+    out_name: &str,
+    values: &str,
+    ambiguities: &Ambiguities,
+) -> Result<()> {
+    let map = fs::read(out_dir.join(format!("{}.fst", out_name)))
+        .context(format!("reading dictionary {}", out_name))?;
+    let asset = postcard::to_stdvec(&(out_name, map, values, ambiguities))
+        .context(format!("serializing asset for {}", out_name))?;
+    fs::write(asset_dir.join(format!("{}.bin", out_name)), asset)
+        .context(format!("writing asset for {}", out_name))
+}
+
+/// Whether the `jieba` feature is on.  Without it, text is only ever split into characters, so
+/// the phrase dictionaries and Jieba's dictionary are left out.
+fn jieba_enabled() -> bool {
+    env::var_os("CARGO_FEATURE_JIEBA").is_some()
 }
 
-/// Reverse a dictionary.
-fn reverse_dict(in_dict: &HashMap<String, String>) -> HashMap<String, String> {
-    HashMap::from_iter(in_dict.iter().map(|(k, v)| (v.to_owned(), k.to_owned())))
+/// Whether the `external-dicts` feature is on, leaving the dictionaries out of the library.
+fn external_dicts() -> bool {
+    env::var_os("CARGO_FEATURE_EXTERNAL_DICTS").is_some()
 }
 
-/// Reads all dictionary files in OpenCC, generating a serialized trie for each.
-/// Emitted files are placed in `$OUT_DIR` with a `.postcard` extension.
-/// Also emits a `keys.postcard` with all keys.
-/// Returns the list of dictionaries.
-fn build_all_dicts(out_dir: &path::Path) -> Result<Vec<String>> {
-    let dict_definitions = HashMap::from([
+/// The directory to write dictionary assets to with the `external-dicts` feature:
+/// `$ZTARCC_ASSET_DIR` if set, or else `$OUT_DIR/assets`.  `None` without the feature.
+fn asset_dir(out_dir: &path::Path) -> Result<Option<path::PathBuf>> {
+    println!("cargo::rerun-if-env-changed=ZTARCC_ASSET_DIR");
+    if !external_dicts() {
+        return Ok(None);
+    }
+    let dir = match env::var_os("ZTARCC_ASSET_DIR") {
+        Some(dir) => path::PathBuf::from(dir),
+        None => out_dir.join("assets"),
+    };
+    fs::create_dir_all(&dir).context(format!("creating asset directory {}", dir.display()))?;
+    Ok(Some(dir))
+}
+
+/// Reads all dictionary files in OpenCC, generating an FST and values file for each (see
+/// [write_dict]), placed in `$OUT_DIR`.
+/// Also emits a `keys.zpostcard` with all keys.
+/// When a key appears in more than one dictionary of a chain, the earliest dictionary wins.
+/// Dictionaries for regions whose features are disabled are skipped, and so are the
+/// [COMPOSED_DICTS] that depend on them.  Without the `jieba` feature, phrase dictionaries are
+/// left out of each chain.
+/// Returns the list of dictionaries, and whether each was built.
+fn build_all_dicts(
+    out_dir: &path::Path,
+    asset_dir: Option<&path::Path>,
+) -> Result<Vec<(String, bool)>> {
+    let reverse_order = ReverseOrder::from_env()?;
+    let all_definitions = BTreeMap::from([
         ("FromStandard", vec![]),
         ("FromChina", vec!["STCharacters", "STPhrases"]),
         (
@@ -60,10 +427,26 @@ fn build_all_dicts(out_dir: &path::Path) -> Result<Vec<String>> {
             ],
         ),
         ("ToHongKong", vec!["HKVariants"]),
+        // Variants only, without Taiwan's phrases, for normalizing traditional text.
+        (
+            "VariantsFromTaiwan",
+            vec!["!TWVariants", "TWVariantsRevPhrases"],
+        ),
+        ("VariantsToTaiwan", vec!["TWVariants"]),
     ]);
-    let source_dir = fs::canonicalize(
-        path::Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("opencc/data/dictionary"),
-    )?;
+    let dict_definitions: BTreeMap<_, _> = all_definitions
+        .clone()
+        .into_iter()
+        .filter(|(out_name, _)| dict_enabled(out_name))
+        .map(|(out_name, in_names)| {
+            let in_names: Vec<_> = in_names
+                .into_iter()
+                .filter(|in_name| jieba_enabled() || !in_name.contains("Phrases"))
+                .collect();
+            (out_name, in_names)
+        })
+        .collect();
+    let source_dir = opencc_data_dir()?;
     println!("cargo::rerun-if-changed={0}", source_dir.display());
 
     let names: Vec<_> = dict_definitions
@@ -71,13 +454,12 @@ fn build_all_dicts(out_dir: &path::Path) -> Result<Vec<String>> {
         .flatten()
         .map(|v| v.trim_start_matches('!'))
         .collect();
-    let mut dicts: HashMap<&str, HashMap<String, String>> =
-        HashMap::from_iter(names.iter().map(|name| {
-            let dict = read_dict(&source_dir, name)
-                .context(anyhow!(format!("failed to read {}", name)))
-                .unwrap();
-            (*name, dict)
-        }));
+    let mut dicts: HashMap<&str, Entries> = HashMap::from_iter(names.iter().map(|name| {
+        let dict = read_dict(&source_dir, name)
+            .context(anyhow!(format!("failed to read {}", name)))
+            .unwrap();
+        (*name, dict)
+    }));
 
     // The largest dictionary by far is STPhrases, which is never used in reverse; therefore, we can
     // optimize total time by doing the reverse ahead of time so that we don't need to clone the huge dict.
@@ -89,97 +471,143 @@ fn build_all_dicts(out_dir: &path::Path) -> Result<Vec<String>> {
                     dicts
                         .get(without_prefix)
                         .ok_or(anyhow!(format!("failed to find dict {}", dict)))?,
+                    &reverse_order,
                 ),
             );
         }
     }
 
-    let mut all_keys = HashSet::<String>::new();
+    let extra_dicts = read_extra_dicts(&all_definitions)?;
 
-    let result = dict_definitions
-        .iter()
-        .map(|(out_name, in_names)| -> Result<()> {
-            let mut builder = TrieBuilder::<u8, String>::new();
-            for in_name in in_names {
-                let from_dict = dicts.get(in_name).ok_or(anyhow!(format!(
-                    "failed to find dictionary {} while constructing {}",
-                    in_name, out_name
-                )))?;
+    let mut all_keys = BTreeSet::<String>::new();
+
+    let mut merged_dicts = BTreeMap::<&str, (Merged, Ambiguities)>::new();
+    for (out_name, in_names) in &dict_definitions {
+        let mut merged = Merged::new();
+        let mut ambiguities = Ambiguities::new();
+        let mut from_dicts: Vec<&Entries> = extra_dicts.get(*out_name).into_iter().collect();
+        for in_name in in_names {
+            from_dicts.push(dicts.get(in_name).ok_or(anyhow!(format!(
+                "failed to find dictionary {} while constructing {}",
+                in_name, out_name
+            )))?);
+        }
+        for from_dict in from_dicts {
+            for (k, candidates) in from_dict {
+                if let btree_map::Entry::Vacant(entry) = merged.entry(k) {
+                    entry.insert(candidates[0].to_owned());
+                    if candidates.len() > 1 {
+                        ambiguities.insert(k, candidates.to_owned());
+                    }
+                }
+            }
+            all_keys.extend(
                 from_dict
                     .iter()
-                    .for_each(|(k, v)| builder.push(k, v.to_owned()));
-                all_keys.extend(
-                    from_dict
-                        .keys()
-                        .filter(|k| k.len() > 3)
-                        .map(|v| v.to_string()),
-                );
-            }
-            let mut out_path = out_dir.join(out_name);
-            out_path.set_extension("zpostcard");
-            let mut out_file = fs::File::create(out_path).context(format!(
-                "could not open dictionary output for {0}",
-                out_name
-            ))?;
-            let dict = builder.build();
-            let serialized_dict = postcard::to_stdvec(&dict)
-                .context(format!("serializing dictionary {}", out_name))?;
-            let compressed_dict = compress_to_vec(&serialized_dict, 6);
-            out_file
-                .write_all(&compressed_dict)
-                .context(format!("writing compressed dictionary {}", out_name))?;
-
-            Ok(())
-        })
-        .find(|result| result.is_err());
-    if let Some(v) = result {
-        v?;
+                    .map(|(k, _)| k)
+                    .filter(|k| k.len() > 3)
+                    .map(|v| v.to_string()),
+            );
+        }
+        merged_dicts.insert(out_name, (merged, ambiguities));
+    }
+
+    let mut composed_names = Vec::new();
+    for (out_name, first, second) in COMPOSED_DICTS {
+        if let (Some((first, first_ambiguities)), Some((second, second_ambiguities))) =
+            (merged_dicts.get(first), merged_dicts.get(second))
+        {
+            let composed = compose_dicts(first, second);
+            // Keep the ambiguities of whichever dictionary the composed entry came from.
+            let ambiguities: Ambiguities = composed
+                .keys()
+                .filter_map(|k| {
+                    let candidates = match first.contains_key(k) {
+                        true => first_ambiguities.get(k),
+                        false => second_ambiguities.get(k),
+                    };
+                    candidates.map(|candidates| (*k, candidates.to_owned()))
+                })
+                .collect();
+            write_dict(out_dir, asset_dir, out_name, &composed, &ambiguities)?;
+            composed_names.push(out_name);
+        }
+    }
+    for (out_name, (merged, ambiguities)) in &merged_dicts {
+        write_dict(out_dir, asset_dir, out_name, merged, ambiguities)?;
     }
     let keys_vec: Vec<_> = all_keys.iter().collect();
     let serialized_keys = postcard::to_stdvec(&keys_vec).context("serializing keys")?;
-    let compressed_keys = compress_to_vec(&serialized_keys, 6);
+    let compressed_keys = compress(&serialized_keys);
     let keys_path = out_dir.join("keys.zpostcard");
     let mut keys_file = fs::File::create(keys_path).context("opening keys output")?;
     keys_file
         .write_all(&compressed_keys)
         .context("writing compressed keys")?;
 
-    Ok(dict_definitions.keys().map(|k| k.to_string()).collect())
+    Ok(all_definitions
+        .keys()
+        .map(|k| (k.to_string(), dict_definitions.contains_key(k)))
+        .chain(
+            COMPOSED_DICTS
+                .iter()
+                .map(|(k, _, _)| (k.to_string(), composed_names.contains(k))),
+        )
+        .collect())
 }
 
-/// Write out the main source file that will be included in the library.
-fn write_source(out_dir: &path::Path, names: &Vec<String>) -> Result<()> {
-    let out_path = out_dir.join("dicts.rs");
-    let mut out_file = fs::File::create(out_path)?;
-
+/// Write out the functions that load the embedded dictionaries and their ambiguities.  These are
+/// left out with the `external-dicts` feature, which registers dictionaries at runtime instead.
+fn write_loaders(out_file: &mut fs::File, names: &Vec<(String, bool)>) -> Result<()> {
     writeln!(
         out_file,
         r##"
-        #[derive(PartialEq,Eq,Hash,Debug,Clone,Copy,enum_map::Enum)]
-        /// DictionaryKeys lists the available dictionary types
-        enum DictionaryKeys {{
+        /// Use one of the embedded dictionaries in place, or return `None` if it was not
+        /// included in the build.
+        fn load_dictionary(key: DictionaryKeys) -> Option<Dictionary> {{
+            let (name, map, values): (&str, &'static [u8], &'static [u8]) = match key {{
     "##
     )?;
-    for name in names {
-        writeln!(out_file, "  {0},", name)?;
+    for (name, enabled) in names {
+        if !enabled {
+            writeln!(out_file, "DictionaryKeys::{0} => return None,", name)?;
+            continue;
+        }
+        writeln!(
+            out_file,
+            r##"
+                DictionaryKeys::{0} => (
+                    "{0}",
+                    &include_bytes!(concat!(env!("OUT_DIR"), "/{0}.fst"))[..],
+                    &include_bytes!(concat!(env!("OUT_DIR"), "/{0}.values"))[..],
+                ),
+        "##,
+            name
+        )?;
     }
     writeln!(
         out_file,
         r##"
+            }};
+            Some(Dictionary::from_static(map, values)
+                .unwrap_or_else(|_| panic!("failed to load dictionary {{}}", name)))
         }}
 
-        type Dictionaries = enum_map::EnumMap<DictionaryKeys, Dictionary>;
-
-        static DICTIONARIES: once_cell::sync::Lazy<Dictionaries> = once_cell::sync::Lazy::new(|| {{
+        /// Decompress and deserialize the candidates of a dictionary's ambiguous entries, or
+        /// return `None` if the dictionary was not included in the build.
+        fn load_ambiguities(key: DictionaryKeys) -> Option<Ambiguities> {{
+            let (name, compressed): (&str, &[u8]) = match key {{
     "##
     )?;
-    for name in names {
+    for (name, enabled) in names {
+        if !enabled {
+            writeln!(out_file, "DictionaryKeys::{0} => return None,", name)?;
+            continue;
+        }
         writeln!(
             out_file,
             r##"
-            #[allow(non_snake_case)]
-            let {0}_bytes = decompress_to_vec(include_bytes!(concat!(env!("OUT_DIR"), "/{0}.zpostcard")))
-                .expect("failed to decompress dictionary {0}");
+                DictionaryKeys::{0} => ("{0}", &include_bytes!(concat!(env!("OUT_DIR"), "/{0}.ambiguous.zpostcard"))[..]),
         "##,
             name
         )?;
@@ -187,42 +615,58 @@ fn write_source(out_dir: &path::Path, names: &Vec<String>) -> Result<()> {
     writeln!(
         out_file,
         r##"
-            enum_map::enum_map! {{
+            }};
+            let bytes = decompress(compressed)
+                .unwrap_or_else(|| panic!("failed to decompress ambiguities for {{}}", name));
+            Some(postcard::from_bytes(&bytes)
+                .unwrap_or_else(|_| panic!("failed to load ambiguities for {{}}", name)))
+        }}
     "##
     )?;
-    for name in names {
-        writeln!(
-            out_file,
-            r##"
-                DictionaryKeys::{0} => postcard::from_bytes(&{0}_bytes).expect("failed to load dictionary {0}"),
-        "##,
-            name
-        )?;
-    }
+    Ok(())
+}
+
+/// Write out the main source file that will be included in the library.
+fn write_source(out_dir: &path::Path, names: &Vec<(String, bool)>) -> Result<()> {
+    let out_path = out_dir.join("dicts.rs");
+    let mut out_file = fs::File::create(out_path)?;
+
     writeln!(
         out_file,
         r##"
-            }}
-        }});
+        #[derive(PartialEq,Eq,Hash,Debug,Clone,Copy,enum_map::Enum)]
+        /// DictionaryKeys lists the available dictionary types
+        enum DictionaryKeys {{
     "##
     )?;
+    for (name, _) in names {
+        writeln!(out_file, "  {0},", name)?;
+    }
+    writeln!(out_file, "}}")?;
+    if !external_dicts() {
+        write_loaders(&mut out_file, names)?;
+    }
 
+    if !jieba_enabled() {
+        return Ok(());
+    }
     let jieba_dict_path =
         path::Path::new(env!("CARGO_MANIFEST_DIR")).join("jieba-rs/src/data/dict.txt");
     let mut jieba_dict_file = fs::File::open(jieba_dict_path)?;
     let mut jieba_dict = Vec::new();
     jieba_dict_file.read_to_end(&mut jieba_dict)?;
-    let jieba_dict_compressed = compress_to_vec(&jieba_dict, 6);
+    let jieba_dict_compressed = compress(&jieba_dict);
     let jieba_compressed_dict_path = out_dir.join("jieba.z");
     let mut jieba_compressed_dict_file = fs::File::create(jieba_compressed_dict_path)?;
     jieba_compressed_dict_file.write_all(&jieba_dict_compressed)?;
     writeln!(
         out_file,
         r##"
-            static JIEBA_DICT: once_cell::sync::Lazy<Vec<u8>> = once_cell::sync::Lazy::new(|| {{
-                decompress_to_vec(include_bytes!(concat!(env!("OUT_DIR"), "/jieba.z")))
+            /// Decompress the embedded jieba dictionary.
+            fn load_jieba_dict() -> Cow<'static, [u8]> {{
+                decompress(include_bytes!(concat!(env!("OUT_DIR"), "/jieba.z")))
                     .expect("failed to decompress jieba dictionary")
-            }});
+            }}
     "##
     )?;
 
@@ -232,16 +676,25 @@ fn write_source(out_dir: &path::Path, names: &Vec<String>) -> Result<()> {
 /// Build everything.
 fn build_all() -> Result<()> {
     let out_dir = fs::canonicalize(path::Path::new(&env::var("OUT_DIR")?))?;
-    let names = build_all_dicts(&out_dir)?;
+    let asset_dir = asset_dir(&out_dir)?;
+    let names = build_all_dicts(&out_dir, asset_dir.as_deref())?;
 
     write_source(&out_dir, &names)?;
     println!(
         "cargo::warning=Generated code written to {0}",
         out_dir.display()
     );
+    if let Some(asset_dir) = asset_dir {
+        println!(
+            "cargo::warning=Dictionary assets written to {0}",
+            asset_dir.display()
+        );
+    }
     Ok(())
 }
 
 fn main() {
+    #[cfg(feature = "napi")]
+    napi_build::setup();
     build_all().unwrap();
 }
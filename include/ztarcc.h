@@ -0,0 +1,39 @@
+#ifndef ZTARCC_H
+#define ZTARCC_H
+
+/* Generated by cbindgen; do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// The conversion succeeded.
+#define ZTARCC_OK 0
+
+// An argument was null, or was not valid UTF-8.
+#define ZTARCC_INVALID_ARGUMENT 1
+
+// The conversion failed, for example because a script name was unknown.
+#define ZTARCC_CONVERSION_FAILED 2
+
+// The library panicked; this is a bug.
+#define ZTARCC_PANIC 3
+
+// Convert `len` bytes of UTF-8 text at `input` between two scripts given by name (`"cn"`, `"tw"`,
+// `"hk"`, `"st"`, or a registered custom script).  On success, `*out` is set to a newly allocated
+// NUL-terminated UTF-8 string, which must be released with [ztarcc_free]; on failure, `*out` is
+// set to null.  Returns one of the `ZTARCC_` status codes.
+//
+// # Safety
+//
+// `from` and `to` must be valid NUL-terminated strings, `input` must point to `len` readable
+// bytes (it may be null if `len` is zero), and `out` must be valid for writes.
+int ztarcc_convert(const char *from, const char *to, const uint8_t *input, size_t len, char **out);
+
+// Release a string returned by [ztarcc_convert].  Passing null does nothing.
+//
+// # Safety
+//
+// `converted` must be null or a string returned by [ztarcc_convert] that has not yet been freed.
+void ztarcc_free(char *converted);
+
+#endif /* ZTARCC_H */
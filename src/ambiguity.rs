@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use anyhow::Result;
+
+use crate::{chain, Chain, Engine, Script, SpanSink};
+
+/// A phrase that the dictionaries could convert in more than one way, such as 发 becoming 發 or
+/// 髮; see [Engine::convert_with_ambiguities].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Ambiguity {
+    /// The byte range of the input the phrase came from.
+    pub span: Range<usize>,
+    /// The phrase as the dictionary matched it.  This is input text for the first dictionary of
+    /// a conversion, and partly converted text for the second.
+    pub phrase: String,
+    /// Every candidate, in dictionary order.
+    pub candidates: Vec<String>,
+    /// The candidate that was used, which is the first unless a [crate::CandidateRanker] chose
+    /// another.
+    pub chosen: String,
+}
+
+impl Engine {
+    /// Find the ambiguous entries used to convert the segment of the input at `span`, applying
+    /// each dictionary like [Engine::convert_word] while keeping track of which input bytes each
+    /// character came from.
+    fn segment_ambiguities(
+        &self,
+        keys: &Chain,
+        input: &str,
+        span: Range<usize>,
+        found: &mut Vec<Ambiguity>,
+    ) -> Result<()> {
+        let segment = &input[span.clone()];
+        let mut word = self.normalization.normalize(segment).into_owned();
+        // Normalization can compose characters, and then only the whole segment is known.
+        let mut sources: Vec<Range<usize>> = match word.chars().count() == segment.chars().count() {
+            true => segment
+                .char_indices()
+                .map(|(i, ch)| span.start + i..span.start + i + ch.len_utf8())
+                .collect(),
+            false => word.chars().map(|_| span.clone()).collect(),
+        };
+        for key in keys {
+            let dict = self.dictionary(*key)?;
+            let ambiguities = self.ambiguities(*key)?;
+            let mut next = String::with_capacity(word.len());
+            let mut next_sources = Vec::with_capacity(sources.len());
+            let mut index = 0;
+            for piece in dict.pieces(&word) {
+                let count = piece.text.chars().count();
+                let source = sources[index].start..sources[index + count - 1].end;
+                index += count;
+                let Some(value) = piece.replacement else {
+                    next.push_str(piece.text);
+                    next_sources.push(source);
+                    continue;
+                };
+                let mut replacement = value;
+                if let Some(candidates) = ambiguities.get(piece.text) {
+                    if let Some(ranker) = &self.ranker {
+                        replacement = candidates
+                            .get(ranker.rank(piece.text, candidates, &word))
+                            .map_or(value, String::as_str);
+                    }
+                    found.push(Ambiguity {
+                        span: source.clone(),
+                        phrase: piece.text.to_owned(),
+                        candidates: candidates.clone(),
+                        chosen: replacement.to_owned(),
+                    });
+                }
+                next.push_str(replacement);
+                next_sources.extend(replacement.chars().map(|_| source.clone()));
+            }
+            word = next;
+            sources = next_sources;
+        }
+        Ok(())
+    }
+
+    /// Convert a string from an input variant to an output variant like [Engine::convert], also
+    /// returning every place where a dictionary entry had several candidates, with the one that
+    /// was chosen, in input order.  These are the spots a human reviewer should check.
+    pub fn convert_with_ambiguities(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> Result<(Vec<String>, Vec<Ambiguity>)> {
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        let mut found = Vec::new();
+        if !self.guards(input) {
+            let keys = chain(from, to);
+            for span in &sink.spans {
+                self.segment_ambiguities(&keys, input, span.start..span.end, &mut found)?;
+            }
+            found.sort_by_key(|ambiguity| ambiguity.span.start);
+        }
+        let segments = sink.spans.into_iter().map(|span| span.converted).collect();
+        Ok((segments, found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrequencyRanker;
+
+    #[test]
+    fn test_convert_with_ambiguities() -> Result<()> {
+        let engine = Engine::new();
+        let (segments, found) = engine.convert_with_ambiguities(Script::CN, Script::TW, "他干")?;
+        assert_eq!("他幹", segments.join(""));
+        let ambiguity = &found[0];
+        assert_eq!(3..6, ambiguity.span);
+        assert_eq!("干", ambiguity.phrase);
+        assert_eq!("幹", ambiguity.chosen);
+        assert!(ambiguity.candidates.contains(&"乾".to_owned()));
+
+        let engine = engine.with_ranker(FrequencyRanker::from_counts([("乾", 10), ("幹", 1)]));
+        let (segments, found) = engine.convert_with_ambiguities(Script::CN, Script::TW, "他干")?;
+        assert_eq!("他乾", segments.join(""));
+        assert_eq!("乾", found[0].chosen);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+use bumpalo::Bump;
+
+use crate::{chain, Dictionary, Engine, Script};
+
+/// Apply a single dictionary to a word, like [crate::convert_pass], but build the result in
+/// `bump` instead of on the heap.
+fn convert_pass_in<'bump>(dict: &Dictionary, word: &str, bump: &'bump Bump) -> &'bump str {
+    let mut output = BumpString::with_capacity_in(word.len(), bump);
+    for piece in dict.pieces(word) {
+        output.push_str(piece.replacement.unwrap_or(piece.text));
+    }
+    output.into_bump_str()
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, allocating the converted
+    /// segments and intermediate results in `bump`.  Everything is freed at once when the arena is
+    /// reset or dropped, which avoids many small allocations in busy services.  Segments that are
+    /// not changed by a dictionary are borrowed from the input rather than copied.
+    pub fn convert_in<'bump>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &'bump str,
+        bump: &'bump Bump,
+    ) -> Result<BumpVec<'bump, &'bump str>> {
+        if self.guards(input) {
+            let mut segments = BumpVec::new_in(bump);
+            segments.push(input);
+            return Ok(segments);
+        }
+        let keys = chain(from, to);
+        let words = self.segments(input);
+        let mut segments = BumpVec::with_capacity_in(words.len(), bump);
+        for (word, copied) in words {
+            let mut converted = word;
+            if copied {
+                segments.push(converted);
+                continue;
+            }
+            if self.ranker.is_some() {
+                segments.push(bump.alloc_str(&self.convert_word(&keys, word)?));
+                continue;
+            }
+            for key in &keys {
+                converted = convert_pass_in(self.dictionary(*key)?, converted, bump);
+            }
+            segments.push(converted);
+        }
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_in() -> Result<()> {
+        let engine = Engine::new();
+        let bump = Bump::new();
+        let result =
+            engine.convert_in(Script::CN, Script::TW, "我能吞下玻璃而不伤身体。", &bump)?;
+        assert_eq!("我能吞下玻璃而不傷身體。", result.concat());
+
+        Ok(())
+    }
+}
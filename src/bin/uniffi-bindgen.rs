@@ -0,0 +1,6 @@
+/// Generate Swift and Kotlin bindings from the built library, as in
+/// `cargo run --features uniffi-bindgen --bin uniffi-bindgen -- generate --library
+/// target/release/libztarcc_rs.so --language kotlin --out-dir out`.
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,265 @@
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::errors::UsageError;
+
+/// Add every file under `dir.join(relative)` to `files`, by its path relative
+/// to `dir`, with its modification time.
+pub fn scan(dir: &Path, relative: &Path, files: &mut HashMap<PathBuf, SystemTime>) -> Result<()> {
+    let path = dir.join(relative);
+    for entry in fs::read_dir(&path).context(format!("reading {}", path.display()))? {
+        let entry = entry?;
+        let relative = relative.join(entry.file_name());
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            scan(dir, &relative, files)?;
+        } else if metadata.is_file() {
+            files.insert(relative, metadata.modified()?);
+        }
+    }
+    Ok(())
+}
+
+/// What converting one file of a batch did, for `--stats`.
+#[derive(Serialize, Debug, Default)]
+pub struct FileStats {
+    /// The input path.
+    pub path: String,
+    /// The size of the input, in bytes.
+    pub bytes: usize,
+    /// The number of characters in phrases that were changed.
+    pub changed_characters: usize,
+    /// Whether the output is the same as the input.
+    pub unchanged: bool,
+    /// Whether the file was copied without converting it, as binary files are.
+    pub skipped: bool,
+    /// Why the file failed to convert, if it did.
+    pub error: Option<String>,
+}
+
+/// The statistics for a whole batch, as written with `--stats-file`.
+#[derive(Serialize, Debug, Default)]
+pub struct BatchStats {
+    pub files: usize,
+    pub converted: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub bytes: usize,
+    pub changed_characters: usize,
+    /// Every file, in order.
+    pub file_stats: Vec<FileStats>,
+}
+
+impl BatchStats {
+    /// Add up the statistics of each file.
+    fn new(file_stats: Vec<FileStats>) -> Self {
+        let mut stats = BatchStats::default();
+        for file in &file_stats {
+            stats.files += 1;
+            stats.bytes += file.bytes;
+            stats.changed_characters += file.changed_characters;
+            if file.error.is_some() {
+                stats.failed += 1;
+            } else if file.skipped {
+                stats.skipped += 1;
+            } else if file.unchanged {
+                stats.unchanged += 1;
+            } else {
+                stats.converted += 1;
+            }
+        }
+        stats.file_stats = file_stats;
+        stats
+    }
+
+    /// Report the statistics of each file and the totals on standard error.
+    fn report(&self) {
+        for file in &self.file_stats {
+            let outcome = if file.error.is_some() {
+                "failed".to_owned()
+            } else if file.skipped {
+                "skipped".to_owned()
+            } else if file.unchanged {
+                "unchanged".to_owned()
+            } else {
+                format!("{} characters changed", file.changed_characters)
+            };
+            eprintln!("{}: {} bytes, {}", file.path, file.bytes, outcome);
+        }
+        eprintln!(
+            "{} files: {} converted, {} unchanged, {} skipped, {} failed; {} bytes, {} characters changed",
+            self.files,
+            self.converted,
+            self.unchanged,
+            self.skipped,
+            self.failed,
+            self.bytes,
+            self.changed_characters
+        );
+    }
+}
+
+/// Converts a file, given its relative path, returning the output and the
+/// number of characters in the phrases that were changed, or `None` for a
+/// file that is copied without converting it.
+pub trait ConvertFile: Fn(&Path, &[u8]) -> Result<(Vec<u8>, Option<usize>)> + Sync {}
+
+impl<F: Fn(&Path, &[u8]) -> Result<(Vec<u8>, Option<usize>)> + Sync> ConvertFile for F {}
+
+/// Convert one file from `dir` to the same relative path under `out`,
+/// returning the input, whether the output is the same, and the number of
+/// characters changed as given by `convert`.  A file that needs no
+/// conversion is copied as it is if `copy_unchanged` is set, and otherwise
+/// not written.
+pub fn convert_one(
+    dir: &Path,
+    out: &Path,
+    relative: &Path,
+    copy_unchanged: bool,
+    convert: &impl ConvertFile,
+) -> Result<(Vec<u8>, bool, Option<usize>)> {
+    let input_path = dir.join(relative);
+    let input = fs::read(&input_path).context(format!("reading {}", input_path.display()))?;
+    let (output, changed) = convert(relative, &input)?;
+    let unchanged = output == input;
+    if unchanged && !copy_unchanged {
+        return Ok((input, unchanged, changed));
+    }
+    let output_path = out.join(relative);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).context(format!("creating {}", parent.display()))?;
+    }
+    if unchanged {
+        fs::copy(&input_path, &output_path)
+            .context(format!("copying to {}", output_path.display()))?;
+    } else {
+        fs::write(&output_path, output).context(format!("writing {}", output_path.display()))?;
+    }
+    Ok((input, unchanged, changed))
+}
+
+/// Convert one file of a batch, collecting its statistics.
+fn convert_counted(
+    dir: &Path,
+    out: &Path,
+    relative: &Path,
+    copy_unchanged: bool,
+    convert: &impl ConvertFile,
+) -> Result<FileStats> {
+    let (input, unchanged, changed) = convert_one(dir, out, relative, copy_unchanged, convert)?;
+    Ok(FileStats {
+        path: dir.join(relative).to_string_lossy().into_owned(),
+        bytes: input.len(),
+        changed_characters: changed.unwrap_or(0),
+        unchanged,
+        skipped: changed.is_none(),
+        error: None,
+    })
+}
+
+/// Convert the given files, and every file under the given directories,
+/// into `out` on all cores.  A file is written under its own name, and a
+/// directory's files under their paths relative to it, except that files
+/// that need no conversion are only copied if `copy_unchanged` is set.
+/// Every file is attempted; the failures are reported on standard error at the end, and
+/// make the whole batch fail.  With `stats`, the statistics of each file and
+/// the totals are reported too, and written as JSON to `stats_file` if
+/// given.  Inputs that would be written to the same output, or an `out`
+/// inside one of the directories, are a usage error before anything is
+/// converted.
+pub fn batch(
+    paths: &[PathBuf],
+    out: &Path,
+    copy_unchanged: bool,
+    convert: impl ConvertFile,
+    stats: bool,
+    stats_file: Option<&Path>,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found = HashMap::new();
+            scan(path, Path::new(""), &mut found)?;
+            files.extend(found.into_keys().map(|relative| (path.as_path(), relative)));
+        } else {
+            let name = path
+                .file_name()
+                .ok_or(anyhow!(format!("{} is not a file", path.display())))?;
+            let dir = path.parent().unwrap_or(Path::new(""));
+            files.push((dir, PathBuf::from(name)));
+        }
+    }
+    files.sort();
+    let mut outputs: HashMap<&Path, &Path> = HashMap::new();
+    for (dir, relative) in &files {
+        if let Some(other) = outputs.insert(relative, dir) {
+            return Err(UsageError(format!(
+                "{} and {} would both be written to {}",
+                other.join(relative).display(),
+                dir.join(relative).display(),
+                out.join(relative).display()
+            ))
+            .into());
+        }
+    }
+    fs::create_dir_all(out).context(format!("creating {}", out.display()))?;
+    let canonical_out = fs::canonicalize(out)?;
+    for path in paths.iter().filter(|path| path.is_dir()) {
+        if canonical_out.starts_with(fs::canonicalize(path)?) {
+            return Err(UsageError(format!(
+                "the --out directory cannot be inside {}",
+                path.display()
+            ))
+            .into());
+        }
+    }
+    let results: Vec<_> = files
+        .par_iter()
+        .map(|(dir, relative)| {
+            convert_counted(dir, out, relative, copy_unchanged, &convert)
+                .map_err(|error| (dir.join(relative), error))
+        })
+        .collect();
+    let mut file_stats = Vec::new();
+    let mut failures = Vec::new();
+    for result in results {
+        match result {
+            Ok(stats) => file_stats.push(stats),
+            Err((path, error)) => {
+                eprintln!("{}: {:#}", path.display(), error);
+                file_stats.push(FileStats {
+                    path: path.to_string_lossy().into_owned(),
+                    error: Some(format!("{:#}", error)),
+                    ..FileStats::default()
+                });
+                failures.push(path);
+            }
+        }
+    }
+    eprintln!(
+        "converted {} of {} files",
+        files.len() - failures.len(),
+        files.len()
+    );
+    if stats {
+        let stats = BatchStats::new(file_stats);
+        stats.report();
+        if let Some(stats_file) = stats_file {
+            let json = serde_json::to_string_pretty(&stats)?;
+            fs::write(stats_file, json + "\n")
+                .context(format!("writing {}", stats_file.display()))?;
+        }
+    }
+    match failures.len() {
+        0 => Ok(()),
+        failed => Err(anyhow!(format!("{} files failed to convert", failed))),
+    }
+}
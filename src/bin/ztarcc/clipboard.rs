@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+
+/// Convert the text in the system clipboard, replacing it with the result.
+pub fn convert_clipboard(convert: impl Fn(&str) -> Result<String>) -> Result<()> {
+    let mut clipboard = Clipboard::new().context("opening the clipboard")?;
+    let text = clipboard.get_text().context("reading the clipboard")?;
+    let converted = convert(&text)?;
+    set_text(&mut clipboard, converted).context("writing the clipboard")
+}
+
+/// Put text in the clipboard.
+#[cfg(not(target_os = "linux"))]
+fn set_text(clipboard: &mut Clipboard, text: String) -> Result<(), arboard::Error> {
+    clipboard.set_text(text)
+}
+
+/// Put text in the clipboard.  On X11 and Wayland, the clipboard is served by
+/// the program that set it, so wait for a clipboard manager (or another
+/// program) to take it over before exiting, or the text would be lost.
+#[cfg(target_os = "linux")]
+fn set_text(clipboard: &mut Clipboard, text: String) -> Result<(), arboard::Error> {
+    use arboard::SetExtLinux;
+    clipboard.set().wait().text(text)
+}
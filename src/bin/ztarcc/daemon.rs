@@ -0,0 +1,185 @@
+use std::env;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+pub use socket::{convert, daemon};
+
+/// The socket to use when none is given: `ztarcc.sock` in `$XDG_RUNTIME_DIR`, which only its
+/// user can reach, or else in the temporary directory, named after the user.
+pub fn default_socket() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("ztarcc.sock"),
+        None => {
+            let user = env::var("USER").unwrap_or_default();
+            env::temp_dir().join(format!("ztarcc-{}.sock", user))
+        }
+    }
+}
+
+#[cfg(unix)]
+mod socket {
+    use anyhow::{anyhow, Context, Result};
+    use serde::{Deserialize, Serialize};
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::Shutdown;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use ztarcc_rs::Engine;
+
+    /// A request to the daemon, sent as one line of JSON.
+    #[derive(Serialize, Deserialize, Debug)]
+    struct DaemonRequest {
+        /// The input script, built-in or custom.
+        from: String,
+        /// The output script, built-in or custom.
+        to: String,
+        /// The text to convert.
+        text: String,
+    }
+
+    /// The daemon's response to a request, sent as one line of JSON: `{"text": ...}` with the
+    /// converted text, or `{"error": ...}`.
+    #[derive(Serialize, Deserialize, Debug)]
+    #[serde(rename_all = "lowercase")]
+    enum DaemonResponse {
+        Text(String),
+        Error(String),
+    }
+
+    /// Convert the text of one line of request.
+    fn respond(engine: &Engine, line: &str) -> DaemonResponse {
+        let result = serde_json::from_str::<DaemonRequest>(line)
+            .map_err(anyhow::Error::from)
+            .and_then(|request| engine.convert_named(&request.from, &request.to, &request.text));
+        match result {
+            Ok(segments) => DaemonResponse::Text(segments.concat()),
+            Err(error) => DaemonResponse::Error(format!("{:#}", error)),
+        }
+    }
+
+    /// Answer each line of request on a connection until the client closes it.
+    fn handle(engine: &Engine, stream: UnixStream) -> Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = serde_json::to_string(&respond(engine, &line?))?;
+            writeln!(writer, "{}", response)?;
+        }
+        Ok(())
+    }
+
+    /// Serve conversions on the Unix domain socket `socket` with one engine, whose dictionaries
+    /// stay loaded between requests, until interrupted.  Each line a client sends is a JSON
+    /// request with `from`, `to`, and `text`, answered with a line of JSON with the converted
+    /// `text` or an `error`.  A stale socket left by a daemon that did not exit cleanly is
+    /// replaced, and each connection is answered on a thread of its own.
+    pub fn daemon(engine: &Engine, socket: &Path) -> Result<()> {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(anyhow!(format!(
+                "a daemon is already listening on {}",
+                socket.display()
+            )));
+        }
+        if fs::symlink_metadata(socket).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            fs::remove_file(socket).context(format!("removing {}", socket.display()))?;
+        }
+        let listener =
+            UnixListener::bind(socket).context(format!("listening on {}", socket.display()))?;
+        eprintln!("listening on {}", socket.display());
+        std::thread::scope(|scope| {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        scope.spawn(move || {
+                            if let Err(error) = handle(engine, stream) {
+                                eprintln!("ztarcc: {:#}", error);
+                            }
+                        });
+                    }
+                    Err(error) => eprintln!("ztarcc: {}", error),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Convert text with the daemon listening on `socket`, instead of loading an engine.
+    pub fn convert(socket: &Path, from: &str, to: &str, text: &str) -> Result<String> {
+        let mut stream = UnixStream::connect(socket).context(format!(
+            "connecting to {}; start the daemon with \"ztarcc daemon\"",
+            socket.display()
+        ))?;
+        let request = DaemonRequest {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            text: text.to_owned(),
+        };
+        writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+        stream.shutdown(Shutdown::Write)?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        match serde_json::from_str(&line).context("reading the daemon's response")? {
+            DaemonResponse::Text(text) => Ok(text),
+            DaemonResponse::Error(error) => Err(anyhow!(error)),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::time::Duration;
+        use ztarcc_rs::CustomScript;
+
+        #[test]
+        fn test_daemon() -> Result<()> {
+            let socket =
+                std::env::temp_dir().join(format!("ztarcc-daemon-{}.sock", std::process::id()));
+            // A socket left by a daemon that did not exit cleanly.
+            drop(UnixListener::bind(&socket)?);
+            let engine: &Engine = Box::leak(Box::new(Engine::new()));
+            engine.register_script(CustomScript::from_definition(
+                "name sg\nbase cn\noverride 出租车 德士\n",
+            )?)?;
+            let path = socket.clone();
+            std::thread::spawn(move || daemon(engine, &path));
+            let mut converted = convert(&socket, "cn", "sg", "坐出租车");
+            for _ in 0..100 {
+                if converted.is_ok() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+                converted = convert(&socket, "cn", "sg", "坐出租车");
+            }
+            let unknown = convert(&socket, "cn", "xx", "坐出租车");
+            let again = daemon(engine, &socket);
+            let _ = fs::remove_file(&socket);
+
+            assert_eq!("坐德士", converted?);
+            assert!(unknown.is_err());
+            assert!(again.is_err());
+            Ok(())
+        }
+    }
+}
+
+/// The daemon needs Unix domain sockets, which this platform does not have.
+#[cfg(not(unix))]
+pub fn daemon(_engine: &ztarcc_rs::Engine, _socket: &std::path::Path) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "the daemon needs Unix domain sockets, which this platform does not have"
+    ))
+}
+
+/// The daemon needs Unix domain sockets, which this platform does not have.
+#[cfg(not(unix))]
+pub fn convert(
+    _socket: &std::path::Path,
+    _from: &str,
+    _to: &str,
+    _text: &str,
+) -> anyhow::Result<String> {
+    Err(anyhow::anyhow!(
+        "the daemon needs Unix domain sockets, which this platform does not have"
+    ))
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+
+/// Find the end of a quoted field's content in `rest` (which starts just after the opening
+/// quote), treating a doubled quote as an escaped one.  Returns `rest.len()` if it is unclosed.
+fn quoted_end(rest: &str) -> usize {
+    let mut offset = 0;
+    while let Some(i) = rest[offset..].find('"') {
+        let quote = offset + i;
+        if rest[quote + 1..].starts_with('"') {
+            offset = quote + 2;
+        } else {
+            return quote;
+        }
+    }
+    rest.len()
+}
+
+/// Convert the selected columns of a CSV or TSV file.  `columns` lists the 1-based columns to
+/// convert; if it is empty, every column is converted.  Quoted fields (which may contain
+/// delimiters and line breaks) have their content converted between the quotes; delimiters,
+/// quotes, escaped quotes, and line endings are copied verbatim.
+pub fn convert_delimited(
+    input: &str,
+    delimiter: char,
+    columns: &[usize],
+    convert: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let selected = |column: usize| columns.is_empty() || columns.contains(&column);
+    let mut column = 1;
+    let mut offset = 0;
+    while offset < input.len() {
+        let rest = &input[offset..];
+        // Copy the field, converting its content if the column is selected.
+        let field_end = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted_end(quoted);
+                output.push('"');
+                match selected(column) {
+                    true => output.push_str(&convert(&quoted[..end])?),
+                    false => output.push_str(&quoted[..end]),
+                }
+                // Copy the closing quote, and anything malformed between it and the delimiter.
+                let after = 1 + end;
+                let trailing = rest[after..]
+                    .find([delimiter, '\r', '\n'])
+                    .map_or(rest.len(), |i| after + i);
+                output.push_str(&rest[after..trailing]);
+                trailing
+            }
+            None => {
+                let end = rest.find([delimiter, '\r', '\n']).unwrap_or(rest.len());
+                match selected(column) {
+                    true => output.push_str(&convert(&rest[..end])?),
+                    false => output.push_str(&rest[..end]),
+                }
+                end
+            }
+        };
+        offset += field_end;
+        // Copy the separator after the field, moving to the next column or record.
+        let Some(separator) = input[offset..].chars().next() else {
+            break;
+        };
+        output.push(separator);
+        offset += separator.len_utf8();
+        if separator == delimiter {
+            column += 1;
+        } else if separator == '\r' && input[offset..].starts_with('\n') {
+            output.push('\n');
+            offset += 1;
+            column = 1;
+        } else {
+            column = 1;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(text: &str) -> Result<String> {
+        Ok(text.replace("软件", "軟體"))
+    }
+
+    #[test]
+    fn test_convert_delimited() -> Result<()> {
+        let csv = "id,软件,软件\r\n1,\"软件,\"\"软件\"\"\n软件\",软件\r\n2,软件,\"软件\"\n";
+        assert_eq!(
+            "id,軟體,软件\r\n1,\"軟體,\"\"軟體\"\"\n軟體\",软件\r\n2,軟體,\"软件\"\n",
+            convert_delimited(csv, ',', &[2], convert)?
+        );
+        assert_eq!(
+            "id,軟體,軟體\r\n1,\"軟體,\"\"軟體\"\"\n軟體\",軟體\r\n2,軟體,\"軟體\"\n",
+            convert_delimited(csv, ',', &[], convert)?
+        );
+        let tsv = "软件\t软件,软件\n软件\t\"软件\t软件\"";
+        assert_eq!(
+            "软件\t軟體,軟體\n软件\t\"軟體\t軟體\"",
+            convert_delimited(tsv, '\t', &[2], convert)?
+        );
+
+        Ok(())
+    }
+}
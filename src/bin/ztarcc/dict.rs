@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use std::{fs, io::Write, path::Path};
+
+/// Write the entries of a dictionary whose phrases start with `prefix` in OpenCC's text format:
+/// each phrase, a tab, and its candidates separated by spaces, the default first.
+fn write_entries(
+    output: &mut impl Write,
+    engine: &ztarcc_rs::Engine,
+    name: &str,
+    prefix: &str,
+) -> Result<()> {
+    for entry in engine.entries_with_prefix(name, prefix)? {
+        writeln!(output, "{}\t{}", entry.phrase, entry.candidates.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Dump the named dictionaries as compiled into this build, including those reversed or
+/// composed at build time, keeping only the entries whose phrases start with `prefix`.  With
+/// `out_dir`, each is written to `NAME.txt` there; otherwise they are written to `output`, each
+/// after a `# NAME` line if there are several.
+pub fn dump(
+    output: &mut impl Write,
+    engine: &ztarcc_rs::Engine,
+    names: &[String],
+    prefix: &str,
+    out_dir: Option<&Path>,
+) -> Result<()> {
+    for (index, name) in names.iter().enumerate() {
+        if let Some(out_dir) = out_dir {
+            fs::create_dir_all(out_dir)?;
+            let path = out_dir.join(format!("{}.txt", name));
+            let mut file = Vec::new();
+            write_entries(&mut file, engine, name, prefix)?;
+            fs::write(&path, file).context(format!("writing {}", path.display()))?;
+            continue;
+        }
+        if names.len() > 1 {
+            if index > 0 {
+                writeln!(output)?;
+            }
+            writeln!(output, "# {}", name)?;
+        }
+        write_entries(output, engine, name, prefix)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use std::io::Write;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// Write a line of a hunk with its prefix, marking a missing final line break the way diff does.
+fn write_line(output: &mut dyn Write, prefix: char, line: &str) -> Result<()> {
+    write!(output, "{}{}", prefix, line)?;
+    if !line.ends_with('\n') {
+        writeln!(output, "\n\\ No newline at end of file")?;
+    }
+    Ok(())
+}
+
+/// The range of a hunk in a hunk header: the 1-based first line and the number of lines, or the
+/// line before the hunk if it has no lines.
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Write the lines `start..end` as a hunk, with the removed lines of each change before the
+/// added ones.
+fn write_hunk(
+    output: &mut dyn Write,
+    old_lines: &[&str],
+    new_lines: &[&str],
+    start: usize,
+    end: usize,
+) -> Result<()> {
+    let count = |lines: &[&str]| lines.len().min(end).saturating_sub(start);
+    writeln!(
+        output,
+        "@@ -{} +{} @@",
+        hunk_range(start, count(old_lines)),
+        hunk_range(start, count(new_lines))
+    )?;
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    for index in start..end {
+        let (old, new) = (old_lines.get(index), new_lines.get(index));
+        if old.is_some() && old == new {
+            for line in removed.drain(..) {
+                write_line(output, '-', line)?;
+            }
+            for line in added.drain(..) {
+                write_line(output, '+', line)?;
+            }
+            write_line(output, ' ', old_lines[index])?;
+        } else {
+            removed.extend(old);
+            added.extend(new);
+        }
+    }
+    for line in removed {
+        write_line(output, '-', line)?;
+    }
+    for line in added {
+        write_line(output, '+', line)?;
+    }
+    Ok(())
+}
+
+/// Write a unified diff from the input to the converted text, labelled with their paths, or
+/// nothing if they are the same.  Conversion keeps line breaks where they are, so the lines are
+/// compared in pairs rather than searched for.
+pub fn write_unified_diff(
+    output: &mut dyn Write,
+    old_label: &str,
+    new_label: &str,
+    old: &str,
+    new: &str,
+) -> Result<()> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let len = old_lines.len().max(new_lines.len());
+    let changed: Vec<usize> = (0..len)
+        .filter(|&index| old_lines.get(index) != new_lines.get(index))
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    writeln!(output, "--- {}", old_label)?;
+    writeln!(output, "+++ {}", new_label)?;
+    // Changes whose context would overlap share a hunk.
+    let mut next = 0;
+    while next < changed.len() {
+        let start = changed[next].saturating_sub(CONTEXT);
+        let mut last = changed[next];
+        next += 1;
+        while next < changed.len() && changed[next] - last <= 2 * CONTEXT + 1 {
+            last = changed[next];
+            next += 1;
+        }
+        write_hunk(
+            output,
+            &old_lines,
+            &new_lines,
+            start,
+            (last + CONTEXT + 1).min(len),
+        )?;
+    }
+    Ok(())
+}
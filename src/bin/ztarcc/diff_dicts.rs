@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Context, Result};
+use std::{collections::BTreeMap, fs, io::Write, path::Path};
+
+/// The OpenCC dictionaries the built-in conversions are made from, and the conversions each one
+/// affects; `*` stands for any script.
+pub const SOURCES: [(&str, &[&str]); 11] = [
+    ("STCharacters", &["cn→*"]),
+    ("STPhrases", &["cn→*"]),
+    ("TSCharacters", &["*→cn"]),
+    ("TSPhrases", &["*→cn"]),
+    ("TWVariants", &["tw→*", "*→tw"]),
+    ("TWVariantsRevPhrases", &["tw→*"]),
+    ("TWPhrasesIT", &["tw→*", "*→tw"]),
+    ("TWPhrasesName", &["tw→*", "*→tw"]),
+    ("TWPhrasesOther", &["tw→*", "*→tw"]),
+    ("HKVariants", &["hk→*", "*→hk"]),
+    ("HKVariantsRevPhrases", &["hk→*"]),
+];
+
+/// A dictionary in OpenCC's text format, mapping each phrase to its candidates.
+type Entries = BTreeMap<String, String>;
+
+/// Read a dictionary in OpenCC's text format, keeping the candidates of each entry as written.
+fn read_entries(path: &Path) -> Result<Entries> {
+    let text =
+        fs::read_to_string(path).context(format!("reading dictionary {}", path.display()))?;
+    let mut entries = Entries::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let (from, to) = line.split_once('\t').ok_or(anyhow!(format!(
+            "no tab on line {} of {}",
+            index + 1,
+            path.display()
+        )))?;
+        entries.insert(from.to_owned(), to.trim().to_owned());
+    }
+    Ok(entries)
+}
+
+/// The dictionaries to compare, by name: every `.txt` file in a directory, or a single file.
+fn dictionary_files(source: &Path) -> Result<BTreeMap<String, std::path::PathBuf>> {
+    let mut files = BTreeMap::new();
+    if source.is_dir() {
+        for entry in fs::read_dir(source).context(format!("reading {}", source.display()))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "txt") {
+                if let Some(stem) = path.file_stem() {
+                    files.insert(stem.to_string_lossy().into_owned(), path);
+                }
+            }
+        }
+    } else {
+        let stem = source
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        files.insert(stem, source.to_owned());
+    }
+    Ok(files)
+}
+
+/// The conversions a dictionary affects, for the report.
+fn affected(name: &str) -> String {
+    match SOURCES.iter().find(|(source, _)| *source == name) {
+        Some((_, conversions)) => conversions.join(", "),
+        None => "no built-in conversion".to_owned(),
+    }
+}
+
+/// Compare two dictionary sources, each a directory of OpenCC text dictionaries (such as
+/// `opencc/data/dictionary`) or a single dictionary file, and write the added, removed, and
+/// changed mappings of each dictionary with the conversions they affect.  Two single files are
+/// compared with each other whatever their names.
+pub fn diff_dicts(output: &mut dyn Write, old: &Path, new: &Path) -> Result<()> {
+    let mut old_files = dictionary_files(old)?;
+    let mut new_files = dictionary_files(new)?;
+    if !old.is_dir() && !new.is_dir() {
+        let name = new_files.keys().next().cloned().unwrap_or_default();
+        old_files = old_files
+            .into_values()
+            .map(|path| (name.clone(), path))
+            .collect();
+        new_files = new_files
+            .into_values()
+            .map(|path| (name.clone(), path))
+            .collect();
+    }
+    let mut names: Vec<&String> = old_files.keys().chain(new_files.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let old_entries = match old_files.get(name) {
+            Some(path) => read_entries(path)?,
+            None => Entries::new(),
+        };
+        let new_entries = match new_files.get(name) {
+            Some(path) => read_entries(path)?,
+            None => Entries::new(),
+        };
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        let mut lines = Vec::new();
+        for (from, old_to) in &old_entries {
+            match new_entries.get(from) {
+                None => {
+                    removed += 1;
+                    lines.push(format!("- {}\t{}", from, old_to));
+                }
+                Some(new_to) if new_to != old_to => {
+                    changed += 1;
+                    lines.push(format!("~ {}\t{} → {}", from, old_to, new_to));
+                }
+                Some(_) => {}
+            }
+        }
+        for (from, new_to) in &new_entries {
+            if !old_entries.contains_key(from) {
+                added += 1;
+                lines.push(format!("+ {}\t{}", from, new_to));
+            }
+        }
+        if lines.is_empty() {
+            continue;
+        }
+        writeln!(
+            output,
+            "{}: {} added, {} removed, {} changed (affects {})",
+            name,
+            added,
+            removed,
+            changed,
+            affected(name)
+        )?;
+        for line in lines {
+            writeln!(output, "{}", line)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use std::io::{Cursor, Read, Write};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::resume::{convert_entry, Resume};
+
+/// The human-readable metadata in an EPUB package document.
+const METADATA: [&str; 6] = [
+    "dc:title",
+    "dc:creator",
+    "dc:contributor",
+    "dc:publisher",
+    "dc:description",
+    "dc:subject",
+];
+
+/// Convert the content of each element called `name` in an XML document,
+/// such as `dc:title`.  The elements must contain only text.
+pub fn convert_elements(
+    xml: &str,
+    name: &str,
+    convert: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut output = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after_name = start + open.len();
+        // Skip elements whose names only start with `name`.
+        let is_element =
+            rest[after_name..].starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace());
+        let Some(tag_len) = rest[after_name..].find('>').filter(|_| is_element) else {
+            output.push_str(&rest[..after_name]);
+            rest = &rest[after_name..];
+            continue;
+        };
+        let content_start = after_name + tag_len + 1;
+        let empty = rest[..content_start].ends_with("/>");
+        let Some(content_len) = rest[content_start..].find(&close).filter(|_| !empty) else {
+            output.push_str(&rest[..content_start]);
+            rest = &rest[content_start..];
+            continue;
+        };
+        output.push_str(&rest[..content_start]);
+        output.push_str(&convert(&rest[content_start..content_start + content_len])?);
+        rest = &rest[content_start + content_len..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Convert an EPUB: its XHTML documents (including the navigation document)
+/// as HTML, the title, creator, and other human-readable metadata of the
+/// package document, and the labels of the NCX table of contents.  The
+/// package's `dc:language` is set to `language`.  Everything else is copied
+/// as it is, and `mimetype` is written first and uncompressed, as EPUB
+/// requires.  With `resume`, converted entries are kept and reused.
+pub fn convert_epub(
+    input: &[u8],
+    language: &str,
+    mut resume: Option<&mut Resume>,
+    convert_html: impl Fn(&str) -> Result<String>,
+    convert_text: impl Fn(&str) -> Result<String>,
+) -> Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(input)).context("reading EPUB")?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    writer.write_all(b"application/epub+zip")?;
+    for index in 0..archive.len() {
+        let name = archive.by_index_raw(index)?.name().to_owned();
+        let extension = name.rsplit_once('.').map(|(_, extension)| extension);
+        let extension = extension.unwrap_or_default().to_ascii_lowercase();
+        if name == "mimetype" {
+            continue;
+        }
+        if !matches!(extension.as_str(), "xhtml" | "html" | "htm" | "opf" | "ncx") {
+            writer.raw_copy_file(archive.by_index_raw(index)?)?;
+            continue;
+        }
+        let crc32 = archive.by_index_raw(index)?.crc32();
+        let converted = convert_entry(resume.as_deref_mut(), &name, crc32, || {
+            let mut text = String::new();
+            archive
+                .by_index(index)?
+                .read_to_string(&mut text)
+                .context(format!("reading {}", name))?;
+            Ok(match extension.as_str() {
+                "opf" => {
+                    let mut converted = text;
+                    for element in METADATA {
+                        converted = convert_elements(&converted, element, &convert_text)?;
+                    }
+                    convert_elements(&converted, "dc:language", |_| Ok(language.to_owned()))?
+                }
+                "ncx" => convert_elements(&text, "text", &convert_text)?,
+                _ => convert_html(&text)?,
+            })
+        })?;
+        writer.start_file(name.as_str(), SimpleFileOptions::default())?;
+        writer.write_all(&converted)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(text: &str) -> Result<String> {
+        Ok(text.replace("软件", "軟體"))
+    }
+
+    fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<String> {
+        let mut text = String::new();
+        archive.by_name(name)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn test_convert_elements() -> Result<()> {
+        let xml = "<dc:title id=\"t\">软件</dc:title><dc:titleX>软件</dc:titleX>\
+                   <dc:title/><dc:title\n>软件</dc:title>";
+        assert_eq!(
+            "<dc:title id=\"t\">軟體</dc:title><dc:titleX>软件</dc:titleX>\
+             <dc:title/><dc:title\n>軟體</dc:title>",
+            convert_elements(xml, "dc:title", convert)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_epub() -> Result<()> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let deflated = SimpleFileOptions::default();
+        writer.start_file("content.opf", deflated)?;
+        writer.write_all(
+            "<metadata><dc:title>软件</dc:title><dc:language>zh-CN</dc:language></metadata>"
+                .as_bytes(),
+        )?;
+        writer.start_file("mimetype", deflated)?;
+        writer.write_all(b"application/epub+zip")?;
+        writer.start_file("text/one.xhtml", deflated)?;
+        writer.write_all("<p>软件</p>".as_bytes())?;
+        writer.start_file("cover.png", deflated)?;
+        writer.write_all("软件".as_bytes())?;
+        let input = writer.finish()?.into_inner();
+
+        let output = convert_epub(&input, "zh-TW", None, convert, convert)?;
+        let mut archive = ZipArchive::new(Cursor::new(output))?;
+        let mimetype = archive.by_index(0)?;
+        assert_eq!(
+            ("mimetype", CompressionMethod::Stored),
+            (mimetype.name(), mimetype.compression())
+        );
+        drop(mimetype);
+        // The mimetype entry is written once.
+        assert_eq!(4, archive.len());
+        assert_eq!(
+            "<metadata><dc:title>軟體</dc:title><dc:language>zh-TW</dc:language></metadata>",
+            read_entry(&mut archive, "content.opf")?
+        );
+        assert_eq!("<p>軟體</p>", read_entry(&mut archive, "text/one.xhtml")?);
+        assert_eq!("软件", read_entry(&mut archive, "cover.png")?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fmt, io};
+
+/// The exit code for a failed conversion, or any other error.
+const EXIT_FAILURE: i32 = 1;
+/// The exit code for bad arguments, as clap uses.
+const EXIT_USAGE: i32 = 2;
+/// The exit code for an error reading or writing a file.
+const EXIT_IO: i32 = 3;
+/// The exit code for input that could not be decoded, or output that could not be encoded.
+const EXIT_ENCODING: i32 = 4;
+
+/// Arguments that clap accepts but that cannot be used together.
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Input that could not be decoded, or output that could not be encoded.
+#[derive(Debug)]
+pub struct EncodingError(pub String);
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Whether an error is only that the reader of the output has gone away, as
+/// in `ztarcc big.txt | head`, which is not worth reporting.
+pub fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// The exit code for an error, by the kind of error it is or was caused by.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    for cause in error.chain() {
+        if cause.is::<UsageError>() {
+            return EXIT_USAGE;
+        } else if cause.is::<EncodingError>() {
+            return EXIT_ENCODING;
+        } else if cause.is::<io::Error>() {
+            return EXIT_IO;
+        }
+    }
+    EXIT_FAILURE
+}
@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Context, Result};
+use std::{fs, io::Write, path::Path};
+use ztarcc_rs::{
+    eval::{EvalReport, LineMismatch},
+    Engine, Script,
+};
+
+/// Replace the XML entities in TMX text with the characters they stand for.
+fn unescape_xml(text: &str) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or(anyhow!(format!("unterminated entity in {:?}", text)))?;
+        let entity = &rest[start + 1..start + end];
+        let ch = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|decimal| decimal.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        output.push(ch.ok_or(anyhow!(format!("unknown entity &{};", entity)))?);
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Remove inline markup (such as `<bpt>` or `<ph>`) from a TMX segment.
+fn strip_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find('>')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Read the pairs of segments from a TMX file.  In each translation unit, the first variant is
+/// the input and the second is the expected output.
+fn read_tmx(text: &str) -> Result<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    for (index, unit) in text.split("<tu").skip(1).enumerate() {
+        let unit = unit.split("</tu>").next().unwrap_or(unit);
+        let segments = unit
+            .split("<seg")
+            .skip(1)
+            .map(|segment| {
+                let content = segment.split_once('>').map_or("", |(_, content)| content);
+                let content = content.split("</seg>").next().unwrap_or(content);
+                unescape_xml(&strip_tags(content))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match segments.as_slice() {
+            [input, expected, ..] => pairs.push((input.to_owned(), expected.to_owned())),
+            _ => {
+                return Err(anyhow!(format!(
+                    "translation unit {} has fewer than two segments",
+                    index + 1
+                )))
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Read the pairs of lines from a tab-separated file of input and expected output.
+fn read_tsv(text: &str) -> Result<Vec<(String, String)>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| {
+            line.split_once('\t')
+                .map(|(input, expected)| (input.to_owned(), expected.to_owned()))
+                .ok_or(anyhow!(format!("no tab on line {}", index + 1)))
+        })
+        .collect()
+}
+
+/// Evaluate a corpus: either a directory of `.in`/`.ans` pairs, a TMX file, or a tab-separated
+/// file.
+pub fn evaluate(engine: &Engine, from: Script, to: Script, corpus: &Path) -> Result<EvalReport> {
+    if corpus.is_dir() {
+        return engine.evaluate_dir(from, to, corpus);
+    }
+    let text =
+        fs::read_to_string(corpus).context(format!("reading corpus {}", corpus.display()))?;
+    let is_tmx = corpus
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tmx"));
+    let pairs = if is_tmx {
+        read_tmx(&text)?
+    } else {
+        read_tsv(&text)?
+    };
+    // The report works on whole lines, so line breaks within a segment cannot be kept.
+    let flatten = |text: &str| text.replace(['\r', '\n'], " ");
+    let (input, expected): (Vec<_>, Vec<_>) = pairs
+        .iter()
+        .map(|(input, expected)| (flatten(input), flatten(expected)))
+        .unzip();
+    let name = corpus
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    let file = engine.evaluate_text(from, to, &name, &input.join("\n"), &expected.join("\n"))?;
+    Ok(EvalReport::from_files(vec![file]))
+}
+
+/// Write a human-readable summary of the report, including the `worst` most divergent lines and
+/// the most frequent mismatched phrases.
+pub fn write_report(output: &mut dyn Write, report: &EvalReport, worst: usize) -> Result<()> {
+    for file in &report.files {
+        writeln!(
+            output,
+            "{}: {}/{} lines exact ({:.2}%), character error rate {:.2}%",
+            file.name,
+            file.exact_lines,
+            file.lines,
+            file.line_accuracy() * 100.0,
+            file.character_error_rate() * 100.0
+        )?;
+    }
+    writeln!(
+        output,
+        "total: {}/{} lines exact ({:.2}%), character error rate {:.2}%",
+        report.exact_lines,
+        report.lines,
+        report.line_accuracy() * 100.0,
+        report.character_error_rate() * 100.0
+    )?;
+
+    let mut mismatches: Vec<(&str, &LineMismatch)> = report
+        .files
+        .iter()
+        .flat_map(|file| file.mismatches.iter().map(|m| (file.name.as_str(), m)))
+        .collect();
+    mismatches.sort_by(|(a_name, a), (b_name, b)| {
+        b.character_errors
+            .cmp(&a.character_errors)
+            .then_with(|| (a_name, a.line).cmp(&(b_name, b.line)))
+    });
+    if !mismatches.is_empty() && worst > 0 {
+        writeln!(output, "\nWorst lines:")?;
+    }
+    for (name, mismatch) in mismatches.into_iter().take(worst) {
+        writeln!(
+            output,
+            "{}:{} ({} errors)\n  input:    {}\n  expected: {}\n  actual:   {}",
+            name,
+            mismatch.line,
+            mismatch.character_errors,
+            mismatch.input,
+            mismatch.expected,
+            mismatch.actual
+        )?;
+    }
+
+    if !report.phrase_mismatches.is_empty() && worst > 0 {
+        writeln!(output, "\nMost frequent phrase mismatches:")?;
+    }
+    for phrase in report.phrase_mismatches.iter().take(worst) {
+        writeln!(
+            output,
+            "{}→{} (expected {}) ×{}",
+            phrase.input, phrase.actual, phrase.expected, phrase.count
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+
+use crate::diff_dicts::SOURCES;
+use crate::Script;
+
+/// A conversion the CLI supports, and the OpenCC dictionaries it applies.
+#[derive(Serialize, Debug)]
+struct Conversion {
+    from: &'static str,
+    to: &'static str,
+    dictionaries: Vec<&'static str>,
+}
+
+/// The supported scripts and conversions, as printed with `--json`.
+#[derive(Serialize, Debug)]
+struct Conversions {
+    scripts: Vec<&'static str>,
+    conversions: Vec<Conversion>,
+}
+
+/// The OpenCC dictionaries applied to convert from one script to another.
+fn dictionaries(from: &str, to: &str) -> Vec<&'static str> {
+    let (from_any, any_to) = (format!("{}→*", from), format!("*→{}", to));
+    SOURCES
+        .iter()
+        .filter(|(_, conversions)| {
+            conversions
+                .iter()
+                .any(|conversion| *conversion == from_any || *conversion == any_to)
+        })
+        .map(|(source, _)| *source)
+        .collect()
+}
+
+/// Write the supported scripts, every pair of them that can be converted between, and the
+/// OpenCC dictionaries each pair applies, as text or JSON.
+pub fn list_conversions(output: &mut dyn Write, json: bool) -> Result<()> {
+    let scripts = Script::value_variants();
+    let mut conversions = Vec::new();
+    for from in scripts {
+        for to in scripts {
+            if from.name() != to.name() {
+                conversions.push(Conversion {
+                    from: from.name(),
+                    to: to.name(),
+                    dictionaries: dictionaries(from.name(), to.name()),
+                });
+            }
+        }
+    }
+    if json {
+        let list = Conversions {
+            scripts: scripts.iter().map(Script::name).collect(),
+            conversions,
+        };
+        writeln!(output, "{}", serde_json::to_string_pretty(&list)?)?;
+        return Ok(());
+    }
+    writeln!(output, "Scripts:")?;
+    for script in scripts {
+        writeln!(output, "  {}\t{}", script.name(), script.description())?;
+    }
+    writeln!(output, "Conversions:")?;
+    for conversion in conversions {
+        writeln!(
+            output,
+            "  {}→{}\t{}",
+            conversion.from,
+            conversion.to,
+            conversion.dictionaries.join(", ")
+        )?;
+    }
+    Ok(())
+}
@@ -1,51 +1,655 @@
-use anyhow::{anyhow, Result};
-use clap::{builder::PossibleValue, Parser, ValueEnum};
+use anyhow::{anyhow, Context, Result};
+use clap::{builder::PossibleValue, ArgGroup, Parser, Subcommand, ValueEnum};
+use delimited::convert_delimited;
+use diff::write_unified_diff;
 use encoding_rs::{BIG5, GB18030, UTF_8};
+use errors::{EncodingError, UsageError};
+use manifest::{CountingSink, FileRecord, HashingWriter, Manifest};
+use markdown::convert_markdown;
+use progress::Progress;
 use rayon::prelude::*;
+use records::{write_records, Record};
 use std::{
+    borrow::Cow,
     fs,
-    io::{self, BufWriter, Read, Write},
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
+use subtitle::{convert_ass, convert_cues};
+use tee::TeeWriter;
+use ztarcc_rs::{ConversionSink, DiffSink};
 
-#[derive(Clone, Debug)]
+mod batch;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod daemon;
+mod delimited;
+mod dict;
+mod diff;
+mod diff_dicts;
+mod epub;
+mod errors;
+mod eval;
+mod list_conversions;
+mod manifest;
+mod markdown;
+mod ooxml;
+mod progress;
+mod records;
+mod rename;
+mod repl;
+mod resume;
+#[cfg(feature = "serve")]
+mod serve;
+mod subtitle;
+mod tee;
+mod watch;
+
+#[derive(Clone, Debug, Default)]
 enum Script {
     /// Convert from or to Simplified Chinese.
+    #[default]
     Simplified,
     /// Convert from or to Traditional Chinese (Taiwan).
     Taiwan,
     /// Convert from or to Traditional Chinese (Hong Kong).
     HongKong,
+    /// Convert from or to OpenCC Standard Traditional Chinese, without any
+    /// regional phrases or variants.
+    Standard,
 }
 
-impl Default for Script {
-    fn default() -> Self {
-        Self::Simplified
-    }
+/// The scripts that an OpenCC configuration converts between, by its name
+/// or the name of its file.  Regional phrases are always converted, so
+/// configurations that differ only in that, such as `s2tw` and `s2twp`, are
+/// the same here.
+fn parse_opencc_config(config: &str) -> Result<(Script, Script), String> {
+    let name = Path::new(config)
+        .file_name()
+        .map_or(config.into(), |name| name.to_string_lossy());
+    let name = name.strip_suffix(".json").unwrap_or(&name);
+    Ok(match name {
+        "s2t" => (Script::Simplified, Script::Standard),
+        "t2s" => (Script::Standard, Script::Simplified),
+        "s2tw" | "s2twp" => (Script::Simplified, Script::Taiwan),
+        "tw2s" | "tw2sp" => (Script::Taiwan, Script::Simplified),
+        "s2hk" => (Script::Simplified, Script::HongKong),
+        "hk2s" => (Script::HongKong, Script::Simplified),
+        "t2tw" => (Script::Standard, Script::Taiwan),
+        "tw2t" => (Script::Taiwan, Script::Standard),
+        "t2hk" => (Script::Standard, Script::HongKong),
+        "hk2t" => (Script::HongKong, Script::Standard),
+        _ => {
+            return Err(format!(
+                "{} is not a supported OpenCC configuration",
+                config
+            ))
+        }
+    })
 }
 
 impl ValueEnum for Script {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Simplified, Self::Taiwan, Self::HongKong]
+        &[
+            Self::Simplified,
+            Self::Taiwan,
+            Self::HongKong,
+            Self::Standard,
+        ]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl Script {
+    /// The name of the script, as given on the command line.
+    fn name(&self) -> &'static str {
+        ztarcc_rs::Script::from(self).name()
+    }
+
+    /// What the script is, for listings.
+    fn description(&self) -> &'static str {
+        ztarcc_rs::Script::from(self).description()
+    }
+}
+
+impl From<&Script> for ztarcc_rs::Script {
+    fn from(script: &Script) -> Self {
+        match script {
+            Script::Simplified => ztarcc_rs::Script::CN,
+            Script::Taiwan => ztarcc_rs::Script::TW,
+            Script::HongKong => ztarcc_rs::Script::HK,
+            Script::Standard => ztarcc_rs::Script::ST,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+enum InputEncoding {
+    /// Detect the encoding from the input.
+    #[default]
+    Auto,
+    /// UTF-8.
+    Utf8,
+    /// Big5.
+    Big5,
+    /// GB 18030.
+    Gb18030,
+}
+
+impl ValueEnum for InputEncoding {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Auto, Self::Utf8, Self::Big5, Self::Gb18030]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Auto => PossibleValue::new("auto"),
+            Self::Utf8 => PossibleValue::new("utf-8"),
+            Self::Big5 => PossibleValue::new("big5"),
+            Self::Gb18030 => PossibleValue::new("gb18030"),
+        })
+    }
+}
+
+/// Decode the raw input, returning the text and the name of the encoding it
+/// was decoded from.  Unless the encoding is auto-detected, invalid byte
+/// sequences are reported as errors rather than replaced.
+fn decode_input<'a>(
+    encoding: &InputEncoding,
+    input: &'a [u8],
+) -> Result<(Cow<'a, str>, &'static str)> {
+    let forced = match encoding {
+        InputEncoding::Auto => None,
+        InputEncoding::Utf8 => Some(UTF_8),
+        InputEncoding::Big5 => Some(BIG5),
+        InputEncoding::Gb18030 => Some(GB18030),
+    };
+    if let Some(forced) = forced {
+        let decoded = forced
+            .decode_without_bom_handling_and_without_replacement(input)
+            .ok_or(EncodingError(format!(
+                "Input is not valid {}",
+                forced.name()
+            )))?;
+        return Ok((decoded, forced.name()));
+    }
+
+    let mut detect_settings = charset_normalizer_rs::entity::NormalizerSettings::default().clone();
+    detect_settings.include_encodings =
+        vec!["utf-8".to_owned(), "big5".to_owned(), "gb18030".to_owned()];
+    let encoding_matches = charset_normalizer_rs::from_bytes(input, Some(detect_settings));
+    let encoding = encoding_matches
+        .get_best()
+        .ok_or(EncodingError("Failed to detect source encoding".to_owned()))?
+        .encoding();
+    let detected = match encoding {
+        "utf-8" => UTF_8,
+        "big5" => BIG5,
+        "gb18030" => GB18030,
+        _ => return Err(EncodingError(format!("Failed to decode from {}", encoding)).into()),
+    };
+    let (decoded, _, _) = detected.decode(input);
+    Ok((decoded, detected.name()))
+}
+
+#[derive(Clone, Debug, Default)]
+enum OutputEncoding {
+    /// UTF-8 without a byte order mark.
+    #[default]
+    Utf8,
+    /// Big5, for legacy Traditional Chinese systems.
+    Big5,
+    /// GB 18030, for legacy Simplified Chinese systems.
+    Gb18030,
+    /// UTF-16 (little endian) with a byte order mark.
+    Utf16Le,
+}
+
+impl OutputEncoding {
+    /// The name of the encoding, as given on the command line.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Big5 => "big5",
+            Self::Gb18030 => "gb18030",
+            Self::Utf16Le => "utf-16le",
+        }
+    }
+}
+
+impl ValueEnum for OutputEncoding {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Utf8, Self::Big5, Self::Gb18030, Self::Utf16Le]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+enum Flush {
+    /// Flush each line when plain text is piped in or out.
+    #[default]
+    Auto,
+    /// Flush each line, like --line-buffered.
+    Line,
+    /// Read the whole input first, even from a pipe.
+    End,
+}
+
+impl ValueEnum for Flush {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Auto, Self::Line, Self::End]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Auto => PossibleValue::new("auto"),
+            Self::Line => PossibleValue::new("line"),
+            Self::End => PossibleValue::new("end"),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+enum LineEndings {
+    /// Keep each line ending as it is.
+    #[default]
+    Keep,
+    /// Write every line ending as LF.
+    Lf,
+    /// Write every line ending as CRLF.
+    Crlf,
+}
+
+impl ValueEnum for LineEndings {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Keep, Self::Lf, Self::Crlf]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Keep => PossibleValue::new("keep"),
+            Self::Lf => PossibleValue::new("lf"),
+            Self::Crlf => PossibleValue::new("crlf"),
+        })
+    }
+}
+
+impl LineEndings {
+    /// Rewrite the line endings of some text.
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        match self {
+            Self::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            Self::Crlf if text.contains('\n') => {
+                Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+            }
+            _ => text,
+        }
+    }
+}
+
+/// Whether raw input starts with a UTF-8 or UTF-16 byte order mark.
+fn has_bom(input: &[u8]) -> bool {
+    input.starts_with(b"\xEF\xBB\xBF")
+        || input.starts_with(b"\xFF\xFE")
+        || input.starts_with(b"\xFE\xFF")
+}
+
+/// Remove a leading byte order mark from the start of the decoded input,
+/// since the output's is written according to --bom, and apply
+/// --line-endings.
+fn prepare_text<'a>(args: &Args, text: Cow<'a, str>) -> Cow<'a, str> {
+    let text = match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.strip_prefix('\u{FEFF}').unwrap_or(text)),
+        Cow::Owned(text) => match text.strip_prefix('\u{FEFF}') {
+            Some(rest) => Cow::Owned(rest.to_owned()),
+            None => Cow::Owned(text),
+        },
+    };
+    args.line_endings.apply(text)
+}
+
+/// The byte order mark to start the output with: always for UTF-16 and with
+/// --bom, never with --no-bom or for Big5 and GB 18030, and otherwise only if
+/// the input had one.
+fn output_bom(args: &Args, input_bom: bool) -> &'static [u8] {
+    let wanted = !args.no_bom
+        && (args.bom || input_bom || matches!(args.output_encoding, OutputEncoding::Utf16Le));
+    match (&args.output_encoding, wanted) {
+        (OutputEncoding::Utf8, true) => b"\xEF\xBB\xBF",
+        (OutputEncoding::Utf16Le, true) => b"\xFF\xFE",
+        _ => b"",
+    }
+}
+
+/// Encode a chunk of converted text in the requested output encoding.
+fn encode_output<'a>(encoding: &OutputEncoding, text: &'a str) -> Result<Cow<'a, [u8]>> {
+    let target = match encoding {
+        OutputEncoding::Utf8 => return Ok(Cow::Borrowed(text.as_bytes())),
+        OutputEncoding::Utf16Le => {
+            return Ok(Cow::Owned(
+                text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            ))
+        }
+        OutputEncoding::Big5 => BIG5,
+        OutputEncoding::Gb18030 => GB18030,
+    };
+    let (encoded, _, unmappable) = target.encode(text);
+    if unmappable {
+        return Err(
+            EncodingError(format!("Failed to encode {:?} as {}", text, target.name())).into(),
+        );
+    }
+    Ok(encoded)
+}
+
+#[derive(Clone, Debug, Default)]
+enum Format {
+    /// Plain text; everything is converted.
+    #[default]
+    Text,
+    /// HTML; only text nodes and human-readable attributes are converted.
+    Html,
+    /// SubRip subtitles; only the subtitle text is converted.
+    Srt,
+    /// Advanced SubStation Alpha subtitles; only dialogue text is converted.
+    Ass,
+    /// WebVTT subtitles; only the cue text is converted.
+    Vtt,
+    /// Markdown; code, link destinations, and front matter keys are not
+    /// converted.
+    Markdown,
+    /// JSON; only string values are converted.
+    Json,
+    /// Comma-separated values; only the columns given by --columns are
+    /// converted.
+    Csv,
+    /// Tab-separated values; only the columns given by --columns are
+    /// converted.
+    Tsv,
+    /// EPUB e-books; the XHTML documents, the title and other metadata, and
+    /// the table of contents are converted, and the language is updated.
+    Epub,
+    /// Word documents; the text of each run is converted, keeping its
+    /// formatting.
+    Docx,
+    /// Excel workbooks; the text of each cell is converted, keeping its
+    /// formatting.
+    Xlsx,
+    /// PowerPoint presentations; the text of each run is converted, keeping
+    /// its formatting.
+    Pptx,
+}
+
+#[derive(Clone, Debug, Default)]
+enum OutputFormat {
+    /// The converted text.
+    #[default]
+    Text,
+    /// A line of JSON for the whole input, with the converted text and what
+    /// was changed.
+    Json,
+    /// A line of JSON for each line of the input.
+    JsonLines,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Text, Self::Json, Self::JsonLines]
     }
     fn to_possible_value(&self) -> Option<PossibleValue> {
         Some(match self {
-            Self::Simplified => PossibleValue::new("cn"),
-            Self::Taiwan => PossibleValue::new("tw"),
-            Self::HongKong => PossibleValue::new("hk"),
+            Self::Text => PossibleValue::new("text"),
+            Self::Json => PossibleValue::new("json"),
+            Self::JsonLines => PossibleValue::new("json-lines"),
         })
     }
 }
 
+impl Format {
+    /// The format a file's extension implies, if any.
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "txt" => Self::Text,
+            "html" | "htm" | "xhtml" => Self::Html,
+            "srt" => Self::Srt,
+            "ass" | "ssa" => Self::Ass,
+            "vtt" => Self::Vtt,
+            "md" | "markdown" => Self::Markdown,
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "tsv" => Self::Tsv,
+            "epub" => Self::Epub,
+            "docx" => Self::Docx,
+            "xlsx" => Self::Xlsx,
+            "pptx" => Self::Pptx,
+            _ => return None,
+        })
+    }
+
+    /// Whether the format is a ZIP container, converted as a whole file.
+    fn is_container(&self) -> bool {
+        matches!(self, Self::Epub | Self::Docx | Self::Xlsx | Self::Pptx)
+    }
+}
+
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Text,
+            Self::Html,
+            Self::Srt,
+            Self::Ass,
+            Self::Vtt,
+            Self::Markdown,
+            Self::Json,
+            Self::Csv,
+            Self::Tsv,
+            Self::Epub,
+            Self::Docx,
+            Self::Xlsx,
+            Self::Pptx,
+        ]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Text => PossibleValue::new("text"),
+            Self::Html => PossibleValue::new("html"),
+            Self::Srt => PossibleValue::new("srt"),
+            Self::Ass => PossibleValue::new("ass"),
+            Self::Vtt => PossibleValue::new("vtt"),
+            Self::Markdown => PossibleValue::new("markdown").alias("md"),
+            Self::Json => PossibleValue::new("json"),
+            Self::Csv => PossibleValue::new("csv"),
+            Self::Tsv => PossibleValue::new("tsv"),
+            Self::Epub => PossibleValue::new("epub"),
+            Self::Docx => PossibleValue::new("docx"),
+            Self::Xlsx => PossibleValue::new("xlsx"),
+            Self::Pptx => PossibleValue::new("pptx"),
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Evaluate the conversion against expected output, and report how
+    /// closely they match.
+    Eval(EvalArgs),
+    /// Compare two sources of OpenCC text dictionaries, such as the vendored
+    /// data and a newer checkout, and report the changed mappings with the
+    /// conversions they affect.
+    DiffDicts(DiffDictsArgs),
+    /// Inspect the dictionaries compiled into this build.
+    #[command(subcommand)]
+    Dict(DictCommand),
+    /// List the supported scripts, the pairs that can be converted between,
+    /// and the OpenCC dictionaries each pair applies.
+    ListConversions(ListConversionsArgs),
+    /// Split the input into the segments it is converted in, without
+    /// converting it, writing each line's segments separated by spaces.
+    Segment(SegmentArgs),
+    /// Convert lines as they are typed, with commands to change the scripts
+    /// and options and to show the candidates for a word.
+    Repl(ReplArgs),
+    /// Keep an engine loaded and serve conversions on a Unix domain socket,
+    /// for clients run with --use-daemon.
+    Daemon(DaemonArgs),
+    /// Serve conversions over HTTP: POST /convert with a JSON body of
+    /// "from", "to", and "text" returns the converted "text", and POST
+    /// /reload rereads the custom script files.
+    #[cfg(feature = "serve")]
+    Serve(ServeArgs),
+}
+
+#[cfg(feature = "serve")]
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// The address to listen on.
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// The port to listen on.
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Files of custom script definitions to register, for use as "from" or
+    /// "to".  Each is reloaded when it or a dictionary it names changes.
+    #[arg(long)]
+    script_file: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DaemonArgs {
+    /// The socket to listen on; by default, ztarcc.sock in $XDG_RUNTIME_DIR
+    /// or the temporary directory.
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Files of custom script definitions to register, for use with
+    /// --to-custom.
+    #[arg(long)]
+    script_file: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SegmentArgs {
+    /// The input file; "-" for standard in.
+    #[arg(default_value = "-")]
+    input: PathBuf,
+
+    /// The text to write between segments.
+    #[arg(long, default_value = " ")]
+    separator: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReplArgs {
+    /// The input script.
+    #[arg(short, long, value_enum, default_value = "cn")]
+    from: Script,
+
+    /// The output script.
+    #[arg(short, long, value_enum, default_value = "tw")]
+    to: Script,
+}
+
+#[derive(clap::Args, Debug)]
+struct ListConversionsArgs {
+    /// Print the list as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum DictCommand {
+    /// List the names of the dictionaries compiled into this build.
+    List,
+    /// Write compiled dictionaries out in OpenCC's text format, including
+    /// the ones reversed or composed at build time.
+    Dump(DictDumpArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group = ArgGroup::new("dictionaries").required(true).args(["names", "from"]))]
+struct DictDumpArgs {
+    /// The dictionaries to dump, by name, such as FromChina.
+    names: Vec<String>,
+
+    /// Dump the dictionaries converting from this script to --to, in the
+    /// order they are applied.
+    #[arg(short, long, value_enum, requires = "to")]
+    from: Option<Script>,
+
+    /// The output script, with --from.
+    #[arg(short, long, value_enum, requires = "from")]
+    to: Option<Script>,
+
+    /// Only dump entries whose phrases start with this.
+    #[arg(long, default_value = "")]
+    prefix: String,
+
+    /// Write each dictionary to NAME.txt in this directory instead of to
+    /// standard out.
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffDictsArgs {
+    /// The old dictionaries: a directory of OpenCC .txt dictionaries, such
+    /// as opencc/data/dictionary, or a single dictionary file.
+    old: PathBuf,
+
+    /// The new dictionaries, given the same way.
+    new: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct EvalArgs {
+    /// The corpus: a directory of NAME.in files with expected NAME.ans
+    /// files, a TMX file, or a file of tab-separated input and expected lines.
+    corpus: PathBuf,
+
+    /// The input script.
+    #[arg(short, long, value_enum, default_value = "cn")]
+    from: Script,
+
+    /// The output script.
+    #[arg(short, long, value_enum, default_value = "tw")]
+    to: Script,
+
+    /// The number of worst-diverging lines and phrases to print.
+    #[arg(long, default_value_t = 10)]
+    worst: usize,
+
+    /// Print the full report as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    group = ArgGroup::new("directory_mode").args(["watch", "batch"])
+)]
 struct Args {
-    /// The input file to convert.  Use "-" to read from standard in.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The input file to convert.  Use "-" (or "CON" on Windows) to read
+    /// from standard in.
     #[arg(default_value = "-")]
-    input: String,
+    input: PathBuf,
 
-    /// The output file.  Use "-" to print to standard output.
+    /// The output file.  Use "-" (or "CON" on Windows) to print to standard
+    /// output.
     #[arg(default_value = "-")]
-    output: String,
+    output: PathBuf,
 
     /// The input script.
     #[arg(short, long, value_enum, default_value = "cn")]
@@ -54,58 +658,1193 @@ struct Args {
     /// The output script.
     #[arg(short, long, value_enum, default_value = "tw")]
     to: Script,
+
+    /// An OpenCC configuration, such as "s2twp" or "s2twp.json", to take the
+    /// input and output scripts from instead of --from and --to.
+    #[arg(short, long, value_parser = parse_opencc_config, conflicts_with_all = ["from", "to"])]
+    config: Option<(Script, Script)>,
+
+    /// The encoding of the input.  Anything other than "auto" skips detection,
+    /// and invalid input is reported as an error.
+    #[arg(long, value_enum, default_value = "auto")]
+    input_encoding: InputEncoding,
+
+    /// The encoding of the output.
+    #[arg(long, value_enum, default_value = "utf-8")]
+    output_encoding: OutputEncoding,
+
+    /// Start UTF-8 output with a byte order mark even if the input had none.
+    /// By default, UTF-8 output has one only if the input did, and UTF-16
+    /// output always has one.
+    #[arg(long)]
+    bom: bool,
+
+    /// Never write a byte order mark, even for UTF-16 output.
+    #[arg(long, conflicts_with = "bom")]
+    no_bom: bool,
+
+    /// The line endings to write: "keep" leaves each as it is, while "lf"
+    /// and "crlf" rewrite them all.
+    #[arg(long, value_enum, default_value = "keep")]
+    line_endings: LineEndings,
+
+    /// Instead of the converted text, output each changed phrase with the
+    /// number of times it occurred, most frequent first.
+    #[arg(long)]
+    diff_summary: bool,
+
+    /// Instead of writing the output, print a unified diff from the input to
+    /// what would be written, for reviewing a conversion before making it.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "diff_summary", "warnings", "fail_on_ambiguous", "manifest", "quarantine",
+            "tee", "progress",
+        ]
+    )]
+    diff: bool,
+
+    /// Instead of writing the output, report each phrase that would not
+    /// convert back to what it was, and any line that would change if
+    /// converted again, then exit with an error if there are any.
+    #[arg(
+        long,
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "diff_summary", "diff", "format", "html", "to_custom", "greedy", "warnings",
+            "fail_on_ambiguous", "manifest", "quarantine", "line_buffered", "tee", "progress",
+            "output_format", "ruby",
+        ]
+    )]
+    verify: bool,
+
+    /// The format of the input; structured formats only have their text
+    /// content converted.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        conflicts_with = "diff_summary"
+    )]
+    format: Format,
+
+    /// A custom script definition file to load; may be repeated.  See
+    /// `CustomScript::from_definition_file` for the file format.
+    #[arg(long)]
+    script_file: Vec<PathBuf>,
+
+    /// Convert from the named custom script instead of the --from script,
+    /// which is the same as converting from its base script.
+    #[arg(long)]
+    from_custom: Option<String>,
+
+    /// Convert to the named custom script instead of the --to script.
+    #[arg(long)]
+    to_custom: Option<String>,
+
+    /// Convert by greedy longest matching without segmenting the input
+    /// first.  This is much faster, but may pick the wrong phrase where
+    /// phrases overlap.
+    #[arg(long, conflicts_with = "diff_summary")]
+    greedy: bool,
+
+    /// Print warnings about suspicious input, such as phrases split by
+    /// segmentation, to standard error.
+    #[arg(long, conflicts_with_all = ["diff_summary", "greedy", "format", "html"])]
+    warnings: bool,
+
+    /// Exit with an error, listing the offending lines, if any phrase had
+    /// several possible conversions.  The output is still written.
+    #[arg(long, conflicts_with_all = ["diff_summary", "greedy", "format", "html"])]
+    fail_on_ambiguous: bool,
+
+    /// Write fullwidth Latin letters, digits, and spaces as halfwidth.
+    #[arg(long)]
+    halfwidth: bool,
+
+    /// Write Latin letters, digits, and spaces as fullwidth.
+    #[arg(long, conflicts_with = "halfwidth")]
+    fullwidth: bool,
+
+    /// A file of words, one per line, that are never converted, such as
+    /// brand names or usernames.
+    #[arg(long)]
+    exclude_file: Option<PathBuf>,
+
+    /// Don't use Jieba's hidden Markov model to find words missing from its
+    /// dictionary, which occasionally glues characters together so that a
+    /// phrase no longer matches.
+    #[arg(long, conflicts_with = "greedy")]
+    no_hmm: bool,
+
+    /// Segment by finding every dictionary word, including overlapping ones,
+    /// and converting the longest one at each position.
+    #[arg(long, conflicts_with = "greedy")]
+    cut_all: bool,
+
+    /// Copy ASCII words, digits, whitespace, and runs of ASCII text through
+    /// without converting them.
+    #[arg(long)]
+    passthrough: bool,
+
+    /// Rewrite punctuation after Chinese text for the output script:
+    /// fullwidth marks, ellipses, and quotation marks.
+    #[arg(long)]
+    punctuation: bool,
+
+    /// Fold Kangxi radicals and compatibility ideographs to the characters
+    /// the dictionaries use before converting.
+    #[arg(long)]
+    normalize: bool,
+
+    /// Copy input that looks like Japanese or Korean through unconverted.
+    #[arg(long)]
+    language_guard: bool,
+
+    /// With "--format json", only convert strings at or below this JSON
+    /// pointer; may be repeated.
+    #[arg(long)]
+    json_pointer: Vec<String>,
+
+    /// With "--format csv" or "--format tsv", the columns to convert,
+    /// numbered from 1 and separated by commas.  All columns are converted
+    /// if this is not given.
+    #[arg(long, value_delimiter = ',')]
+    columns: Vec<usize>,
+
+    /// Write a JSON record of the run to this file: the input and output
+    /// files with their encodings and SHA-256 checksums, the scripts, the
+    /// number of phrases changed, and any warnings.
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// If converting the input gives ambiguous conversions or unknown
+    /// characters (such as those left by decoding errors), write the output
+    /// to this directory instead, under the input's file name, along with a
+    /// NAME.warnings.txt report.
+    #[arg(long)]
+    quarantine: Option<PathBuf>,
+
+    /// Treat the input as HTML; shorthand for "--format html".
+    #[arg(long, conflicts_with_all = ["diff_summary", "format"])]
+    html: bool,
+
+    /// Convert and flush each line as it arrives instead of reading the
+    /// whole input first, as for "tail -f app.log | ztarcc".  This is the
+    /// default for plain text piped to standard in.  With "auto" input
+    /// encoding, lines are read as UTF-8 when they are valid UTF-8.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "diff_summary", "format", "html", "warnings", "fail_on_ambiguous", "manifest",
+            "quarantine", "progress", "diff",
+        ]
+    )]
+    line_buffered: bool,
+
+    /// When to write out converted text: "line" converts and flushes each
+    /// line as it arrives, "end" reads the whole input first, and "auto"
+    /// flushes each line when plain text is piped in on standard in or out
+    /// on standard out, as in "some-tool | ztarcc | fzf".  Lines are only
+    /// flushed for plain text without options that need the whole input.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "auto",
+        conflicts_with = "line_buffered"
+    )]
+    flush: Flush,
+
+    /// Also write the output to this file, for keeping the results of a
+    /// conversion that is being watched, such as one piped into a pager.
+    #[arg(long)]
+    tee: Option<PathBuf>,
+
+    /// Show the progress of plain text conversion on standard error: how
+    /// much has been converted, the throughput, and the time left.
+    #[arg(long, conflicts_with_all = ["diff_summary", "format", "html", "warnings", "fail_on_ambiguous"])]
+    progress: bool,
+
+    /// An OpenCC text dictionary of Hong Kong phrases, each mapping an
+    /// OpenCC Standard phrase to the one used in Hong Kong, to layer over
+    /// the built-in dictionaries when converting to or from Hong Kong.
+    #[arg(long)]
+    hk_phrases: Option<PathBuf>,
+
+    /// Convert the text in the system clipboard, replacing it with the
+    /// result, instead of reading and writing files.
+    #[cfg(feature = "clipboard")]
+    #[arg(
+        long,
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "input", "output", "input_encoding", "output_encoding", "diff_summary", "diff",
+            "format", "html", "warnings", "fail_on_ambiguous", "manifest", "quarantine",
+            "line_buffered", "tee", "progress", "output_format", "rename", "use_daemon",
+        ]
+    )]
+    clipboard: bool,
+
+    /// Convert every file in this directory into the --out directory,
+    /// keeping their relative paths, then keep converting files as they
+    /// change until interrupted.  Each file's format is given by its
+    /// extension, falling back to --format.
+    #[arg(
+        long,
+        requires = "out",
+        conflicts_with_all = [
+            "input", "output", "diff_summary", "diff", "warnings", "fail_on_ambiguous",
+            "manifest", "quarantine", "line_buffered", "tee", "progress",
+        ]
+    )]
+    watch: Option<PathBuf>,
+
+    /// Convert these files, and every file in these directories, into the
+    /// --out directory on all cores, then report any that failed.  Formats
+    /// are chosen as for --watch.
+    #[arg(
+        long,
+        num_args = 1..,
+        requires = "out",
+        conflicts_with_all = [
+            "input", "output", "diff_summary", "diff", "warnings", "fail_on_ambiguous",
+            "manifest", "quarantine", "line_buffered", "tee", "progress",
+        ]
+    )]
+    batch: Vec<PathBuf>,
+
+    /// Convert the names of every file and directory under this directory,
+    /// instead of any contents, listing each rename.  If any converted names
+    /// collide with each other or with existing files, nothing is renamed.
+    #[arg(
+        long,
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "input", "output", "diff_summary", "diff", "format", "html", "warnings",
+            "fail_on_ambiguous", "manifest", "quarantine", "line_buffered", "tee", "progress",
+            "output_format", "ruby",
+        ]
+    )]
+    rename: Option<PathBuf>,
+
+    /// With --batch, report for each file and in total how many bytes were
+    /// converted and characters changed, and which files were unchanged or
+    /// skipped.
+    #[arg(long, requires = "batch")]
+    stats: bool,
+
+    /// With --batch, also write the --stats report to this file as JSON.
+    #[arg(long, requires = "batch")]
+    stats_file: Option<PathBuf>,
+
+    /// With --rename, list the renames without making them.
+    #[arg(long, requires = "rename")]
+    dry_run: bool,
+
+    /// The directory to write converted files to, with --watch or --batch.
+    #[arg(long, requires = "directory_mode")]
+    out: Option<PathBuf>,
+
+    /// When converting an EPUB or Office file, keep each converted entry in
+    /// this directory as it is finished, so that an interrupted conversion
+    /// run again with the same directory resumes from the last finished
+    /// entry.  Its files are removed once the output is written, and the
+    /// directory too if nothing else is in it.
+    #[arg(long, conflicts_with = "directory_mode", conflicts_with_all = ["line_buffered"])]
+    resume: Option<PathBuf>,
+
+    /// With --watch or --batch, copy files that need no conversion, such as
+    /// images and text already in the output script, to the --out directory
+    /// as they are, instead of leaving them out.
+    #[arg(long, requires = "directory_mode")]
+    copy_unchanged: bool,
+
+    /// Write the output as HTML, with each changed phrase annotated with its
+    /// original form in ruby text above it.
+    #[arg(
+        long,
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "diff_summary", "diff", "format", "html", "greedy", "halfwidth", "fullwidth",
+            "warnings", "fail_on_ambiguous", "line_buffered", "progress", "output_format",
+        ]
+    )]
+    ruby: bool,
+
+    /// The template for each changed phrase with --ruby, in which
+    /// "{converted}" and "{original}" stand for the escaped text.
+    #[arg(long, requires = "ruby", default_value = ztarcc_rs::RUBY_TEMPLATE)]
+    ruby_template: String,
+
+    /// A directory to keep the built segmentation dictionary in, so that
+    /// later runs start faster, such as when converting many small files
+    /// one invocation at a time.
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Convert with the engine of a running "ztarcc daemon" instead of
+    /// loading one, which saves most of the startup time.
+    #[arg(
+        long,
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "diff_summary", "diff", "verify", "format", "html", "greedy", "halfwidth",
+            "fullwidth", "warnings", "fail_on_ambiguous", "manifest", "quarantine",
+            "line_buffered", "progress", "output_format", "ruby", "exclude_file", "no_hmm",
+            "cut_all", "passthrough", "punctuation", "normalize", "language_guard", "hk_phrases",
+            "script_file", "cache_dir", "rename",
+        ]
+    )]
+    use_daemon: bool,
+
+    /// The socket of the daemon to use with --use-daemon, if not the default.
+    #[arg(long, requires = "use_daemon")]
+    daemon_socket: Option<PathBuf>,
+
+    /// The number of threads to convert on, including the main thread.  With
+    /// 1, everything runs on the main thread and no others are started.
+    /// Defaults to one for each core.
+    #[arg(long)]
+    threads: Option<NonZeroUsize>,
+
+    /// Write JSON records instead of the converted text: "json" writes one
+    /// for the whole input and "json-lines" one for each line, each with the
+    /// converted text, the input encoding, the scripts, and the byte offsets
+    /// of every changed span.  Records are always written in UTF-8.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        conflicts_with = "directory_mode",
+        conflicts_with_all = [
+            "output_encoding", "bom", "diff_summary", "diff", "format", "greedy", "warnings",
+            "fail_on_ambiguous", "manifest", "quarantine", "html", "line_buffered", "progress",
+        ]
+    )]
+    output_format: OutputFormat,
+}
+
+impl Args {
+    /// The names of the input and output scripts, which may be custom scripts.
+    fn script_names(&self) -> (&str, &str) {
+        (
+            self.from_custom.as_deref().unwrap_or(self.from.name()),
+            self.to_custom.as_deref().unwrap_or(self.to.name()),
+        )
+    }
+
+    /// Whether to convert a line at a time as the input arrives.
+    fn streams(&self) -> bool {
+        let piped = match self.flush {
+            Flush::Auto => {
+                is_console(&self.input)
+                    && (stdin_is_pipe() || (is_console(&self.output) && stdout_is_pipe()))
+            }
+            Flush::Line => true,
+            Flush::End => false,
+        };
+        self.line_buffered
+            || (piped
+                && matches!(self.output_format, OutputFormat::Text)
+                && !self.ruby
+                && matches!(self.format, Format::Text)
+                && !self.html
+                && !self.diff_summary
+                && !self.diff
+                && !self.verify
+                && !self.warnings
+                && !self.fail_on_ambiguous
+                && self.manifest.is_none()
+                && self.quarantine.is_none()
+                && !self.progress)
+    }
+}
+
+/// Whether a path stands for standard in or out: "-", or the console device
+/// "CON" on Windows, which has to be written through standard out to be
+/// shown correctly.
+fn is_console(path: &Path) -> bool {
+    path == Path::new("-") || (cfg!(windows) && path.as_os_str().eq_ignore_ascii_case("CON"))
+}
+
+/// Open the input file, or standard in.
+fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    if is_console(path) {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = fs::File::open(path).context(format!("opening {}", path.display()))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Create the output file, or use standard out, also writing to the `--tee`
+/// file if there is one.
+fn open_output(path: &Path, tee: Option<&Path>) -> Result<Box<dyn Write>> {
+    let output: Box<dyn Write> = match is_console(path) {
+        true => Box::new(io::stdout().lock()),
+        false => {
+            let file = fs::File::create(path).context(format!("creating {}", path.display()))?;
+            Box::new(BufWriter::new(file))
+        }
+    };
+    let Some(tee) = tee else {
+        return Ok(output);
+    };
+    let file = fs::File::create(tee).context(format!("creating {}", tee.display()))?;
+    Ok(Box::new(TeeWriter::new(output, BufWriter::new(file))))
+}
+
+/// Whether standard in is a pipe, such as from "tail -f".
+#[cfg(unix)]
+fn stdin_is_pipe() -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata("/dev/stdin").is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+/// Whether standard in is a pipe, such as from "tail -f".
+#[cfg(not(unix))]
+fn stdin_is_pipe() -> bool {
+    false
+}
+
+/// Whether standard out is a pipe, such as into "fzf".
+#[cfg(unix)]
+fn stdout_is_pipe() -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata("/dev/stdout").is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+/// Whether standard out is a pipe, such as into "fzf".
+#[cfg(not(unix))]
+fn stdout_is_pipe() -> bool {
+    false
+}
+
+/// Convert the input a line at a time, writing and flushing each converted
+/// line before reading the next.
+fn stream_lines(
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    args: &Args,
+    convert: impl Fn(&str) -> Result<Vec<String>>,
+) -> Result<()> {
+    let mut line = Vec::new();
+    let mut first = true;
+    while input.read_until(b'\n', &mut line)? > 0 {
+        let decoded = match (&args.input_encoding, std::str::from_utf8(&line)) {
+            (InputEncoding::Auto, Ok(text)) => Cow::Borrowed(text),
+            _ => decode_input(&args.input_encoding, &line)?.0,
+        };
+        let decoded = match first {
+            true => {
+                output.write_all(output_bom(args, has_bom(&line)))?;
+                first = false;
+                prepare_text(args, decoded)
+            }
+            false => args.line_endings.apply(decoded),
+        };
+        for chunk in convert(&decoded)? {
+            output.write_all(&encode_output(&args.output_encoding, &chunk)?)?;
+        }
+        output.flush()?;
+        line.clear();
+    }
+    Ok(())
+}
+
+/// Write a summary of the changed phrases, most frequent first.
+fn write_diff_summary(
+    output: &mut dyn Write,
+    encoding: &OutputEncoding,
+    lines: Vec<Result<DiffSink>>,
+) -> Result<()> {
+    let mut diff = DiffSink::default();
+    for line in lines {
+        diff.merge(line?);
+    }
+    for ((original, converted), count) in diff.summary() {
+        let entry = format!("{}→{} ×{}\n", original, converted, count);
+        output.write_all(&encode_output(encoding, &entry)?)?;
+    }
+    Ok(())
+}
+
+/// The warnings that mean a file needs a human to check it: ambiguous
+/// conversions and unknown characters, including the replacement characters
+/// left by decoding errors.
+fn risky_warnings(
+    engine: &ztarcc_rs::Engine,
+    from: ztarcc_rs::Script,
+    to: ztarcc_rs::Script,
+    lines: &[&str],
+) -> Result<Vec<String>> {
+    let lines: Vec<_> = lines
+        .par_iter()
+        .map(|line| engine.convert_with_warnings(from, to, line))
+        .collect();
+    let mut risky = Vec::new();
+    for (index, line) in lines.into_iter().enumerate() {
+        for warning in line?.1 {
+            if matches!(
+                warning,
+                ztarcc_rs::Warning::Ambiguous { .. } | ztarcc_rs::Warning::UnknownCharacter { .. }
+            ) {
+                risky.push(format!("line {}: {}", index + 1, warning));
+            }
+        }
+    }
+    Ok(risky)
+}
+
+/// Convert a document in a structured format, or return `None` for plain
+/// text, which is converted a line at a time by the caller.
+fn convert_structured(
+    args: &Args,
+    format: &Format,
+    text: &str,
+    convert_text: impl Fn(&str) -> Result<String>,
+) -> Result<Option<String>> {
+    Ok(match format {
+        Format::Text => None,
+        Format::Html => Some(ztarcc_rs::convert_html_with(text, convert_text)?),
+        Format::Srt | Format::Vtt => Some(convert_cues(text, convert_text)?),
+        Format::Ass => Some(convert_ass(text, convert_text)?),
+        Format::Markdown => Some(convert_markdown(text, convert_text)?),
+        Format::Csv => Some(convert_delimited(text, ',', &args.columns, convert_text)?),
+        Format::Tsv => Some(convert_delimited(text, '\t', &args.columns, convert_text)?),
+        Format::Json => {
+            let pointers: Vec<&str> = args.json_pointer.iter().map(String::as_str).collect();
+            Some(ztarcc_rs::convert_json_with(text, &pointers, convert_text)?)
+        }
+        Format::Epub | Format::Docx | Format::Xlsx | Format::Pptx => {
+            return Err(UsageError(format!(
+                "{} can only be converted as a whole file",
+                format.to_possible_value().unwrap().get_name()
+            ))
+            .into())
+        }
+    })
+}
+
+/// Convert an EPUB or Office Open XML file, keeping and reusing converted
+/// entries in `resume` if given.
+fn convert_container(
+    engine: &ztarcc_rs::Engine,
+    args: &Args,
+    format: &Format,
+    input: &[u8],
+    resume: Option<&mut resume::Resume>,
+    convert_text: impl Fn(&str) -> Result<String>,
+) -> Result<Vec<u8>> {
+    if !matches!(format, Format::Epub) {
+        return ooxml::convert_ooxml(input, resume, convert_text);
+    }
+    let (_, to) = args.script_names();
+    epub::convert_epub(
+        input,
+        engine.script_base(to)?.language_tag(),
+        resume,
+        |html| ztarcc_rs::convert_html_with(html, &convert_text),
+        &convert_text,
+    )
+}
+
+/// Write a report of the phrases in each line that do not convert back to what
+/// they were, with their line and column, and of the lines that change when
+/// converted again.  Returns the number of problems reported.
+fn write_verify_report(
+    output: &mut impl Write,
+    path: &Path,
+    lines: &[&str],
+    reports: &[ztarcc_rs::VerifyReport],
+) -> Result<usize> {
+    let mut problems = 0;
+    for (index, (line, report)) in lines.iter().zip(reports).enumerate() {
+        for span in &report.lossy {
+            writeln!(
+                output,
+                "{}:{}:{}: {} -> {} -> {}",
+                path.display(),
+                index + 1,
+                line[..span.start].chars().count() + 1,
+                span.original,
+                span.forward,
+                span.back
+            )?;
+        }
+        if !report.idempotent {
+            writeln!(
+                output,
+                "{}:{}: converting again changes the output",
+                path.display(),
+                index + 1
+            )?;
+        }
+        problems += report.lossy.len() + usize::from(!report.idempotent);
+    }
+    Ok(problems)
+}
+
+/// Convert the contents of a whole file, in the format its extension implies
+/// or else `format`, and encode the result, along with the number of
+/// characters in the phrases that `convert` changed.  Files containing NUL
+/// bytes, such as images, are taken to be binary and copied as they are, with
+/// no count.
+fn convert_file(
+    engine: &ztarcc_rs::Engine,
+    args: &Args,
+    format: &Format,
+    path: &Path,
+    input: &[u8],
+    convert: impl Fn(&str) -> Result<CountingSink> + Sync,
+) -> Result<(Vec<u8>, Option<usize>)> {
+    let format = Format::from_path(path).unwrap_or(format.clone());
+    if input.contains(&0) && !format.is_container() {
+        return Ok((input.to_vec(), None));
+    }
+    let changed = AtomicUsize::new(0);
+    let convert_text = |text: &str| -> Result<String> {
+        let sink = convert(text)?;
+        changed.fetch_add(sink.changed_characters, Ordering::Relaxed);
+        Ok(sink.segments.concat())
+    };
+    if format.is_container() {
+        let output = convert_container(engine, args, &format, input, None, convert_text)?;
+        return Ok((output, Some(changed.into_inner())));
+    }
+    let decoded = prepare_text(args, decode_input(&args.input_encoding, input)?.0);
+    let converted = match convert_structured(args, &format, &decoded, convert_text)? {
+        Some(converted) => converted,
+        None => decoded
+            .split_inclusive('\n')
+            .collect::<Vec<_>>()
+            .par_iter()
+            .map(|line| convert_text(line))
+            .collect::<Result<String>>()?,
+    };
+    let mut output = output_bom(args, has_bom(input)).to_vec();
+    output.extend_from_slice(&encode_output(&args.output_encoding, &converted)?);
+    Ok((output, Some(changed.into_inner())))
+}
+
+/// The conversion options given on the command line.
+fn convert_options(args: &Args) -> Result<ztarcc_rs::ConvertOptions> {
+    let mut options = ztarcc_rs::ConvertOptions::default();
+    options.cut.hmm = !args.no_hmm;
+    options.cut.cut_all = args.cut_all;
+    if args.passthrough {
+        options.passthrough = ztarcc_rs::Passthrough::all();
+    }
+    if args.punctuation {
+        options.punctuation = ztarcc_rs::Punctuation::all();
+    }
+    if args.normalize {
+        options.normalization = ztarcc_rs::Normalization::all();
+    }
+    options.language_guard = args.language_guard;
+    options.width = match (args.halfwidth, args.fullwidth) {
+        (true, _) => ztarcc_rs::Width::Halfwidth,
+        (_, true) => ztarcc_rs::Width::Fullwidth,
+        _ => ztarcc_rs::Width::Unchanged,
+    };
+    if let Some(exclude_file) = &args.exclude_file {
+        let words = fs::read_to_string(exclude_file)
+            .context(format!("reading {}", exclude_file.display()))?;
+        options.protected_words = words
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(str::to_owned)
+            .collect();
+    }
+    Ok(options)
+}
+
+/// Run the `eval` subcommand.
+fn run_eval(args: EvalArgs) -> Result<()> {
+    let engine = ztarcc_rs::Engine::new();
+    let report = eval::evaluate(
+        &engine,
+        (&args.from).into(),
+        (&args.to).into(),
+        &args.corpus,
+    )?;
+    let mut output = io::stdout().lock();
+    if args.json {
+        writeln!(output, "{}", report.to_json()?)?;
+    } else {
+        eval::write_report(&mut output, &report, args.worst)?;
+    }
+    output.flush()?;
+    Ok(())
 }
 
 fn setup() -> Result<()> {
-    let args = Args::parse();
-    let mut input = Vec::new();
-    match args.input.as_str() {
-        "-" => io::stdin().read_to_end(&mut input)?,
-        _ => fs::File::open(args.input)?.read_to_end(&mut input)?,
+    let mut args = Args::parse();
+    if let Some((from, to)) = args.config.take() {
+        args.from = from;
+        args.to = to;
+    }
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.get())
+            .use_current_thread()
+            .build_global()?;
+    }
+    match args.command {
+        Some(Command::Eval(eval_args)) => return run_eval(eval_args),
+        Some(Command::DiffDicts(diff_args)) => {
+            let mut output = io::stdout().lock();
+            diff_dicts::diff_dicts(&mut output, &diff_args.old, &diff_args.new)?;
+            return Ok(output.flush()?);
+        }
+        Some(Command::Dict(dict_command)) => {
+            let engine = ztarcc_rs::Engine::new();
+            let mut output = io::stdout().lock();
+            match dict_command {
+                DictCommand::List => {
+                    for name in engine.dictionary_names() {
+                        writeln!(output, "{}", name)?;
+                    }
+                }
+                DictCommand::Dump(dump_args) => {
+                    let names = match (&dump_args.from, &dump_args.to) {
+                        (Some(from), Some(to)) => engine.dictionary_chain(from.into(), to.into()),
+                        _ => dump_args.names.clone(),
+                    };
+                    dict::dump(
+                        &mut output,
+                        &engine,
+                        &names,
+                        &dump_args.prefix,
+                        dump_args.out_dir.as_deref(),
+                    )?;
+                }
+            }
+            return Ok(output.flush()?);
+        }
+        Some(Command::Segment(segment_args)) => {
+            let engine = ztarcc_rs::Engine::new();
+            let mut input = Vec::new();
+            open_input(&segment_args.input)?.read_to_end(&mut input)?;
+            let (text, _) = decode_input(&InputEncoding::Auto, &input)?;
+            let mut output = io::stdout().lock();
+            for line in text.lines() {
+                writeln!(
+                    output,
+                    "{}",
+                    engine.segment(line).join(&segment_args.separator)
+                )?;
+            }
+            return Ok(output.flush()?);
+        }
+        Some(Command::ListConversions(list_args)) => {
+            let mut output = io::stdout().lock();
+            list_conversions::list_conversions(&mut output, list_args.json)?;
+            return Ok(output.flush()?);
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve(serve_args)) => {
+            let engine = ztarcc_rs::Engine::new();
+            return serve::serve(
+                &engine,
+                &serve_args.script_file,
+                &serve_args.host,
+                serve_args.port,
+            );
+        }
+        Some(Command::Daemon(daemon_args)) => {
+            let engine = ztarcc_rs::Engine::new();
+            for script_file in &daemon_args.script_file {
+                engine
+                    .register_script(ztarcc_rs::CustomScript::from_definition_file(script_file)?)?;
+            }
+            engine.prewarm(None)?;
+            let socket = daemon_args.socket.unwrap_or_else(daemon::default_socket);
+            return daemon::daemon(&engine, &socket);
+        }
+        Some(Command::Repl(repl_args)) => {
+            let prompt = io::stdin().is_terminal();
+            let mut input = io::stdin().lock();
+            let mut output = io::stdout().lock();
+            return repl::repl(
+                &mut input,
+                &mut output,
+                prompt,
+                repl_args.from,
+                repl_args.to,
+            );
+        }
+        None => {}
+    }
+    // The Windows console is written in UTF-16, which the standard library
+    // can only produce from UTF-8.
+    if cfg!(windows)
+        && is_console(&args.output)
+        && io::stdout().is_terminal()
+        && !matches!(args.output_encoding, OutputEncoding::Utf8)
+    {
+        eprintln!(
+            "writing to the console as UTF-8 instead of {}",
+            args.output_encoding.name()
+        );
+        args.output_encoding = OutputEncoding::Utf8;
+    }
+    let (from_name, to_name) = args.script_names();
+    let mut run_warnings = Vec::new();
+    if args.use_daemon {
+        let socket = args
+            .daemon_socket
+            .clone()
+            .unwrap_or_else(daemon::default_socket);
+        let mut input = Vec::new();
+        open_input(&args.input)?.read_to_end(&mut input)?;
+        let decoded = prepare_text(&args, decode_input(&args.input_encoding, &input)?.0);
+        let converted = daemon::convert(&socket, from_name, to_name, &decoded)?;
+        let mut output = open_output(&args.output, args.tee.as_deref())?;
+        output.write_all(output_bom(&args, has_bom(&input)))?;
+        output.write_all(&encode_output(&args.output_encoding, &converted)?)?;
+        output.flush()?;
+        return Ok(());
+    }
+    let options = convert_options(&args)?;
+    let mut engine = match &args.cache_dir {
+        Some(cache_dir) => {
+            ztarcc_rs::Engine::with_cache_dir(options.cut, cache_dir).with_options(&options)
+        }
+        None => ztarcc_rs::Engine::from_options(&options),
     };
-    let mut output: Box<dyn Write> = match args.output.as_str() {
-        "-" => Box::new(io::stdout()),
-        _ => Box::new(BufWriter::new(fs::File::create(args.output)?)),
+    if let Some(hk_phrases) = &args.hk_phrases {
+        engine = engine.with_regional_phrases_file(ztarcc_rs::Script::HK, hk_phrases)?;
+    }
+    for script_file in &args.script_file {
+        let script = ztarcc_rs::CustomScript::from_definition_file(script_file)?;
+        for warning in script.warnings() {
+            eprintln!("{}: {}", script_file.display(), warning);
+            run_warnings.push(format!("{}: {}", script_file.display(), warning));
+        }
+        engine.register_script(script)?;
+    }
+    let from_script = engine.script_base(from_name)?;
+    let to_script = engine.script_base(to_name)?;
+    let stats = args.stats || args.stats_file.is_some();
+    // Convert some text, counting what was changed for --manifest and --stats.
+    let convert_counted = |text: &str| -> Result<CountingSink> {
+        let mut sink = CountingSink::default();
+        if args.greedy {
+            // Greedy conversion does not segment, so the whole text counts as one phrase.
+            let converted = engine.convert_greedy(from_script, to_script, text)?;
+            let converted = engine.apply_script(to_name, &converted)?;
+            match converted == text {
+                true => sink.unchanged(text)?,
+                false => sink.replaced(text, &converted)?,
+            }
+        } else if args.to_custom.is_some() || args.manifest.is_some() || stats {
+            engine.convert_named_into(from_name, to_name, text, &mut sink)?;
+        } else {
+            sink.segments = engine.convert(from_script, to_script, text)?;
+        }
+        Ok(sink)
     };
-    let from_script = match args.from {
-        Script::Simplified => ztarcc_rs::Script::CN,
-        Script::Taiwan => ztarcc_rs::Script::TW,
-        Script::HongKong => ztarcc_rs::Script::HK,
+    // The phrases changed by the conversions that wrote the output, for the manifest.
+    let substitutions = AtomicUsize::new(0);
+    let convert_segments = |text: &str| -> Result<Vec<String>> {
+        let sink = convert_counted(text)?;
+        substitutions.fetch_add(sink.substitutions, Ordering::Relaxed);
+        Ok(sink.segments)
     };
-    let to_script = match args.to {
-        Script::Simplified => ztarcc_rs::Script::CN,
-        Script::Taiwan => ztarcc_rs::Script::TW,
-        Script::HongKong => ztarcc_rs::Script::HK,
+    #[cfg(feature = "clipboard")]
+    if args.clipboard {
+        return clipboard::convert_clipboard(|text| Ok(convert_segments(text)?.join("")));
+    }
+    let convert_text = |text: &str| -> Result<String> { Ok(convert_segments(text)?.join("")) };
+    let format = if args.html {
+        Format::Html
+    } else {
+        args.format.clone()
     };
-    let mut detect_settings = charset_normalizer_rs::entity::NormalizerSettings::default().clone();
-    detect_settings.include_encodings =
-        vec!["utf-8".to_owned(), "big5".to_owned(), "gb18030".to_owned()];
-    let encoding_matches = charset_normalizer_rs::from_bytes(&input, Some(detect_settings));
-    let encoding = encoding_matches
-        .get_best()
-        .ok_or(anyhow!(format!("Failed to detect source encoding")))?
-        .encoding();
-    let (decoded, _, _) = match encoding {
-        "utf-8" => UTF_8.decode(&input),
-        "big5" => BIG5.decode(&input),
-        "gb18030" => GB18030.decode(&input),
-        _ => return Err(anyhow!(format!("Failed to decode from {}", encoding))),
+    let convert_path = |path: &Path, input: &[u8]| {
+        convert_file(&engine, &args, &format, path, input, convert_counted)
     };
-    let lines: Vec<_> = decoded
-        .split_inclusive('\n')
-        .collect::<Vec<_>>()
-        .par_iter()
-        .map(|line| ztarcc_rs::convert(from_script, to_script, line))
-        .collect();
+    if let Some(root) = &args.rename {
+        let mut output = io::stdout().lock();
+        return rename::rename(root, args.dry_run, &mut output, convert_text);
+    }
+    if let (Some(dir), Some(out)) = (&args.watch, &args.out) {
+        return watch::watch(dir, out, args.copy_unchanged, convert_path);
+    }
+    if let (false, Some(out)) = (args.batch.is_empty(), &args.out) {
+        return batch::batch(
+            &args.batch,
+            out,
+            args.copy_unchanged,
+            convert_path,
+            stats,
+            args.stats_file.as_deref(),
+        );
+    }
+    if args.streams() {
+        let mut input = open_input(&args.input)?;
+        let mut output = open_output(&args.output, args.tee.as_deref())?;
+        return stream_lines(&mut input, &mut output, &args, convert_segments);
+    }
+    let mut input = Vec::new();
+    open_input(&args.input)?.read_to_end(&mut input)?;
+    if format.is_container() {
+        let conversion = format!("{} {} {:?}", from_name, to_name, options);
+        let mut resume = match &args.resume {
+            Some(dir) => Some(resume::Resume::open(dir, &conversion)?),
+            None => None,
+        };
+        let converted = convert_container(
+            &engine,
+            &args,
+            &format,
+            &input,
+            resume.as_mut(),
+            convert_text,
+        )?;
+        let mut output = open_output(&args.output, args.tee.as_deref())?;
+        output.write_all(&converted)?;
+        output.flush()?;
+        if let Some(resume) = resume {
+            resume.remove()?;
+        }
+        return Ok(());
+    }
+    if args.resume.is_some() {
+        return Err(UsageError("--resume only applies to EPUB and Office files".to_owned()).into());
+    }
+    let (decoded, input_encoding) = decode_input(&args.input_encoding, &input)?;
+    let decoded = prepare_text(&args, decoded);
+    let input_lines: Vec<_> = decoded.split_inclusive('\n').collect();
+    if args.verify {
+        let reports = input_lines
+            .par_iter()
+            .map(|line| engine.verify(from_script, to_script, line))
+            .collect::<Result<Vec<_>>>()?;
+        let mut stdout = io::stdout().lock();
+        let problems = write_verify_report(&mut stdout, &args.input, &input_lines, &reports)?;
+        stdout.flush()?;
+        if problems > 0 {
+            return Err(anyhow!(format!(
+                "{} phrases or lines did not convert reversibly",
+                problems
+            )));
+        }
+        return Ok(());
+    }
+    if args.ruby {
+        let html = input_lines
+            .par_iter()
+            .map(|line| {
+                let mut sink = ztarcc_rs::RubySink::with_template(&args.ruby_template);
+                engine.convert_named_into(from_name, to_name, line, &mut sink)?;
+                Ok(sink.into_html())
+            })
+            .collect::<Result<String>>()?;
+        let mut output = open_output(&args.output, args.tee.as_deref())?;
+        output.write_all(output_bom(&args, has_bom(&input)))?;
+        output.write_all(&encode_output(&args.output_encoding, &html)?)?;
+        output.flush()?;
+        return Ok(());
+    }
+    if !matches!(args.output_format, OutputFormat::Text) {
+        let input_name = args.input.to_string_lossy();
+        let record = |line: Option<usize>, text: &str| {
+            let (text, changes) = records::convert(&engine, from_name, to_name, text)?;
+            Ok::<_, anyhow::Error>(Record {
+                input: &input_name,
+                line,
+                input_encoding,
+                from: from_name,
+                to: to_name,
+                text,
+                changes,
+            })
+        };
+        let records = match args.output_format {
+            OutputFormat::JsonLines => input_lines
+                .par_iter()
+                .enumerate()
+                .map(|(index, line)| record(Some(index + 1), line))
+                .collect::<Result<Vec<_>>>()?,
+            _ => vec![record(None, &decoded)?],
+        };
+        let mut output = open_output(&args.output, args.tee.as_deref())?;
+        write_records(&mut output, &records)?;
+        output.flush()?;
+        return Ok(());
+    }
 
-    for line in lines {
-        for chunk in line? {
-            output.write_all(chunk.as_bytes())?;
+    let mut output_path = args.output.clone();
+    if let Some(quarantine) = &args.quarantine {
+        let risky = risky_warnings(&engine, from_script, to_script, &input_lines)?;
+        if !risky.is_empty() {
+            let name: Cow<str> = match is_console(&args.input) {
+                true => "stdin".into(),
+                false => args
+                    .input
+                    .file_name()
+                    .map_or("input".into(), |name| name.to_string_lossy()),
+            };
+            fs::create_dir_all(quarantine)?;
+            let quarantined = quarantine.join(name.as_ref());
+            let report = quarantine.join(format!("{}.warnings.txt", name));
+            fs::write(&report, risky.join("\n") + "\n")?;
+            eprintln!(
+                "{}: {} warnings; quarantined in {}",
+                args.input.display(),
+                risky.len(),
+                quarantined.display()
+            );
+            output_path = quarantined;
+            run_warnings.extend(risky);
         }
     }
-    Ok(())
+    let mut failure = None;
+    let converted = convert_structured(&args, &format, &decoded, convert_text)?;
+    if args.diff {
+        let converted = match converted {
+            Some(converted) => converted,
+            None => input_lines
+                .par_iter()
+                .map(|line| convert_text(line))
+                .collect::<Result<String>>()?,
+        };
+        let mut stdout = io::stdout().lock();
+        write_unified_diff(
+            &mut stdout,
+            &args.input.to_string_lossy(),
+            &output_path.to_string_lossy(),
+            &decoded,
+            &converted,
+        )?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    let mut output = HashingWriter::new(open_output(&output_path, args.tee.as_deref())?);
+    output.write_all(output_bom(&args, has_bom(&input)))?;
+    if let Some(converted) = converted {
+        output.write_all(&encode_output(&args.output_encoding, &converted)?)?;
+    } else if args.diff_summary {
+        let lines: Vec<Result<DiffSink>> = input_lines
+            .par_iter()
+            .map(|line| {
+                let mut diff = DiffSink::default();
+                engine.convert_named_into(from_name, to_name, line, &mut diff)?;
+                let changed: usize = diff.summary().iter().map(|(_, count)| count).sum();
+                substitutions.fetch_add(changed, Ordering::Relaxed);
+                Ok(diff)
+            })
+            .collect();
+        write_diff_summary(&mut output, &args.output_encoding, lines)?;
+    } else if args.warnings || args.fail_on_ambiguous {
+        let lines: Vec<_> = input_lines
+            .par_iter()
+            .map(|line| -> Result<_> {
+                if args.to_custom.is_some() || args.greedy {
+                    // The warnings are about the segmented conversion to the base script.
+                    let (_, warnings) =
+                        engine.convert_with_warnings(from_script, to_script, line)?;
+                    return Ok((convert_segments(line)?, warnings));
+                }
+                let mut sink = CountingSink::default();
+                let warnings =
+                    engine.convert_with_warnings_into(from_script, to_script, line, &mut sink)?;
+                substitutions.fetch_add(sink.substitutions, Ordering::Relaxed);
+                Ok((sink.segments, warnings))
+            })
+            .collect();
+        let mut ambiguous_lines = 0;
+        let mut line_warnings = Vec::new();
+        for (index, line) in lines.into_iter().enumerate() {
+            let (chunks, warnings) = line?;
+            let mut ambiguous = false;
+            for warning in warnings {
+                let is_ambiguous = matches!(warning, ztarcc_rs::Warning::Ambiguous { .. });
+                ambiguous |= is_ambiguous;
+                if args.warnings || (args.fail_on_ambiguous && is_ambiguous) {
+                    eprintln!("line {}: {}", index + 1, warning);
+                    line_warnings.push(format!("line {}: {}", index + 1, warning));
+                }
+            }
+            ambiguous_lines += usize::from(ambiguous);
+            for chunk in chunks {
+                output.write_all(&encode_output(&args.output_encoding, &chunk)?)?;
+            }
+        }
+        run_warnings.extend(line_warnings);
+        if args.fail_on_ambiguous && ambiguous_lines > 0 {
+            failure = Some(anyhow!(format!(
+                "{} lines had ambiguous conversions",
+                ambiguous_lines
+            )));
+        }
+    } else {
+        let mut progress = args
+            .progress
+            .then(|| Progress::new(&args.input.to_string_lossy(), decoded.len()));
+        let batch_size = match progress {
+            Some(_) => progress::BATCH_SIZE,
+            None => usize::MAX,
+        };
+        let mut offset = 0;
+        for batch in progress::batches(&input_lines, batch_size) {
+            let len: usize = batch.iter().map(|line| line.len()).sum();
+            if args.manifest.is_none() && args.to_custom.is_none() && !args.greedy {
+                let text = &decoded[offset..offset + len];
+                for chunk in engine.convert_parallel(from_script, to_script, text)? {
+                    output.write_all(&encode_output(&args.output_encoding, &chunk)?)?;
+                }
+            } else {
+                let lines: Vec<_> = batch
+                    .par_iter()
+                    .map(|line| convert_segments(line))
+                    .collect();
+                for line in lines {
+                    for chunk in line? {
+                        output.write_all(&encode_output(&args.output_encoding, &chunk)?)?;
+                    }
+                }
+            }
+            offset += len;
+            if let Some(progress) = &mut progress {
+                progress.advance(len);
+            }
+        }
+        if let Some(progress) = &mut progress {
+            progress.finish();
+        }
+    }
+    output.flush()?;
+    if let Some(manifest_path) = &args.manifest {
+        let manifest = Manifest {
+            version: env!("CARGO_PKG_VERSION"),
+            from: from_name.to_owned(),
+            to: to_name.to_owned(),
+            files: vec![FileRecord {
+                input: args.input.to_string_lossy().into_owned(),
+                input_encoding: input_encoding.to_owned(),
+                input_sha256: manifest::sha256(&input),
+                output: output_path.to_string_lossy().into_owned(),
+                output_encoding: args.output_encoding.name().to_owned(),
+                output_sha256: output.sha256(),
+                substitutions: substitutions.into_inner(),
+                warnings: run_warnings,
+            }],
+        };
+        manifest.write(manifest_path)?;
+    }
+    failure.map_or(Ok(()), Err)
 }
 
 fn main() {
-    setup().unwrap();
+    if let Err(error) = setup() {
+        if errors::is_broken_pipe(&error) {
+            return;
+        }
+        eprintln!("ztarcc: {:#}", error);
+        std::process::exit(errors::exit_code(&error));
+    }
 }
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{fs, io::Write, path::Path};
+use ztarcc_rs::ConversionSink;
+
+/// A record of what a run did, written with `--manifest` for downstream automation.
+#[derive(Serialize, Debug)]
+pub struct Manifest {
+    /// The version of ztarcc that made the run.
+    pub version: &'static str,
+    /// The input script.
+    pub from: String,
+    /// The output script, or custom script.
+    pub to: String,
+    /// Every file converted, in order.
+    pub files: Vec<FileRecord>,
+}
+
+/// What was done with a single input file.
+#[derive(Serialize, Debug)]
+pub struct FileRecord {
+    /// The input path, or "-" for standard in.
+    pub input: String,
+    /// The encoding the input was decoded from.
+    pub input_encoding: String,
+    /// The SHA-256 of the input bytes, in hexadecimal.
+    pub input_sha256: String,
+    /// The output path, or "-" for standard out.
+    pub output: String,
+    /// The encoding the output was written in.
+    pub output_encoding: String,
+    /// The SHA-256 of the output bytes, in hexadecimal.
+    pub output_sha256: String,
+    /// The number of phrases that were changed.
+    pub substitutions: usize,
+    /// The warnings reported during the run.
+    pub warnings: Vec<String>,
+}
+
+impl Manifest {
+    /// Write the manifest as JSON to the given path.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").context(format!("writing manifest {}", path.display()))
+    }
+}
+
+/// Collects the converted segments of some text, counting the phrases that were changed so
+/// that the manifest and batch statistics record what the conversion that wrote the output did.
+#[derive(Default)]
+pub struct CountingSink {
+    pub segments: Vec<String>,
+    /// The number of phrases changed.
+    pub substitutions: usize,
+    /// The number of characters in the phrases changed.
+    pub changed_characters: usize,
+}
+
+impl ConversionSink for CountingSink {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.segments.push(text.to_owned());
+        Ok(())
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        self.segments.push(converted.to_owned());
+        self.substitutions += 1;
+        self.changed_characters += original.chars().count();
+        Ok(())
+    }
+}
+
+/// Format a SHA-256 digest as hexadecimal.
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The SHA-256 of some bytes, in hexadecimal.
+pub fn sha256(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// A writer that hashes everything written through it.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The SHA-256 of everything written so far, in hexadecimal.
+    pub fn sha256(&self) -> String {
+        hex(&self.hasher.clone().finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::Result;
+
+/// Split a line into its content and its line ending.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let content = line.trim_end_matches(['\r', '\n']);
+    (content, &line[content.len()..])
+}
+
+/// If the line opens a fenced code block, return the fence character and length.
+fn code_fence(content: &str) -> Option<(char, usize)> {
+    let trimmed = content.trim_start_matches(' ');
+    if content.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let fence = trimmed
+        .chars()
+        .next()
+        .filter(|ch| *ch == '`' || *ch == '~')?;
+    let length = trimmed.chars().take_while(|ch| *ch == fence).count();
+    (length >= 3).then_some((fence, length))
+}
+
+/// Find the end of the parenthesized link destination starting at `rest` (which begins with
+/// `(`), allowing nested parentheses.
+fn destination_end(rest: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, ch) in rest.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// If `rest` starts with an autolink such as `<https://example.com>`, return its length.
+fn autolink_end(rest: &str) -> Option<usize> {
+    let end = rest.find('>')?;
+    let inner = &rest[1..end];
+    let is_link = (inner.contains("://") || inner.contains('@'))
+        && !inner.contains(|ch: char| ch.is_whitespace() || ch == '<');
+    is_link.then_some(end + 1)
+}
+
+/// Convert the prose in a block of inline Markdown, leaving code spans, link destinations, and
+/// autolinks untouched.
+fn convert_inline(text: &str, convert: &impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut prose_start = 0;
+    let mut offset = 0;
+    while let Some(ch) = text[offset..].chars().next() {
+        let rest = &text[offset..];
+        let verbatim = match ch {
+            '`' => {
+                let ticks = rest.chars().take_while(|ch| *ch == '`').count();
+                let fence = &rest[..ticks];
+                // A code span closes at the next run of exactly as many backticks.
+                let mut search = ticks;
+                let mut end = None;
+                while let Some(i) = rest[search..].find(fence) {
+                    let close = search + i;
+                    let run = rest[close..].chars().take_while(|ch| *ch == '`').count();
+                    if run == ticks {
+                        end = Some(close + ticks);
+                        break;
+                    }
+                    search = close + run;
+                }
+                Some(end.unwrap_or(ticks))
+            }
+            '(' if text[..offset].ends_with(']') => destination_end(rest),
+            '<' => autolink_end(rest),
+            _ => None,
+        };
+        match verbatim {
+            Some(len) => {
+                output.push_str(&convert(&text[prose_start..offset])?);
+                output.push_str(&rest[..len]);
+                offset += len;
+                prose_start = offset;
+            }
+            None => offset += ch.len_utf8(),
+        }
+    }
+    output.push_str(&convert(&text[prose_start..])?);
+    Ok(output)
+}
+
+/// Convert a line of YAML front matter, leaving the key of a `key: value` line untouched.
+fn convert_front_matter(
+    content: &str,
+    convert: &impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    match content.find(':') {
+        Some(colon) if !content.trim_start().starts_with('-') => Ok(format!(
+            "{}{}",
+            &content[..colon + 1],
+            convert(&content[colon + 1..])?
+        )),
+        _ => convert(content),
+    }
+}
+
+/// Convert the prose in a Markdown document.  Fenced code blocks, inline code spans, link
+/// destinations, autolinks, the destinations of link reference definitions, and the keys of YAML
+/// front matter are copied verbatim.
+pub fn convert_markdown(input: &str, convert: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut lines = input.split_inclusive('\n').peekable();
+
+    if lines.peek().map(|line| split_line_ending(line).0) == Some("---") {
+        output.push_str(lines.next().unwrap_or_default());
+        for line in lines.by_ref() {
+            let (content, ending) = split_line_ending(line);
+            if content == "---" || content == "..." {
+                output.push_str(line);
+                break;
+            }
+            output.push_str(&convert_front_matter(content, &convert)?);
+            output.push_str(ending);
+        }
+    }
+
+    let mut fence: Option<(char, usize)> = None;
+    for line in lines {
+        let (content, ending) = split_line_ending(line);
+        if let Some((fence_char, fence_length)) = fence {
+            if code_fence(content).is_some_and(|(ch, length)| {
+                ch == fence_char && length >= fence_length && content.trim().len() == length
+            }) {
+                fence = None;
+            }
+            output.push_str(line);
+            continue;
+        }
+        if let Some(opened) = code_fence(content) {
+            fence = Some(opened);
+            output.push_str(line);
+            continue;
+        }
+        let trimmed = content.trim_start();
+        let definition = trimmed
+            .strip_prefix('[')
+            .and_then(|label| label.find("]:"))
+            .map(|end| content.len() - trimmed.len() + end + 3);
+        match definition {
+            Some(label_end) => {
+                output.push_str(&convert(&content[..label_end])?);
+                output.push_str(&content[label_end..]);
+            }
+            None => output.push_str(&convert_inline(content, &convert)?),
+        }
+        output.push_str(ending);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(text: &str) -> Result<String> {
+        Ok(text.replace("软件", "軟體"))
+    }
+
+    #[test]
+    fn test_convert_inline() -> Result<()> {
+        assert_eq!(
+            "軟體 `软件` ``a `软件` b`` 軟體",
+            convert_inline("软件 `软件` ``a `软件` b`` 软件", &convert)?
+        );
+        // Only parentheses straight after a link's text hold a destination.
+        assert_eq!(
+            "[軟體](/软件/(1)) <https://软件.cn> 軟體(軟體)",
+            convert_inline("[软件](/软件/(1)) <https://软件.cn> 软件(软件)", &convert)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_markdown() -> Result<()> {
+        let input =
+            "---\n软件: 软件\n---\n# 软件\n\n```软件\n软件\n```\n\n~~~~\n软件\n~~~\n软件\n~~~~\n\
+                     [软件]: /软件 \"软件\"\n";
+        assert_eq!(
+            "---\n软件: 軟體\n---\n# 軟體\n\n```软件\n软件\n```\n\n~~~~\n软件\n~~~\n软件\n~~~~\n\
+             [軟體]: /软件 \"软件\"\n",
+            convert_markdown(input, convert)?
+        );
+
+        Ok(())
+    }
+}
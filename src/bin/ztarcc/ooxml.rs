@@ -0,0 +1,113 @@
+use crate::epub::convert_elements;
+use crate::resume::{convert_entry, Resume};
+use anyhow::{Context, Result};
+use std::io::{Cursor, Read, Write};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+/// The text elements converted in each part of a document, by the prefix of
+/// the part's name: runs of text in Word documents, text in DrawingML shapes
+/// and charts, and shared and inline strings in spreadsheets.
+const TEXT_ELEMENTS: [(&str, &[&str]); 3] = [
+    ("word/", &["w:t", "a:t"]),
+    ("ppt/", &["a:t"]),
+    ("xl/", &["t", "a:t"]),
+];
+
+/// Convert an Office Open XML document (`.docx`, `.xlsx`, or `.pptx`): the
+/// text of each run in its XML parts is converted in place, so formatting,
+/// styles, and everything else are kept.  Runs are converted separately, so
+/// a phrase split between differently formatted runs is converted a piece
+/// at a time.  Parts that aren't XML, such as images, are copied as they are.
+/// With `resume`, converted parts are kept and reused.
+pub fn convert_ooxml(
+    input: &[u8],
+    mut resume: Option<&mut Resume>,
+    convert_text: impl Fn(&str) -> Result<String>,
+) -> Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(input)).context("reading document")?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for index in 0..archive.len() {
+        let name = archive.by_index_raw(index)?.name().to_owned();
+        let elements = TEXT_ELEMENTS
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map(|(_, elements)| *elements)
+            .filter(|_| name.to_ascii_lowercase().ends_with(".xml"));
+        let Some(elements) = elements else {
+            writer.raw_copy_file(archive.by_index_raw(index)?)?;
+            continue;
+        };
+        let crc32 = archive.by_index_raw(index)?.crc32();
+        let converted = convert_entry(resume.as_deref_mut(), &name, crc32, || {
+            let mut converted = String::new();
+            archive
+                .by_index(index)?
+                .read_to_string(&mut converted)
+                .context(format!("reading {}", name))?;
+            for element in elements {
+                converted = convert_elements(&converted, element, &convert_text)?;
+            }
+            Ok(converted)
+        })?;
+        writer.start_file(name.as_str(), SimpleFileOptions::default())?;
+        writer.write_all(&converted)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_ooxml() -> Result<()> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let parts = [
+            ("[Content_Types].xml", "<t>软件</t>"),
+            (
+                "xl/sharedStrings.xml",
+                "<sst><si><t>软件</t></si><tableParts>软件</tableParts>\
+                 <text>软件</text><t/>软件<t xml:space=\"preserve\"> 软件</t></sst>",
+            ),
+            (
+                "word/document.xml",
+                "<w:p><w:r><w:t>软件</w:t></w:r><w:tab/></w:p>",
+            ),
+            ("xl/media/image1.png", "<t>软件</t>"),
+        ];
+        for (name, content) in parts {
+            writer.start_file(name, SimpleFileOptions::default())?;
+            writer.write_all(content.as_bytes())?;
+        }
+        let input = writer.finish()?.into_inner();
+
+        let output = convert_ooxml(&input, None, |text| Ok(text.replace("软件", "軟體")))?;
+        let mut archive = ZipArchive::new(Cursor::new(output))?;
+        let mut converted = Vec::new();
+        for index in 0..archive.len() {
+            let mut part = archive.by_index(index)?;
+            let mut text = String::new();
+            part.read_to_string(&mut text)?;
+            converted.push((part.name().to_owned(), text));
+        }
+        let expected = [
+            ("[Content_Types].xml", "<t>软件</t>"),
+            (
+                "xl/sharedStrings.xml",
+                "<sst><si><t>軟體</t></si><tableParts>软件</tableParts>\
+                 <text>软件</text><t/>软件<t xml:space=\"preserve\"> 軟體</t></sst>",
+            ),
+            (
+                "word/document.xml",
+                "<w:p><w:r><w:t>軟體</w:t></w:r><w:tab/></w:p>",
+            ),
+            ("xl/media/image1.png", "<t>软件</t>"),
+        ];
+        assert_eq!(
+            expected.map(|(name, text)| (name.to_owned(), text.to_owned())),
+            converted[..]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// How often the progress line is redrawn.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The size, in bytes, of the batches the input is converted in when progress is shown.
+pub const BATCH_SIZE: usize = 4 << 20;
+
+/// A progress line on standard error, showing how much of the input has been converted, the
+/// throughput, and the estimated time left.
+pub struct Progress {
+    name: String,
+    total: usize,
+    done: usize,
+    started: Instant,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(name: &str, total: usize) -> Self {
+        Progress {
+            name: name.to_owned(),
+            total,
+            done: 0,
+            started: Instant::now(),
+            drawn: None,
+        }
+    }
+
+    /// Record that another `bytes` of the input have been converted.
+    pub fn advance(&mut self, bytes: usize) {
+        self.done += bytes;
+        match self.drawn {
+            Some(drawn) if drawn.elapsed() < REDRAW_INTERVAL => {}
+            _ => self.draw(),
+        }
+    }
+
+    /// Draw the final state of the progress line and end it.
+    pub fn finish(&mut self) {
+        self.draw();
+        eprintln!();
+    }
+
+    fn draw(&mut self) {
+        const MIB: f64 = (1 << 20) as f64;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = match elapsed > 0.0 {
+            true => self.done as f64 / elapsed,
+            false => 0.0,
+        };
+        let percent = match self.total {
+            0 => 100.0,
+            total => self.done as f64 * 100.0 / total as f64,
+        };
+        let eta = match rate > 0.0 {
+            true => format!("{:.0}s", (self.total - self.done) as f64 / rate),
+            false => "?".to_owned(),
+        };
+        eprint!(
+            "\r{}: {:5.1}% {:.1}/{:.1} MiB, {:.1} MiB/s, ETA {}  ",
+            self.name,
+            percent,
+            self.done as f64 / MIB,
+            self.total as f64 / MIB,
+            rate / MIB,
+            eta
+        );
+        let _ = io::stderr().flush();
+        self.drawn = Some(Instant::now());
+    }
+}
+
+/// Split the lines of the input into batches of about `size` bytes each.
+pub fn batches<'a>(lines: &'a [&'a str], size: usize) -> Vec<&'a [&'a str]> {
+    let mut batches = Vec::new();
+    let (mut start, mut bytes) = (0, 0);
+    for (index, line) in lines.iter().enumerate() {
+        bytes += line.len();
+        if bytes >= size {
+            batches.push(&lines[start..=index]);
+            (start, bytes) = (index + 1, 0);
+        }
+    }
+    if start < lines.len() {
+        batches.push(&lines[start..]);
+    }
+    batches
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+use ztarcc_rs::{Engine, SpanSink};
+
+/// A conversion of a file, or of a line with `--output-format json-lines`,
+/// written as a line of JSON for build systems and checks to consume.
+#[derive(Serialize, Debug)]
+pub struct Record<'a> {
+    /// The input path, or "-" for standard in.
+    pub input: &'a str,
+    /// The line number, counting from 1, with `--output-format json-lines`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The encoding the input was decoded from.
+    pub input_encoding: &'a str,
+    /// The input script.
+    pub from: &'a str,
+    /// The output script.
+    pub to: &'a str,
+    /// The converted text.
+    pub text: String,
+    /// Every span of the input that was changed, in order.
+    pub changes: Vec<Change>,
+}
+
+/// A span of the input that was changed.  Offsets are in bytes of UTF-8,
+/// from the start of the file or line.
+#[derive(Serialize, Debug)]
+pub struct Change {
+    /// Where the span starts in the input.
+    pub start: usize,
+    /// Where the span ends in the input.
+    pub end: usize,
+    /// Where the span's conversion starts in the output.
+    pub output_start: usize,
+    /// Where the span's conversion ends in the output.
+    pub output_end: usize,
+    /// The span as it was in the input.
+    pub original: String,
+    /// The span as it was converted.
+    pub converted: String,
+}
+
+/// Convert some text between two scripts given by name, returning the
+/// converted text and the spans that were changed.
+pub fn convert(engine: &Engine, from: &str, to: &str, text: &str) -> Result<(String, Vec<Change>)> {
+    let mut sink = SpanSink::default();
+    engine.convert_named_into(from, to, text, &mut sink)?;
+    let mut converted = String::with_capacity(text.len());
+    let mut changes = Vec::new();
+    for span in sink.spans {
+        let original = &text[span.start..span.end];
+        if span.converted != original {
+            changes.push(Change {
+                start: span.start,
+                end: span.end,
+                output_start: converted.len(),
+                output_end: converted.len() + span.converted.len(),
+                original: original.to_owned(),
+                converted: span.converted.clone(),
+            });
+        }
+        converted.push_str(&span.converted);
+    }
+    Ok((converted, changes))
+}
+
+/// Write records as JSON, one to a line.
+pub fn write_records(output: &mut dyn Write, records: &[Record]) -> Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut *output, record)?;
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
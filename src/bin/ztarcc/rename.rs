@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Add every file and directory under `dir` to `paths`, each directory's
+/// contents before the directory itself, without following symbolic links.
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).context(format!("reading {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            walk(&path, paths)?;
+        }
+        paths.push(path);
+    }
+    Ok(())
+}
+
+/// Convert the names of every file and directory under `root`, listing each
+/// rename on `output` as "OLD -> NEW", relative to `root`.  With `dry_run`,
+/// nothing is renamed.  If two names would convert to the same name, or a
+/// converted name is already taken, nothing is renamed and every collision
+/// is reported.  Names that are not valid Unicode are left alone.
+pub fn rename(
+    root: &Path,
+    dry_run: bool,
+    output: &mut dyn Write,
+    convert: impl Fn(&str) -> Result<String>,
+) -> Result<()> {
+    let mut paths = Vec::new();
+    walk(root, &mut paths)?;
+    let mut renames = Vec::new();
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let converted = convert(name)?;
+        if converted != name {
+            let target = path.with_file_name(converted);
+            renames.push((path, target));
+        }
+    }
+
+    let mut sources: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for (path, target) in &renames {
+        sources
+            .entry(target.as_path())
+            .or_default()
+            .push(path.as_path());
+    }
+    let mut collisions: Vec<String> = sources
+        .iter()
+        .filter(|(target, paths)| paths.len() > 1 || target.symlink_metadata().is_ok())
+        .map(|(target, paths)| {
+            let paths: Vec<_> = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            format!("{} <- {}", target.display(), paths.join(", "))
+        })
+        .collect();
+    if !collisions.is_empty() {
+        collisions.sort();
+        for collision in &collisions {
+            eprintln!("collision: {}", collision);
+        }
+        return Err(anyhow!(format!(
+            "{} converted names collide; nothing was renamed",
+            collisions.len()
+        )));
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    for (path, target) in &renames {
+        writeln!(output, "{} -> {}", relative(path), relative(target))?;
+        if !dry_run {
+            fs::rename(path, target).context(format!("renaming {}", path.display()))?;
+        }
+    }
+    Ok(output.flush()?)
+}
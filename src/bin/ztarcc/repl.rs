@@ -0,0 +1,134 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::io::{BufRead, Write};
+use ztarcc_rs::{CutOptions, Engine};
+
+use crate::Script;
+
+/// The commands the REPL understands, as printed by `:help`.
+const HELP: &str = "\
+Lines are converted and printed.  Commands:
+  :from SCRIPT       convert from SCRIPT (cn, tw, hk, or st)
+  :to SCRIPT         convert to SCRIPT
+  :swap              swap the input and output scripts
+  :greedy            toggle converting by longest match, without segmenting
+  :hmm               toggle Jieba's hidden Markov model for unknown words
+  :candidates WORD   show every way WORD can be converted
+  :help              show this help
+  :quit              exit";
+
+/// The state of the REPL between lines.
+struct Session {
+    engine: Engine,
+    from: Script,
+    to: Script,
+    greedy: bool,
+    hmm: bool,
+}
+
+impl Session {
+    /// Convert a line with the current scripts and options.
+    fn convert(&self, text: &str) -> Result<String> {
+        let (from, to) = ((&self.from).into(), (&self.to).into());
+        match self.greedy {
+            true => self.engine.convert_greedy(from, to, text),
+            false => Ok(self.engine.convert(from, to, text)?.concat()),
+        }
+    }
+
+    /// Run a command, without its leading ':', and return what to print, or `None` to exit.
+    fn command(&mut self, command: &str) -> Result<Option<String>> {
+        let mut words = command.split_whitespace();
+        let parse = |name: &str| Script::from_str(name, true).map_err(anyhow::Error::msg);
+        let message = match (words.next(), words.next()) {
+            (Some("from"), Some(name)) => {
+                self.from = parse(name)?;
+                self.scripts()
+            }
+            (Some("to"), Some(name)) => {
+                self.to = parse(name)?;
+                self.scripts()
+            }
+            (Some("swap"), None) => {
+                std::mem::swap(&mut self.from, &mut self.to);
+                self.scripts()
+            }
+            (Some("greedy"), None) => {
+                self.greedy = !self.greedy;
+                format!("greedy {}", on_off(self.greedy))
+            }
+            (Some("hmm"), None) => {
+                self.hmm = !self.hmm;
+                let mut cut = CutOptions::default();
+                cut.hmm = self.hmm;
+                self.engine = Engine::with_cut_options(cut);
+                format!("hmm {}", on_off(self.hmm))
+            }
+            (Some("candidates"), Some(word)) => {
+                let (from, to) = ((&self.from).into(), (&self.to).into());
+                self.engine.candidates(from, to, word)?.join(" ")
+            }
+            (Some("help"), None) => HELP.to_owned(),
+            (Some("quit"), None) => return Ok(None),
+            _ => format!("unknown command :{}; try :help", command),
+        };
+        Ok(Some(message))
+    }
+
+    /// The current scripts, as printed after changing them.
+    fn scripts(&self) -> String {
+        format!("converting {} to {}", self.from.name(), self.to.name())
+    }
+}
+
+/// The name of a toggle's state.
+fn on_off(on: bool) -> &'static str {
+    match on {
+        true => "on",
+        false => "off",
+    }
+}
+
+/// Convert lines read from `input` until it ends or `:quit`, printing each converted line, and
+/// a prompt before each line if `prompt` is set.  Lines starting with ':' are commands that
+/// change the scripts and options; errors are printed without ending the session.
+pub fn repl(
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    prompt: bool,
+    from: Script,
+    to: Script,
+) -> Result<()> {
+    let mut session = Session {
+        engine: Engine::new(),
+        from,
+        to,
+        greedy: false,
+        hmm: true,
+    };
+    let mut line = String::new();
+    loop {
+        if prompt {
+            write!(output, "{}→{}> ", session.from.name(), session.to.name())?;
+            output.flush()?;
+        }
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            if prompt {
+                writeln!(output)?;
+            }
+            return Ok(output.flush()?);
+        }
+        let text = line.trim_end_matches(['\r', '\n']);
+        let result = match text.strip_prefix(':') {
+            Some(command) => session.command(command),
+            None => session.convert(text).map(Some),
+        };
+        match result {
+            Ok(Some(message)) => writeln!(output, "{}", message)?,
+            Ok(None) => return Ok(output.flush()?),
+            Err(error) => writeln!(output, "error: {:#}", error)?,
+        }
+        output.flush()?;
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// The name of the progress manifest in a resume directory.
+const MANIFEST: &str = "manifest.jsonl";
+
+/// The first line of a progress manifest.
+#[derive(Serialize, Deserialize, Debug)]
+struct Header {
+    /// The scripts and options the entries were converted with.
+    conversion: String,
+}
+
+/// A line of a progress manifest for an entry that has been converted.
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    /// The name of the entry in the archive.
+    name: String,
+    /// The CRC-32 of the entry in the input, as recorded in the archive.
+    crc32: u32,
+    /// The file in the resume directory holding the converted entry.
+    file: String,
+}
+
+/// The converted entries of an EPUB or Office file kept on disk for `--resume`, so that an
+/// interrupted conversion picks up from the last entry it finished instead of starting over.
+/// Each entry is written to its own file, then recorded in a progress manifest.
+pub struct Resume {
+    dir: PathBuf,
+    manifest: File,
+    done: HashMap<(String, u32), String>,
+}
+
+impl Resume {
+    /// Open the resume directory `dir`, creating it if needed.  Entries converted before are
+    /// reused only if they were converted the same way, as described by `conversion`, and their
+    /// input is unchanged; otherwise the directory starts over.
+    pub fn open(dir: &Path, conversion: &str) -> Result<Self> {
+        fs::create_dir_all(dir).context(format!("creating {}", dir.display()))?;
+        let path = dir.join(MANIFEST);
+        let mut entries = Vec::new();
+        let mut current = false;
+        // The length of the manifest up to the end of its last complete line.
+        let mut good = 0;
+        if let Ok(file) = File::open(&path) {
+            let mut reader = BufReader::new(file);
+            let mut line = String::new();
+            if reader.read_line(&mut line)? > 0 && line.ends_with('\n') {
+                current = serde_json::from_str::<Header>(&line)
+                    .is_ok_and(|header| header.conversion == conversion);
+                good = line.len() as u64;
+            }
+            // A run interrupted while recording an entry may leave a partial last line.
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+                    break;
+                }
+                let Ok(entry) = serde_json::from_str::<Entry>(&line) else {
+                    break;
+                };
+                good += line.len() as u64;
+                entries.push(entry);
+            }
+        }
+        let mut manifest = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .truncate(false)
+            .open(&path)
+            .context(format!("opening {}", path.display()))?;
+        let mut done = HashMap::new();
+        if current {
+            manifest.set_len(good)?;
+            done.extend(
+                entries
+                    .into_iter()
+                    .map(|entry| ((entry.name, entry.crc32), entry.file)),
+            );
+        } else {
+            for entry in entries.iter().filter(|entry| is_entry_file(&entry.file)) {
+                let _ = fs::remove_file(dir.join(&entry.file));
+            }
+            manifest.set_len(0)?;
+            let header = Header {
+                conversion: conversion.to_owned(),
+            };
+            writeln!(manifest, "{}", serde_json::to_string(&header)?)?;
+        }
+        Ok(Resume {
+            dir: dir.to_owned(),
+            manifest,
+            done,
+        })
+    }
+
+    /// The converted form of an entry, if it was converted before from the same input.
+    pub fn get(&self, name: &str, crc32: u32) -> Result<Option<Vec<u8>>> {
+        let Some(file) = self.done.get(&(name.to_owned(), crc32)) else {
+            return Ok(None);
+        };
+        let path = self.dir.join(file);
+        Ok(Some(
+            fs::read(&path).context(format!("reading {}", path.display()))?,
+        ))
+    }
+
+    /// Keep the converted form of an entry, so that a later run can reuse it.
+    pub fn finish(&mut self, name: &str, crc32: u32, converted: &[u8]) -> Result<()> {
+        let file = self.done.len().to_string();
+        let path = self.dir.join(&file);
+        fs::write(&path, converted).context(format!("writing {}", path.display()))?;
+        let entry = Entry {
+            name: name.to_owned(),
+            crc32,
+            file,
+        };
+        writeln!(self.manifest, "{}", serde_json::to_string(&entry)?)?;
+        self.manifest.sync_data()?;
+        self.done.insert((entry.name, crc32), entry.file);
+        Ok(())
+    }
+
+    /// Remove the manifest and the converted entries, once the whole output has been written,
+    /// then the resume directory itself if nothing else is in it.
+    pub fn remove(self) -> Result<()> {
+        for file in self.done.values().filter(|file| is_entry_file(file)) {
+            let path = self.dir.join(file);
+            fs::remove_file(&path).context(format!("removing {}", path.display()))?;
+        }
+        drop(self.manifest);
+        let path = self.dir.join(MANIFEST);
+        fs::remove_file(&path).context(format!("removing {}", path.display()))?;
+        if fs::read_dir(&self.dir)?.next().is_none() {
+            fs::remove_dir(&self.dir).context(format!("removing {}", self.dir.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `file` names a converted entry, which are numbered from 0.
+fn is_entry_file(file: &str) -> bool {
+    !file.is_empty() && file.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// Convert an archive entry with `convert`, or with `resume`, reuse its converted form from an
+/// earlier run and keep it for a later one.
+pub fn convert_entry(
+    resume: Option<&mut Resume>,
+    name: &str,
+    crc32: u32,
+    convert: impl FnOnce() -> Result<String>,
+) -> Result<Vec<u8>> {
+    let Some(resume) = resume else {
+        return Ok(convert()?.into_bytes());
+    };
+    if let Some(converted) = resume.get(name, crc32)? {
+        return Ok(converted);
+    }
+    let converted = convert()?.into_bytes();
+    resume.finish(name, crc32, &converted)?;
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("ztarcc-resume-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("notes.txt"), "kept")?;
+
+        let mut resume = Resume::open(&dir, "cn tw")?;
+        resume.finish("a.xhtml", 1, "甲".as_bytes())?;
+        drop(resume);
+        // A partial line left by an interrupted run is dropped before appending.
+        OpenOptions::new()
+            .append(true)
+            .open(dir.join(MANIFEST))?
+            .write_all(b"{\"name\":")?;
+        let mut resume = Resume::open(&dir, "cn tw")?;
+        assert_eq!(Some("甲".as_bytes().to_vec()), resume.get("a.xhtml", 1)?);
+        assert_eq!(None, resume.get("a.xhtml", 2)?);
+        resume.finish("b.xhtml", 2, "乙".as_bytes())?;
+        drop(resume);
+
+        let resume = Resume::open(&dir, "cn tw")?;
+        assert_eq!(Some("乙".as_bytes().to_vec()), resume.get("b.xhtml", 2)?);
+        resume.remove()?;
+        // Only the files written for resuming are removed.
+        let left = fs::read_dir(&dir)?
+            .map(|entry| Ok(entry?.file_name()))
+            .collect::<Result<Vec<_>>>();
+        fs::remove_dir_all(&dir)?;
+        assert_eq!(vec!["notes.txt"], left?);
+
+        let dir = std::env::temp_dir().join(format!("ztarcc-resume-{}-new", std::process::id()));
+        let mut resume = Resume::open(&dir, "cn tw")?;
+        resume.finish("a.xhtml", 1, "甲".as_bytes())?;
+        let resume = Resume::open(&dir, "cn hk")?;
+        assert_eq!(None, resume.get("a.xhtml", 1)?);
+        resume.remove()?;
+        assert!(!dir.exists());
+
+        Ok(())
+    }
+}
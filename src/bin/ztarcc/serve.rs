@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tiny_http::{Header, Method, Request, Response, Server};
+use ztarcc_rs::{CustomScript, Engine};
+
+/// The largest request body accepted, in bytes.
+const MAX_BODY: u64 = 16 << 20;
+
+/// How often the custom script files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The body of a `POST /convert` request.
+#[derive(Deserialize, Debug)]
+struct ConvertRequest {
+    /// The input script, built-in or custom.
+    from: String,
+    /// The output script, built-in or custom.
+    to: String,
+    /// The text to convert.
+    text: String,
+}
+
+/// The body of a successful response.
+#[derive(Serialize, Debug)]
+struct ConvertResponse {
+    /// The converted text.
+    text: String,
+}
+
+/// The body of a successful `POST /reload` response.
+#[derive(Serialize, Debug)]
+struct ReloadResponse {
+    /// How many custom scripts were reloaded.
+    reloaded: usize,
+}
+
+/// The body of a response to a request that failed.
+#[derive(Serialize, Debug)]
+struct ErrorResponse {
+    /// What went wrong.
+    error: String,
+}
+
+/// Handle a `POST /convert` request with the body read from `reader`, returning the status code
+/// and JSON body to respond with.
+fn convert(engine: &Engine, reader: impl Read) -> (u16, String) {
+    let mut body = Vec::new();
+    // Read one byte more than allowed, to tell a body at the limit from one over it.
+    match reader.take(MAX_BODY + 1).read_to_end(&mut body) {
+        Ok(length) if length as u64 > MAX_BODY => {
+            return error_response(413, &format!("the body is over {} bytes", MAX_BODY))
+        }
+        Ok(_) => {}
+        Err(error) => return error_response(400, &error.to_string()),
+    }
+    let result = serde_json::from_slice::<ConvertRequest>(&body)
+        .map_err(anyhow::Error::from)
+        .and_then(|request| engine.convert_named(&request.from, &request.to, &request.text));
+    let response = match result {
+        Ok(segments) => serde_json::to_string(&ConvertResponse {
+            text: segments.concat(),
+        }),
+        Err(error) => return error_response(400, &format!("{:#}", error)),
+    };
+    match response {
+        Ok(json) => (200, json),
+        Err(error) => error_response(500, &error.to_string()),
+    }
+}
+
+/// A custom script definition file being served, with the files it was last read from and their
+/// modification times then.
+struct ScriptFile {
+    definition: PathBuf,
+    files: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+/// The modification times of some files, with `None` for any that cannot be read.
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+/// The custom script files registered with the server, which are reloaded when they change.
+struct Scripts {
+    files: Mutex<Vec<ScriptFile>>,
+}
+
+impl Scripts {
+    /// Read and register each custom script definition file.
+    fn load(engine: &Engine, definitions: &[PathBuf]) -> Result<Self> {
+        let mut files = Vec::new();
+        for definition in definitions {
+            let script = CustomScript::from_definition_file(definition)?;
+            let script_files = script.files().to_vec();
+            engine.register_script(script)?;
+            files.push(ScriptFile {
+                definition: definition.clone(),
+                modified: modified(&script_files),
+                files: script_files,
+            });
+        }
+        Ok(Scripts {
+            files: Mutex::new(files),
+        })
+    }
+
+    /// Reread and register every script whose files changed since it was last read, or every
+    /// script if `all` is set, returning how many were reloaded.  Registering replaces a script
+    /// atomically, so requests that are already converting finish with the old version.  A
+    /// script that fails to load is left as it was, and tried again when its files next change;
+    /// the errors are returned after trying the rest.
+    fn reload(&self, engine: &Engine, all: bool) -> (usize, Vec<anyhow::Error>) {
+        let mut files = match self.files.lock() {
+            Ok(files) => files,
+            Err(_) => return (0, vec![anyhow!("custom script files are poisoned")]),
+        };
+        let (mut reloaded, mut errors) = (0, Vec::new());
+        for file in files.iter_mut() {
+            let modified_now = modified(&file.files);
+            if !all && modified_now == file.modified {
+                continue;
+            }
+            file.modified = modified_now;
+            let result = CustomScript::from_definition_file(&file.definition).and_then(|script| {
+                let script_files = script.files().to_vec();
+                engine.register_script(script)?;
+                Ok(script_files)
+            });
+            match result.context(format!("reloading {}", file.definition.display())) {
+                Ok(script_files) => {
+                    file.modified = modified(&script_files);
+                    file.files = script_files;
+                    reloaded += 1;
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        (reloaded, errors)
+    }
+
+    /// Reload changed scripts every [POLL_INTERVAL], until the process ends.
+    fn watch(&self, engine: &Engine) {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let (reloaded, errors) = self.reload(engine, false);
+            if reloaded > 0 {
+                eprintln!("reloaded {} custom scripts", reloaded);
+            }
+            for error in errors {
+                eprintln!("ztarcc: {:#}", error);
+            }
+        }
+    }
+}
+
+/// Handle a `POST /reload` request by reloading every custom script file.
+fn reload(engine: &Engine, scripts: &Scripts) -> (u16, String) {
+    let (reloaded, errors) = scripts.reload(engine, true);
+    if !errors.is_empty() {
+        let messages: Vec<_> = errors.iter().map(|error| format!("{:#}", error)).collect();
+        return error_response(500, &messages.join("; "));
+    }
+    match serde_json::to_string(&ReloadResponse { reloaded }) {
+        Ok(json) => (200, json),
+        Err(error) => error_response(500, &error.to_string()),
+    }
+}
+
+/// The status code and JSON body of an error response.
+fn error_response(status: u16, error: &str) -> (u16, String) {
+    let body = ErrorResponse {
+        error: error.to_owned(),
+    };
+    (status, serde_json::to_string(&body).unwrap_or_default())
+}
+
+/// Route a request by its method and URL, returning the status code and JSON body to respond
+/// with.  Only `POST /convert` reads the body from `reader`.
+fn route(
+    engine: &Engine,
+    scripts: &Scripts,
+    method: &Method,
+    url: &str,
+    reader: impl Read,
+) -> (u16, String) {
+    match (method, url) {
+        (Method::Post, "/convert") => convert(engine, reader),
+        (Method::Post, "/reload") => reload(engine, scripts),
+        (_, "/convert" | "/reload") => error_response(405, "use POST"),
+        _ => error_response(404, "not found; use POST /convert"),
+    }
+}
+
+/// Route a request and respond to it.
+fn handle(engine: &Engine, scripts: &Scripts, mut request: Request) -> Result<()> {
+    let (method, url) = (request.method().clone(), request.url().to_owned());
+    let (status, body) = route(engine, scripts, &method, &url, request.as_reader());
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .map_err(|_| anyhow!("invalid header"))?;
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    Ok(request.respond(response)?)
+}
+
+/// Serve `POST /convert` with a JSON body of `from`, `to`, and `text` on `host` and `port`,
+/// converting with one engine on a thread for each core until interrupted.  The custom scripts
+/// defined in `script_files` are registered, and reloaded whenever their files change or on
+/// `POST /reload`.
+pub fn serve(engine: &Engine, script_files: &[PathBuf], host: &str, port: u16) -> Result<()> {
+    let scripts = Scripts::load(engine, script_files)?;
+    let server = Server::http((host, port))
+        .map_err(|error| anyhow!(format!("listening on {}:{}: {}", host, port, error)))?;
+    eprintln!("listening on http://{}", server.server_addr());
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    std::thread::scope(|scope| {
+        if !script_files.is_empty() {
+            scope.spawn(|| scripts.watch(engine));
+        }
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    if let Err(error) = handle(engine, &scripts, request) {
+                        eprintln!("ztarcc: {:#}", error);
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_reload() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("ztarcc-serve-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("sg.txt"), "出租车\t德士\n")?;
+        fs::write(dir.join("sg.def"), "name sg\nbase cn\ndictionary sg.txt\n")?;
+        let engine = Engine::new();
+        let result = (|| {
+            let scripts = Scripts::load(&engine, &[dir.join("sg.def")])?;
+            let before = engine.convert_named("cn", "sg", "坐出租车")?.concat();
+            let (unchanged, _) = scripts.reload(&engine, false);
+
+            let dictionary = fs::File::options()
+                .write(true)
+                .truncate(true)
+                .open(dir.join("sg.txt"))?;
+            (&dictionary).write_all("出租车\t的士\n".as_bytes())?;
+            dictionary.set_modified(SystemTime::now() + Duration::from_secs(60))?;
+            let (reloaded, errors) = scripts.reload(&engine, false);
+            let after = engine.convert_named("cn", "sg", "坐出租车")?.concat();
+            anyhow::Ok((before, unchanged, reloaded, errors.len(), after))
+        })();
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(("坐德士".to_owned(), 0, 1, 0, "坐的士".to_owned()), result?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_route() -> Result<()> {
+        let engine = Engine::new();
+        let scripts = Scripts::load(&engine, &[])?;
+        let route = |method, url, body: &[u8]| route(&engine, &scripts, &method, url, body);
+
+        let body = r#"{"from": "cn", "to": "tw", "text": "软件"}"#;
+        assert_eq!(
+            (200, r#"{"text":"軟體"}"#.to_owned()),
+            route(Method::Post, "/convert", body.as_bytes())
+        );
+        assert_eq!(
+            (200, r#"{"reloaded":0}"#.to_owned()),
+            route(Method::Post, "/reload", b"")
+        );
+        let unknown = r#"{"from": "cn", "to": "xx", "text": "软件"}"#;
+        assert_eq!(400, route(Method::Post, "/convert", unknown.as_bytes()).0);
+        assert_eq!(400, route(Method::Post, "/convert", b"{").0);
+        assert_eq!(405, route(Method::Get, "/convert", b"").0);
+        assert_eq!(405, route(Method::Get, "/reload", b"").0);
+        assert_eq!(404, route(Method::Post, "/", b"").0);
+
+        // A body over the limit is refused rather than cut off and parsed.
+        let text = "软".repeat(MAX_BODY as usize / 3);
+        let body = format!(r#"{{"from": "cn", "to": "tw", "text": "{}"}}"#, text);
+        assert_eq!(413, route(Method::Post, "/convert", body.as_bytes()).0);
+
+        Ok(())
+    }
+}
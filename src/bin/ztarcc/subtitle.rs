@@ -0,0 +1,131 @@
+use anyhow::Result;
+
+/// Split a line into its content and its line ending.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let content = line.trim_end_matches(['\r', '\n']);
+    (content, &line[content.len()..])
+}
+
+/// Convert the cue text in an SRT or WebVTT file.  Cue text is every line following a timing line
+/// (one containing `-->`) in the same block; everything else, including cue numbers, timestamps,
+/// and header, note, and style blocks, is copied verbatim.
+pub fn convert_cues(input: &str, convert: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut in_cue_text = false;
+    for line in input.split_inclusive('\n') {
+        let (content, ending) = split_line_ending(line);
+        if content.trim().is_empty() {
+            in_cue_text = false;
+            output.push_str(line);
+        } else if in_cue_text {
+            output.push_str(&convert(content)?);
+            output.push_str(ending);
+        } else {
+            in_cue_text = content.contains("-->");
+            output.push_str(line);
+        }
+    }
+    Ok(output)
+}
+
+/// Convert the text of an ASS dialogue line, leaving style override blocks (such as
+/// `{\pos(10,10)}`) untouched.
+fn convert_ass_text(text: &str, convert: &impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map_or(rest.len(), |i| start + i + 1);
+        output.push_str(&convert(&rest[..start])?);
+        output.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    output.push_str(&convert(rest)?);
+    Ok(output)
+}
+
+/// Convert the dialogue text in an ASS (or SSA) file.  Only the final (text) field of `Dialogue:`
+/// lines in the `[Events]` section is converted; everything else is copied verbatim.
+pub fn convert_ass(input: &str, convert: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut in_events = false;
+    // The number of fields in an event, as declared by the `Format:` line.
+    let mut field_count = 10;
+    for line in input.split_inclusive('\n') {
+        let (content, ending) = split_line_ending(line);
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('[') {
+            in_events = trimmed.trim_end().eq_ignore_ascii_case("[events]");
+        } else if in_events {
+            if let Some(format) = trimmed.strip_prefix("Format:") {
+                field_count = format.split(',').count();
+            } else if let Some(fields) = trimmed.strip_prefix("Dialogue:") {
+                let text_start = fields
+                    .match_indices(',')
+                    .nth(field_count.saturating_sub(2))
+                    .map(|(i, _)| content.len() - fields.len() + i + 1);
+                if let Some(text_start) = text_start {
+                    output.push_str(&content[..text_start]);
+                    output.push_str(&convert_ass_text(&content[text_start..], &convert)?);
+                    output.push_str(ending);
+                    continue;
+                }
+            }
+        }
+        output.push_str(line);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stand-in conversion that shows what was converted.
+    fn convert(text: &str) -> Result<String> {
+        Ok(text.replace('1', "一").replace(',', "，"))
+    }
+
+    #[test]
+    fn test_convert_cues() -> Result<()> {
+        let srt = "1\n00:00:01,000 --> 00:00:02,100\n第1行,\n第2行\n\n2\n00:00:03,000 --> 00:00:04,000\n1\n";
+        assert_eq!(
+            "1\n00:00:01,000 --> 00:00:02,100\n第一行，\n第2行\n\n2\n00:00:03,000 --> 00:00:04,000\n一\n",
+            convert_cues(srt, convert)?
+        );
+        let vtt =
+            "WEBVTT 1\r\n\r\nNOTE 1,1\r\n\r\ncue-1\r\n00:01.000 --> 00:02.000 line:1\r\n1,1\r\n";
+        assert_eq!(
+            "WEBVTT 1\r\n\r\nNOTE 1,1\r\n\r\ncue-1\r\n00:01.000 --> 00:02.000 line:1\r\n一，一\r\n",
+            convert_cues(vtt, convert)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_ass() -> Result<()> {
+        let ass = "[Script Info]\nTitle: 1,1\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\pos(1,1)}1,1\\N1\r\n\
+                   Comment: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,1\n\
+                   [Fonts]\nDialogue: 1,1\n";
+        assert_eq!(
+            "[Script Info]\nTitle: 1,1\n\n[Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+             Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\pos(1,1)}一，一\\N一\r\n\
+             Comment: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,1\n\
+             [Fonts]\nDialogue: 1,1\n",
+            convert_ass(ass, convert)?
+        );
+        // The text is the last of the fields the Format line declares.
+        let short = "[Events]\nFormat: Start, End, Text\nDialogue: 0:00:01.00,0:00:02.00,1,1\n";
+        assert_eq!(
+            "[Events]\nFormat: Start, End, Text\nDialogue: 0:00:01.00,0:00:02.00,一，一\n",
+            convert_ass(short, convert)?
+        );
+
+        Ok(())
+    }
+}
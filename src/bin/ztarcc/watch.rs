@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::batch::{convert_one, scan, ConvertFile};
+use crate::errors::UsageError;
+
+/// How often the watched directory is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Convert every file under `dir` to the same relative path under `out`, then
+/// keep converting each file that changes, and removing the output of each
+/// file that is removed, until interrupted.  Files that need no conversion
+/// are copied if `copy_unchanged` is set, and otherwise have any earlier
+/// output removed.  Changes are found by polling
+/// modification times.  A file that fails to convert is reported on standard
+/// error and tried again when it next changes.
+pub fn watch(
+    dir: &Path,
+    out: &Path,
+    copy_unchanged: bool,
+    convert: impl ConvertFile,
+) -> Result<()> {
+    let dir = fs::canonicalize(dir).context(format!("opening {}", dir.display()))?;
+    fs::create_dir_all(out).context(format!("creating {}", out.display()))?;
+    let out = fs::canonicalize(out)?;
+    if out.starts_with(&dir) {
+        return Err(UsageError(
+            "the --out directory cannot be inside the watched directory".to_owned(),
+        )
+        .into());
+    }
+    let mut converted: HashMap<PathBuf, SystemTime> = HashMap::new();
+    loop {
+        let mut files = HashMap::new();
+        scan(&dir, Path::new(""), &mut files)?;
+        for (relative, modified) in &files {
+            if converted.get(relative) == Some(modified) {
+                continue;
+            }
+            match convert_one(&dir, &out, relative, copy_unchanged, &convert) {
+                Ok((_, false, _)) => eprintln!("converted {}", relative.display()),
+                Ok((_, true, _)) if copy_unchanged => eprintln!("copied {}", relative.display()),
+                Ok((_, true, _)) => {
+                    if fs::remove_file(out.join(relative)).is_ok() {
+                        eprintln!("removed {}", relative.display());
+                    }
+                }
+                Err(error) => eprintln!("{}: {:#}", relative.display(), error),
+            }
+        }
+        for relative in converted.keys() {
+            if !files.contains_key(relative) && fs::remove_file(out.join(relative)).is_ok() {
+                eprintln!("removed {}", relative.display());
+            }
+        }
+        converted = files;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+use crate::incremental::ends_sentence;
+use crate::{Engine, Script};
+
+/// Where a bounded conversion stopped, to pass back to [Engine::convert_bounded] to resume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Continuation {
+    offset: usize,
+}
+
+impl Continuation {
+    /// The byte offset in the input where conversion will resume.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// The result of a conversion that may have run out of time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundedConversion<'a> {
+    /// The converted text, up to the end of the last sentence converted.
+    pub converted: String,
+    /// The rest of the input, not yet converted.
+    pub remainder: &'a str,
+    /// Where to resume, or `None` if the whole input has been converted.
+    pub continuation: Option<Continuation>,
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant a sentence at a time, stopping
+    /// once `budget` has been used up.  The sentence in progress is always finished, and at
+    /// least one sentence is converted per call.  To resume, call again with the same input and
+    /// the returned continuation; pass `None` to start at the beginning.  Not available on
+    /// wasm32, which has no clock.
+    pub fn convert_bounded<'a>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &'a str,
+        budget: Duration,
+        resume: Option<Continuation>,
+    ) -> Result<BoundedConversion<'a>> {
+        let started = Instant::now();
+        let start = resume.map_or(0, |continuation| continuation.offset);
+        if start > input.len() || !input.is_char_boundary(start) {
+            return Err(anyhow!(format!(
+                "continuation at byte {} does not belong to this input",
+                start
+            )));
+        }
+        let mut converted = String::new();
+        let mut offset = start;
+        for sentence in input[start..].split_inclusive(ends_sentence) {
+            if offset > start && started.elapsed() >= budget {
+                break;
+            }
+            self.convert_into(from, to, sentence, &mut converted)?;
+            offset += sentence.len();
+        }
+        Ok(BoundedConversion {
+            converted,
+            remainder: &input[offset..],
+            continuation: (offset < input.len()).then_some(Continuation { offset }),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_bounded;
+
+    #[test]
+    fn test_convert_bounded() -> Result<()> {
+        let input = "他们的软件。软件！软件";
+        let first = convert_bounded(Script::CN, Script::TW, input, Duration::ZERO, None)?;
+        assert_eq!("他們的軟體。", first.converted);
+        assert_eq!("软件！软件", first.remainder);
+
+        let mut output = first.converted;
+        let mut continuation = first.continuation;
+        while continuation.is_some() {
+            let next =
+                convert_bounded(Script::CN, Script::TW, input, Duration::ZERO, continuation)?;
+            output.push_str(&next.converted);
+            continuation = next.continuation;
+        }
+        assert_eq!("他們的軟體。軟體！軟體", output);
+
+        let all = convert_bounded(Script::CN, Script::TW, input, Duration::MAX, None)?;
+        assert_eq!(output, all.converted);
+        assert_eq!(None, all.continuation);
+
+        Ok(())
+    }
+}
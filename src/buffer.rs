@@ -0,0 +1,124 @@
+use anyhow::Result;
+
+use crate::{chain, Dictionary, Engine, Script};
+
+/// Inputs of at most this many bytes are converted without allocating.
+const SMALL_INPUT_LIMIT: usize = 256;
+
+/// Room for the text between two dictionary passes, which may be somewhat longer than the input.
+const SCRATCH_SIZE: usize = SMALL_INPUT_LIMIT * 4;
+
+/// A fixed-size buffer on the stack for the text between two dictionary passes.
+struct Scratch {
+    bytes: [u8; SCRATCH_SIZE],
+    len: usize,
+}
+
+impl Scratch {
+    fn new() -> Self {
+        Scratch {
+            bytes: [0; SCRATCH_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Append some text, returning false if it does not fit.
+    fn push(&mut self, text: &str) -> bool {
+        let end = self.len + text.len();
+        if end > SCRATCH_SIZE {
+            return false;
+        }
+        self.bytes[self.len..end].copy_from_slice(text.as_bytes());
+        self.len = end;
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).expect("scratch holds whole characters")
+    }
+}
+
+/// Apply a single dictionary to `text`, replacing the longest matches first, and pass each piece
+/// of the output to `emit`.  Stops and returns false as soon as `emit` does.
+fn greedy_pass(dict: &Dictionary, text: &str, mut emit: impl FnMut(&str) -> bool) -> bool {
+    dict.pieces(text)
+        .all(|piece| emit(piece.replacement.unwrap_or(piece.text)))
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant like [Engine::convert_greedy],
+    /// appending the result to `out`.  Once the dictionaries are loaded, inputs of up to 256
+    /// bytes are converted without any heap allocation other than growing `out`, so reusing one
+    /// buffer makes converting many short strings cheap.
+    pub fn convert_greedy_into(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        out: &mut String,
+    ) -> Result<()> {
+        if self.guards(input) {
+            out.push_str(input);
+            return Ok(());
+        }
+        if self.protects() || self.ranker.is_some() {
+            out.push_str(&self.convert_greedy(from, to, input)?);
+            return Ok(());
+        }
+        let keys = chain(from, to);
+        let (last, rest) = keys
+            .split_last()
+            .expect("a chain has at least one dictionary");
+        let mut scratch = Scratch::new();
+        let text = match rest {
+            [] => input,
+            [first]
+                if input.len() <= SMALL_INPUT_LIMIT
+                    && greedy_pass(self.dictionary(*first)?, input, |piece| {
+                        scratch.push(piece)
+                    }) =>
+            {
+                scratch.as_str()
+            }
+            _ => {
+                out.push_str(&self.convert_greedy(from, to, input)?);
+                return Ok(());
+            }
+        };
+        greedy_pass(self.dictionary(*last)?, text, |piece| {
+            out.push_str(piece);
+            true
+        });
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::{convert_greedy, convert_greedy_into};
+
+    #[test]
+    fn test_convert_greedy_into() -> Result<()> {
+        let mut out = String::with_capacity(64);
+        for (from, to, input) in [
+            (Script::CN, Script::TW, "他们的软件"),
+            (Script::HK, Script::TW, "看着他"),
+            (Script::TW, Script::CN, "我們的軟體"),
+        ] {
+            out.clear();
+            convert_greedy_into(from, to, input, &mut out)?;
+            assert_eq!(convert_greedy(from, to, input)?, out);
+        }
+
+        out = "前缀".to_owned();
+        let long = "软件".repeat(SMALL_INPUT_LIMIT);
+        convert_greedy_into(Script::CN, Script::TW, &long, &mut out)?;
+        assert_eq!(
+            format!("前缀{}", convert_greedy(Script::CN, Script::TW, &long)?),
+            out
+        );
+
+        Ok(())
+    }
+}
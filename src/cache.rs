@@ -0,0 +1,204 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use crate::{Engine, Script};
+
+/// Options for caching the results of whole-input conversions with [Engine::with_cache].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CacheOptions {
+    /// The most bytes of inputs and converted segments to keep in memory.  Once it is reached,
+    /// the oldest entries are evicted first.  Inputs too large to fit are not cached.
+    pub max_bytes: usize,
+    /// A directory to also keep entries in, so that they outlive the engine.  It should only be
+    /// shared by engines with the same options and version.
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        CacheOptions {
+            max_bytes: 16 << 20,
+            directory: None,
+        }
+    }
+}
+
+/// How well the conversion cache is doing, from [Engine::cache_stats].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// The number of conversions answered from the cache, in memory or on disk.
+    pub hits: u64,
+    /// The number of conversions that had to be done.
+    pub misses: u64,
+    /// The number of entries in memory.
+    pub entries: usize,
+    /// The number of bytes of inputs and converted segments in memory.
+    pub bytes: usize,
+}
+
+/// A conversion and its input.
+type Key = (Script, Script, String);
+
+/// The entries in memory, in the order they were added.
+#[derive(Default)]
+struct Entries {
+    segments: HashMap<Key, Vec<String>>,
+    order: VecDeque<Key>,
+    stats: CacheStats,
+}
+
+/// The size of an entry, for the memory limit.
+fn entry_bytes(input: &str, segments: &[String]) -> usize {
+    input.len() + segments.iter().map(String::len).sum::<usize>()
+}
+
+/// A hash of a conversion and its input that stays the same across runs, to name files by.
+fn stable_hash(from: Script, to: Script, input: &str) -> u64 {
+    // FNV-1a.
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let header = format!("{}:{:?}:{:?}:", env!("CARGO_PKG_VERSION"), from, to);
+    for byte in header.bytes().chain(input.bytes()) {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+    }
+    hash
+}
+
+/// A cache of converted segments, keyed by the whole input.
+pub(crate) struct Cache {
+    options: CacheOptions,
+    entries: Mutex<Entries>,
+}
+
+impl Cache {
+    /// The file an entry is kept in on disk, if there is a directory.
+    fn path(&self, from: Script, to: Script, input: &str) -> Option<PathBuf> {
+        let directory = self.options.directory.as_ref()?;
+        Some(directory.join(format!("{:016x}", stable_hash(from, to, input))))
+    }
+
+    /// Look up the segments of an earlier conversion, in memory and then on disk.
+    pub(crate) fn get(&self, from: Script, to: Script, input: &str) -> Option<Vec<String>> {
+        let key = (from, to, input.to_owned());
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(segments) = entries.segments.get(&key) {
+            let segments = segments.clone();
+            entries.stats.hits += 1;
+            return Some(segments);
+        }
+        let stored = self
+            .path(from, to, input)
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| postcard::from_bytes::<(String, Vec<String>)>(&bytes).ok());
+        match stored {
+            // The hash may collide, so check the input too.
+            Some((stored_input, segments)) if stored_input == input => {
+                entries.stats.hits += 1;
+                Self::remember(&mut entries, self.options.max_bytes, key, segments.clone());
+                Some(segments)
+            }
+            _ => {
+                entries.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Keep the segments of a conversion.  Failing to write them to disk is not an error, since
+    /// the conversion can always be done again.
+    pub(crate) fn insert(&self, from: Script, to: Script, input: &str, segments: &[String]) {
+        if let Some(path) = self.path(from, to, input) {
+            if let Ok(bytes) = postcard::to_stdvec(&(input, segments)) {
+                let _ = fs::create_dir_all(path.parent().unwrap_or(&path))
+                    .and_then(|_| fs::write(&path, bytes));
+            }
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (from, to, input.to_owned());
+        Self::remember(&mut entries, self.options.max_bytes, key, segments.to_vec());
+    }
+
+    /// Add an entry in memory, evicting the oldest entries to stay within `max_bytes`.
+    fn remember(entries: &mut Entries, max_bytes: usize, key: Key, segments: Vec<String>) {
+        let bytes = entry_bytes(&key.2, &segments);
+        if bytes > max_bytes || entries.segments.contains_key(&key) {
+            return;
+        }
+        while entries.stats.bytes + bytes > max_bytes {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = entries.segments.remove(&oldest) {
+                entries.stats.bytes -= entry_bytes(&oldest.2, &evicted);
+                entries.stats.entries -= 1;
+            }
+        }
+        entries.stats.bytes += bytes;
+        entries.stats.entries += 1;
+        entries.order.push_back(key.clone());
+        entries.segments.insert(key, segments);
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stats
+    }
+}
+
+impl Engine {
+    /// Cache the results of [Engine::convert] by their whole input, so that converting the same
+    /// string again, such as a UI label, is a lookup.  Looking up the cache takes a lock.  Set
+    /// this after the engine's other options, since entries are not invalidated when those
+    /// change.
+    pub fn with_cache(mut self, options: CacheOptions) -> Self {
+        self.cache = Some(Cache {
+            options,
+            entries: Mutex::new(Entries::default()),
+        });
+        self
+    }
+
+    /// How well the conversion cache is doing, or `None` if there is no cache.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(Cache::stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_cache() -> Result<()> {
+        let engine = Engine::new().with_cache(CacheOptions::default());
+        for _ in 0..2 {
+            assert_eq!(
+                "他們的軟體",
+                engine
+                    .convert(Script::CN, Script::TW, "他们的软件")?
+                    .join("")
+            );
+        }
+        let stats = engine.cache_stats().unwrap();
+        assert_eq!((1, 1, 1), (stats.hits, stats.misses, stats.entries));
+
+        let options = CacheOptions {
+            max_bytes: 50,
+            ..CacheOptions::default()
+        };
+        let engine = Engine::new().with_cache(options);
+        engine.convert(Script::CN, Script::TW, "他们的软件")?;
+        engine.convert(Script::CN, Script::TW, "他们是勇敢的士兵")?;
+        let stats = engine.cache_stats().unwrap();
+        assert_eq!(1, stats.entries);
+        assert!(stats.bytes <= 50);
+
+        Ok(())
+    }
+}
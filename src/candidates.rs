@@ -0,0 +1,69 @@
+use anyhow::Result;
+
+use crate::{chain, Ambiguities, Dictionary, Engine, Script};
+
+/// The most candidates returned for a word, since every ambiguous entry in it multiplies them.
+const MAX_CANDIDATES: usize = 64;
+
+/// Every conversion of a word by a single dictionary, starting with the one that is used by
+/// default.
+fn pass_candidates(dict: &Dictionary, ambiguities: &Ambiguities, word: &str) -> Vec<String> {
+    let mut results = vec![String::new()];
+    for piece in dict.pieces(word) {
+        let options: Vec<&str> = match (piece.replacement, ambiguities.get(piece.text)) {
+            (Some(_), Some(candidates)) => candidates.iter().map(String::as_str).collect(),
+            (Some(value), None) => vec![value],
+            (None, _) => vec![piece.text],
+        };
+        results = results
+            .iter()
+            .flat_map(|prefix| options.iter().map(move |option| prefix.clone() + option))
+            .take(MAX_CANDIDATES)
+            .collect();
+    }
+    results
+}
+
+impl Engine {
+    /// List every conversion of a word or phrase, using all the candidates of ambiguous
+    /// dictionary entries rather than only the first, for input methods and dictionary apps.
+    /// The word is converted as a unit, without segmenting it.  The first result is the default
+    /// conversion, ignoring any [crate::CandidateRanker]; there are no duplicates, and at most
+    /// 64 results.
+    pub fn candidates(&self, from: Script, to: Script, word: &str) -> Result<Vec<String>> {
+        let mut results = vec![self.normalization.normalize(word).into_owned()];
+        for key in &chain(from, to) {
+            let dict = self.dictionary(*key)?;
+            let ambiguities = self.ambiguities(*key)?;
+            let mut next: Vec<String> = Vec::new();
+            for result in &results {
+                for candidate in pass_candidates(dict, ambiguities, result) {
+                    if next.len() < MAX_CANDIDATES && !next.contains(&candidate) {
+                        next.push(candidate);
+                    }
+                }
+            }
+            results = next;
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() -> Result<()> {
+        let engine = Engine::new();
+        let candidates = engine.candidates(Script::CN, Script::TW, "干")?;
+        assert_eq!("幹", candidates[0]);
+        assert!(candidates.contains(&"乾".to_owned()));
+        assert_eq!(
+            vec!["軟體"],
+            engine.candidates(Script::CN, Script::TW, "软件")?
+        );
+
+        Ok(())
+    }
+}
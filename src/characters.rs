@@ -0,0 +1,108 @@
+use crate::{DictionaryKeys, Engine};
+
+impl Engine {
+    /// Whether every dictionary of a chain maps single characters only, so that segmenting the
+    /// input cannot change how it is converted.  A dictionary missing from the build counts as
+    /// having phrases, leaving the error to the conversion.
+    fn characters_only(&self, keys: &[DictionaryKeys]) -> bool {
+        keys.iter().all(|key| {
+            self.regional[*key].is_none()
+                && self
+                    .dictionary(*key)
+                    .is_ok_and(|dictionary| !dictionary.has_phrases())
+        })
+    }
+
+    /// Segment the input to convert with a chain of dictionaries, like [Engine::segments].  When
+    /// the chain has no phrases, such as OpenCC Standard to Hong Kong, the input is split into
+    /// characters without running the segmenter, which is much faster.  A [CandidateRanker] or
+    /// NFC normalization still needs whole words, so either turns this off.
+    ///
+    /// [CandidateRanker]: crate::CandidateRanker
+    pub(crate) fn segments_for<'a>(
+        &self,
+        keys: &[DictionaryKeys],
+        input: &'a str,
+    ) -> Vec<(&'a str, bool)> {
+        #[cfg(feature = "nfc")]
+        if self.normalization.nfc {
+            return self.segments(input);
+        }
+        if self.ranker.is_some() || !self.characters_only(keys) {
+            return self.segments(input);
+        }
+        self.segments_by(input, cut_characters)
+    }
+}
+
+/// Split text into characters, keeping runs of ASCII letters and digits, and runs of whitespace,
+/// together as the segmenter would.
+fn cut_characters(text: &str) -> Vec<&str> {
+    let joins = |a: char, b: char| {
+        (a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric())
+            || (a.is_whitespace() && b.is_whitespace())
+    };
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (offset, ch) in text.char_indices() {
+        if previous.is_some_and(|previous| !joins(previous, ch)) {
+            segments.push(&text[start..offset]);
+            start = offset;
+        }
+        previous = Some(ch);
+    }
+    if start < text.len() {
+        segments.push(&text[start..]);
+    }
+    segments
+}
+
+/// Splits text into characters, for builds without the `jieba` feature, whose dictionaries map
+/// single characters only.
+#[cfg(not(feature = "jieba"))]
+pub(crate) struct CharacterSegmenter;
+
+#[cfg(not(feature = "jieba"))]
+impl crate::Segmenter for CharacterSegmenter {
+    fn cut<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        cut_characters(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Dictionary, Script};
+    use anyhow::Result;
+
+    #[test]
+    fn test_cut_characters() {
+        assert_eq!(
+            vec!["用", "iPhone15", "  ", "上", "網"],
+            cut_characters("用iPhone15  上網")
+        );
+    }
+
+    #[test]
+    fn test_has_phrases() {
+        assert!(!Dictionary::from_pairs([("僞", "偽")]).has_phrases());
+        assert!(Dictionary::from_pairs([("僞", "偽"), ("軟件", "軟體")]).has_phrases());
+    }
+
+    #[test]
+    fn test_characters_only_chain() -> Result<()> {
+        let engine = Engine::new();
+        assert_eq!(
+            vec!["他", "們", "的", "軟", "件"],
+            engine.convert(Script::ST, Script::HK, "他們的軟件")?
+        );
+        assert_eq!(
+            "他們的軟體",
+            engine
+                .convert(Script::ST, Script::TW, "他們的軟件")?
+                .join("")
+        );
+        Ok(())
+    }
+}
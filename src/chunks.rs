@@ -0,0 +1,117 @@
+use anyhow::Result;
+
+use crate::incremental::ends_sentence;
+use crate::{Engine, Script};
+
+/// An iterator over a conversion a chunk at a time; see [Engine::convert_chunks].
+#[derive(Clone)]
+pub struct ConvertChunks<'e, 'a> {
+    engine: &'e Engine,
+    from: Script,
+    to: Script,
+    rest: &'a str,
+    max_chunk_bytes: usize,
+}
+
+impl Iterator for ConvertChunks<'_, '_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let len = self.engine.chunk_len(self.rest, self.max_chunk_bytes);
+        let (chunk, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(
+            self.engine
+                .convert(self.from, self.to, chunk)
+                .map(|segments| segments.concat()),
+        )
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant a chunk at a time, as the
+    /// returned iterator is advanced, so that a service can bound the memory a request uses and
+    /// start sending the output before the whole input is converted.  Each chunk of the input is
+    /// at most `max_chunk_bytes` long, and ends after the last line break or sentence-ending
+    /// punctuation within that, or else the last whitespace, or else the last whole segment, so
+    /// that no phrase is split between chunks.  A chunk is only longer if a single segment is.
+    /// Converted chunks may be somewhat longer than the input, where phrases are localized.
+    pub fn convert_chunks<'a>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &'a str,
+        max_chunk_bytes: usize,
+    ) -> ConvertChunks<'_, 'a> {
+        ConvertChunks {
+            engine: self,
+            from,
+            to,
+            rest: input,
+            max_chunk_bytes,
+        }
+    }
+
+    /// The length of the next chunk of `text` to convert; see [Engine::convert_chunks].
+    fn chunk_len(&self, text: &str, max: usize) -> usize {
+        if text.len() <= max {
+            return text.len();
+        }
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let window = &text[..end];
+        let after = |(i, ch): (usize, char)| i + ch.len_utf8();
+        let boundary = window
+            .char_indices()
+            .rev()
+            .find(|(_, ch)| ends_sentence(*ch))
+            .or_else(|| {
+                window
+                    .char_indices()
+                    .rev()
+                    .find(|(_, ch)| ch.is_whitespace())
+            })
+            .map(after);
+        if let Some(end) = boundary {
+            return end;
+        }
+        // The last segment of the window may continue past it, so cut before it.
+        match self.segment(window).as_slice() {
+            [.., last] if last.len() < window.len() => window.len() - last.len(),
+            _ => {
+                // Nothing in the window can be cut, so take the whole first segment, which
+                // ends by the next boundary after it.
+                let next = text[end..]
+                    .find(|ch: char| ends_sentence(ch) || ch.is_whitespace())
+                    .map_or(text.len(), |i| end + i)
+                    .max(text.chars().next().map_or(0, char::len_utf8));
+                let segments = self.segment(&text[..next]);
+                segments.first().map_or(text.len(), |segment| segment.len())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_chunks() -> Result<()> {
+        let engine = Engine::new();
+        let input = "他们是勇敢的士兵。".repeat(10) + &"他们的软件".repeat(10);
+        let expected = engine.convert(Script::CN, Script::TW, &input)?.concat();
+        let chunks = engine
+            .convert_chunks(Script::CN, Script::TW, &input, 40)
+            .collect::<Result<Vec<_>>>()?;
+        assert!(chunks.len() > 5);
+        assert_eq!(expected, chunks.concat());
+        assert!(chunks.contains(&"他們是勇敢的士兵。".to_owned()));
+        Ok(())
+    }
+}
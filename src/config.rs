@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::dictionary::{opencc_pairs, read_opencc_text};
+use crate::{Dictionary, Pipeline};
+
+/// A dictionary in an OpenCC configuration.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum DictConfig {
+    Text { file: PathBuf },
+    Ocd { file: PathBuf },
+    Ocd2 { file: PathBuf },
+    Group { dicts: Vec<DictConfig> },
+}
+
+/// One step of an OpenCC conversion chain.
+#[derive(Deserialize)]
+struct StepConfig {
+    dict: DictConfig,
+}
+
+/// An OpenCC configuration file.  The segmentation settings are ignored.
+#[derive(Deserialize)]
+struct Config {
+    name: Option<String>,
+    conversion_chain: Vec<StepConfig>,
+}
+
+impl DictConfig {
+    /// The paths of the text dictionaries this dictionary is made of, in priority order.
+    /// Compiled dictionaries are replaced by the text dictionaries they were built from, which
+    /// OpenCC ships alongside them with a `.txt` extension.
+    fn text_files(&self, dir: &Path, files: &mut Vec<PathBuf>) {
+        match self {
+            DictConfig::Text { file } => files.push(dir.join(file)),
+            DictConfig::Ocd { file } | DictConfig::Ocd2 { file } => {
+                files.push(dir.join(file).with_extension("txt"))
+            }
+            DictConfig::Group { dicts } => {
+                for dict in dicts {
+                    dict.text_files(dir, files);
+                }
+            }
+        }
+    }
+
+    /// Load the dictionary, merging groups so that earlier dictionaries take priority.
+    fn load(&self, dir: &Path) -> Result<Dictionary> {
+        let mut files = Vec::new();
+        self.text_files(dir, &mut files);
+        let texts = files
+            .iter()
+            .map(|file| read_opencc_text(file))
+            .collect::<Result<Vec<_>>>()?;
+        let mut pairs = Vec::new();
+        for (file, text) in files.iter().zip(&texts) {
+            pairs.extend(opencc_pairs(text).context(format!("parsing {}", file.display()))?);
+        }
+        Ok(Dictionary::from_pairs(pairs))
+    }
+}
+
+impl Pipeline {
+    /// Read an OpenCC configuration file such as `s2twp.json`.  Dictionary files are found
+    /// relative to the configuration file; `.ocd2` and `.ocd` dictionaries are read from the
+    /// `.txt` files of the same name instead.  Segmentation always uses the engine's segmenter.
+    pub fn from_opencc_config(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text =
+            fs::read_to_string(path).context(format!("reading config {}", path.display()))?;
+        let config: Config =
+            serde_json::from_str(&text).context(format!("parsing config {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let dictionaries = config
+            .conversion_chain
+            .iter()
+            .map(|step| step.dict.load(dir))
+            .collect::<Result<Vec<_>>>()
+            .context(format!("loading dictionaries for {}", path.display()))?;
+        Ok(Pipeline {
+            name: config.name,
+            dictionaries,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_pipeline;
+
+    #[test]
+    fn test_opencc_config() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("ztarcc-config-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("STPhrases.txt"), "软件\t軟件\n")?;
+        fs::write(dir.join("STCharacters.txt"), "们\t們\n软\t軟\n件\t件\n")?;
+        fs::write(dir.join("TWPhrases.txt"), "軟件\t軟體\n")?;
+        fs::write(
+            dir.join("s2twp.json"),
+            r#"{
+                "name": "Simplified Chinese to Traditional Chinese (Taiwan)",
+                "segmentation": {"type": "mmseg", "dict": {"type": "ocd2", "file": "STPhrases.ocd2"}},
+                "conversion_chain": [
+                    {"dict": {"type": "group", "dicts": [
+                        {"type": "ocd2", "file": "STPhrases.ocd2"},
+                        {"type": "text", "file": "STCharacters.txt"}
+                    ]}},
+                    {"dict": {"type": "ocd2", "file": "TWPhrases.ocd2"}}
+                ]
+            }"#,
+        )?;
+        let pipeline = Pipeline::from_opencc_config(dir.join("s2twp.json"));
+        fs::remove_dir_all(&dir)?;
+        let pipeline = pipeline?;
+
+        assert_eq!(
+            Some("Simplified Chinese to Traditional Chinese (Taiwan)"),
+            pipeline.name()
+        );
+        assert_eq!(
+            "他們的軟體",
+            convert_pipeline(&pipeline, "他们的软件").join("")
+        );
+
+        Ok(())
+    }
+}
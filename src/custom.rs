@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::dictionary::{opencc_pairs, read_opencc_text};
+use crate::{
+    builtin_script, chain, convert_pass, ConversionSink, Dictionary, Engine, Script, Warning,
+};
+
+/// A custom target script: the conversion to a built-in script, followed by layers of phrase
+/// dictionaries, user overrides, and variant preferences.  This can describe a variant that
+/// OpenCC does not ship, such as Singapore usage as mainland simplified plus a layer of local
+/// phrases.  Register it with [Engine::register_script] to use it by name.
+pub struct CustomScript {
+    name: String,
+    base: Script,
+    layers: Vec<Dictionary>,
+    overrides: BTreeMap<String, String>,
+    overrides_dict: Option<Dictionary>,
+    preferences: HashMap<char, char>,
+    warnings: Vec<Warning>,
+    files: Vec<PathBuf>,
+}
+
+impl CustomScript {
+    /// Create a custom script that starts out identical to `base`.
+    pub fn new(name: &str, base: Script) -> Self {
+        CustomScript {
+            name: name.to_owned(),
+            base,
+            layers: Vec::new(),
+            overrides: BTreeMap::new(),
+            overrides_dict: None,
+            preferences: HashMap::new(),
+            warnings: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Parse a custom script definition.  Each line is blank, a `#` comment, or one of:
+    /// - `name NAME`
+    /// - `base SCRIPT` (one of `st`, `cn`, `tw`, or `hk`)
+    /// - `override FROM TO`, replacing the phrase `FROM` in the base script's output with `TO`
+    /// - `prefer FROM TO`, replacing the character `FROM` with the variant `TO`
+    ///
+    /// Definitions read with [CustomScript::from_definition_file] may also have
+    /// `dictionary PATH` lines; see there.
+    pub fn from_definition(text: &str) -> Result<Self> {
+        Self::parse_definition(text, None)
+    }
+
+    /// Read a custom script definition from a file.  Besides the lines described in
+    /// [CustomScript::from_definition], it may have `dictionary PATH` lines naming dictionaries in
+    /// OpenCC's text format, relative to the definition file, which are added as layers in order.
+    pub fn from_definition_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context(format!("reading {}", path.display()))?;
+        let mut script =
+            Self::parse_definition(&text, Some(path.parent().unwrap_or(Path::new(""))))
+                .context(format!("parsing {}", path.display()))?;
+        script.files.insert(0, path.to_owned());
+        Ok(script)
+    }
+
+    /// The files this script was read from: the definition file and the dictionaries it names,
+    /// so that they can be watched for changes.  Empty unless it was read with
+    /// [CustomScript::from_definition_file].
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Parse a definition, resolving `dictionary` lines against `directory` if there is one.
+    fn parse_definition(text: &str, directory: Option<&Path>) -> Result<Self> {
+        let mut name = None;
+        let mut base = None;
+        let mut layers = Vec::new();
+        let mut files = Vec::new();
+        let mut overrides = Vec::new();
+        let mut preferences = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["name", value] => name = Some(*value),
+                ["base", value] => {
+                    base = Some(
+                        builtin_script(value)
+                            .ok_or(anyhow!(format!("unknown base script {}", value)))?,
+                    )
+                }
+                ["dictionary", file] => {
+                    let directory = directory.ok_or(anyhow!(format!(
+                        "dictionary on line {} can only be used in a definition file",
+                        index + 1
+                    )))?;
+                    let path = directory.join(file);
+                    let text = read_opencc_text(&path)?;
+                    let pairs = opencc_pairs(&text)
+                        .context(format!("parsing dictionary {}", path.display()))?;
+                    layers.push(Dictionary::from_pairs(pairs));
+                    files.push(path);
+                }
+                ["override", from, to] => overrides.push((*from, *to)),
+                ["prefer", from, to] => {
+                    let single = |value: &str| {
+                        let mut chars = value.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(ch), None) => Ok(ch),
+                            _ => Err(anyhow!(format!(
+                                "preference {} is not a single character on line {}",
+                                value,
+                                index + 1
+                            ))),
+                        }
+                    };
+                    preferences.push((single(from)?, single(to)?));
+                }
+                _ => {
+                    return Err(anyhow!(format!(
+                        "invalid script definition on line {}: {}",
+                        index + 1,
+                        line
+                    )))
+                }
+            }
+        }
+        let name = name.ok_or(anyhow!("script definition has no name"))?;
+        let base = base.ok_or(anyhow!("script definition has no base"))?;
+        let mut script = CustomScript::new(name, base);
+        script.layers = layers;
+        script.files = files;
+        script = script.with_overrides(overrides);
+        for (from, to) in preferences {
+            script = script.with_preference(from, to);
+        }
+        Ok(script)
+    }
+
+    /// Add a layer of phrase mappings, applied by longest match to the base script's output
+    /// before the overrides.  Layers apply in the order they are added, each to the output of
+    /// the one before.  If a phrase appears more than once in a layer, its first mapping is used.
+    pub fn with_dictionary<'a>(
+        mut self,
+        phrases: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        self.layers.push(Dictionary::from_pairs(phrases));
+        self
+    }
+
+    /// Add phrase overrides, which replace text in the base script's output.
+    /// Later overrides for the same phrase replace earlier ones.
+    pub fn with_overrides<'a>(
+        mut self,
+        overrides: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        for (from, to) in overrides {
+            if let Some(previous) = self.overrides.insert(from.to_owned(), to.to_owned()) {
+                if previous != to {
+                    self.warnings.push(Warning::ShadowedOverride {
+                        phrase: from.to_owned(),
+                        replacement: previous,
+                        shadowed_by: to.to_owned(),
+                    });
+                }
+            }
+        }
+        self.overrides_dict = Some(Dictionary::from_pairs(
+            self.overrides
+                .iter()
+                .map(|(from, to)| (from.as_str(), to.as_str())),
+        ));
+        self
+    }
+
+    /// Prefer the variant `to` wherever the character `from` would be output.
+    pub fn with_preference(mut self, from: char, to: char) -> Self {
+        self.preferences.insert(from, to);
+        self
+    }
+
+    /// The name of the script.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The built-in script this script is based on.
+    pub fn base(&self) -> Script {
+        self.base
+    }
+
+    /// Warnings about the script's definition, such as overrides replaced by later ones.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Apply the layers, overrides, and preferences to text already converted to the base
+    /// script.
+    fn apply(&self, text: &str) -> String {
+        let mut overridden = text.to_owned();
+        for dict in self.layers.iter().chain(&self.overrides_dict) {
+            overridden = convert_pass(dict, &overridden).into_owned();
+        }
+        if self.preferences.is_empty() {
+            return overridden;
+        }
+        overridden
+            .chars()
+            .map(|ch| *self.preferences.get(&ch).unwrap_or(&ch))
+            .collect()
+    }
+
+    /// Apply the script to `text`, the base script's conversion of `original`, and pass the
+    /// result to `sink`.
+    fn apply_into<S: ConversionSink + ?Sized>(
+        &self,
+        original: &str,
+        text: &str,
+        sink: &mut S,
+    ) -> Result<()> {
+        let converted = self.apply(text);
+        match converted == original {
+            true => sink.unchanged(original),
+            false => sink.replaced(original, &converted),
+        }
+    }
+}
+
+impl Engine {
+    /// Register a custom script, so that it can be used by name with [Engine::convert_named].
+    /// Registering a script with the same name as an existing custom script replaces it; the
+    /// names of built-in scripts cannot be used.
+    pub fn register_script(&self, script: CustomScript) -> Result<()> {
+        if builtin_script(&script.name).is_some() {
+            return Err(anyhow!(format!(
+                "cannot replace built-in script {}",
+                script.name
+            )));
+        }
+        self.custom_scripts
+            .write()
+            .map_err(|_| anyhow!("custom script registry is poisoned"))?
+            .insert(script.name.clone(), Arc::new(script));
+        Ok(())
+    }
+
+    /// Look up a built-in or registered custom script by name.
+    pub(crate) fn resolve_script(&self, name: &str) -> Result<(Script, Option<Arc<CustomScript>>)> {
+        if let Some(script) = builtin_script(name) {
+            return Ok((script, None));
+        }
+        let custom = self
+            .custom_scripts
+            .read()
+            .map_err(|_| anyhow!("custom script registry is poisoned"))?
+            .get(name)
+            .cloned()
+            .ok_or(anyhow!(format!("unknown script {}", name)))?;
+        Ok((custom.base, Some(custom)))
+    }
+
+    /// The built-in script that the script named `name` is based on, which is the script itself
+    /// for a built-in script.
+    pub fn script_base(&self, name: &str) -> Result<Script> {
+        Ok(self.resolve_script(name)?.0)
+    }
+
+    /// Apply the layers, overrides, and preferences of the script named `to` to text already
+    /// converted to its base script, such as the output of [Engine::convert_greedy].  Text is
+    /// returned as it is for a built-in script.
+    pub fn apply_script(&self, to: &str, text: &str) -> Result<String> {
+        Ok(match self.resolve_script(to)?.1 {
+            Some(custom) => custom.apply(text),
+            None => text.to_owned(),
+        })
+    }
+
+    /// Convert a string between two scripts given by name, which may be either built-in scripts
+    /// (`st`, `cn`, `tw`, `hk`) or registered custom scripts.  Converting from a custom script is
+    /// the same as converting from its base script.  A custom script's layers and overrides
+    /// apply to each run of converted segments as a whole, so that a phrase split by
+    /// segmentation still matches, and each run is returned as one segment.
+    pub fn convert_named(&self, from: &str, to: &str, input: &str) -> Result<Vec<String>> {
+        let (from_script, _) = self.resolve_script(from)?;
+        let (to_script, custom) = self.resolve_script(to)?;
+        if custom.is_none() {
+            return self.convert(from_script, to_script, input);
+        }
+        let mut segments = Vec::new();
+        self.convert_named_into(from, to, input, &mut segments)?;
+        Ok(segments)
+    }
+
+    /// Convert a string between two scripts given by name, as with [Engine::convert_named],
+    /// passing each segment to `sink` as it is converted.
+    pub fn convert_named_into<S: ConversionSink + ?Sized>(
+        &self,
+        from: &str,
+        to: &str,
+        input: &str,
+        sink: &mut S,
+    ) -> Result<()> {
+        let (from_script, _) = self.resolve_script(from)?;
+        let (to_script, custom) = self.resolve_script(to)?;
+        let Some(custom) = custom else {
+            return self.convert_into(from_script, to_script, input, sink);
+        };
+        if self.guards(input) {
+            return sink.unchanged(input);
+        }
+        let keys = chain(from_script, to_script);
+        // The input and base conversion of the current run of converted segments.
+        let mut start = 0;
+        let mut end = 0;
+        let mut run = String::new();
+        for (word, copied) in self.segments(input) {
+            if !copied {
+                run.push_str(&self.width.apply(self.convert_word(&keys, word)?));
+                end += word.len();
+                continue;
+            }
+            if start < end {
+                custom.apply_into(&input[start..end], &std::mem::take(&mut run), sink)?;
+            }
+            sink.unchanged(word)?;
+            start = end + word.len();
+            end = start;
+        }
+        if start < end {
+            custom.apply_into(&input[start..end], &run, sink)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_script() -> Result<()> {
+        let engine = Engine::new();
+        let script = CustomScript::from_definition(
+            "# Taiwan, but with some Hong Kong usage\n\
+             name company-tw\n\
+             base tw\n\
+             override 軟體 軟件\n\
+             prefer 的 嘅\n",
+        )?;
+        engine.register_script(script)?;
+        let result = engine.convert_named("cn", "company-tw", "他们的软件")?;
+        assert_eq!("他們嘅軟件", result.join(""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_script_layers() -> Result<()> {
+        let engine = Engine::new();
+        let script = CustomScript::new("sg", Script::CN)
+            .with_dictionary([("出租车", "德士")])
+            .with_dictionary([("德士", "的士")]);
+        engine.register_script(script)?;
+        let result = engine.convert_named("cn", "sg", "坐出租车")?;
+        assert_eq!("坐的士", result.join(""));
+
+        assert!(CustomScript::from_definition("name sg\nbase cn\ndictionary sg.txt\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_script_across_segments() -> Result<()> {
+        let engine = Engine::new();
+        let script = CustomScript::new("sg", Script::CN).with_overrides([("坐出租车", "搭德士")]);
+        engine.register_script(script)?;
+        assert_eq!(
+            "我搭德士",
+            engine.convert_named("cn", "sg", "我坐出租车")?.join("")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_script_files() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("ztarcc-custom-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("sg.txt"), "出租车\t德士\n")?;
+        std::fs::write(dir.join("sg.def"), "name sg\nbase cn\ndictionary sg.txt\n")?;
+        let script = CustomScript::from_definition_file(dir.join("sg.def"));
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(&[dir.join("sg.def"), dir.join("sg.txt")], script?.files());
+        assert!(CustomScript::new("sg", Script::CN).files().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_script_shadowed_override() {
+        let script = CustomScript::new("test", Script::TW)
+            .with_overrides([("軟體", "軟件"), ("軟體", "軟体")]);
+        assert_eq!(
+            &[Warning::ShadowedOverride {
+                phrase: "軟體".to_owned(),
+                replacement: "軟件".to_owned(),
+                shadowed_by: "軟体".to_owned(),
+            }],
+            script.warnings()
+        );
+    }
+
+    #[test]
+    fn test_custom_script_builtin_name() {
+        let engine = Engine::new();
+        assert!(engine
+            .register_script(CustomScript::new("tw", Script::HK))
+            .is_err());
+    }
+}
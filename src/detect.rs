@@ -0,0 +1,107 @@
+use anyhow::Result;
+
+use crate::{Dictionary, DictionaryKeys, Engine, Script};
+
+/// The dictionaries whose changes are evidence for a script: a phrase that a dictionary changes
+/// is written in a form that the script uses, or that the script would not use.
+const EVIDENCE: [(DictionaryKeys, Script); 6] = [
+    // Simplified forms.
+    (DictionaryKeys::FromChina, Script::CN),
+    // Traditional forms, from no particular region.
+    (DictionaryKeys::ToChina, Script::ST),
+    // Taiwan forms, and forms Hong Kong would write differently.
+    (DictionaryKeys::FromTaiwan, Script::TW),
+    (DictionaryKeys::ToHongKong, Script::TW),
+    // Hong Kong forms, and forms Taiwan would write differently.
+    (DictionaryKeys::FromHongKong, Script::HK),
+    (DictionaryKeys::ToTaiwan, Script::HK),
+];
+
+/// The result of [Engine::detect_script].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ScriptDetection {
+    /// The script the input is most likely written in, or `None` if nothing in it tells the
+    /// scripts apart.  Traditional text that gives no evidence of its region is [Script::ST].
+    pub script: Option<Script>,
+    /// The phrases of the input that were counted, in input order, with the script each counted
+    /// toward.  [Script::ST] marks traditional forms used in every region.
+    pub evidence: Vec<(Script, String)>,
+}
+
+/// The phrases of a word that a dictionary changes, by longest match.
+fn changed_phrases<'a>(dict: &Dictionary, word: &'a str) -> Vec<&'a str> {
+    dict.pieces(word)
+        .filter(|piece| piece.replacement.is_some_and(|value| value != piece.text))
+        .map(|piece| piece.text)
+        .collect()
+}
+
+impl Engine {
+    /// Guess which script some text is written in.  Besides simplified and traditional
+    /// characters, this counts the phrases that only one region writes a given way, such as
+    /// 軟體 in Taiwan and 軟件 in Hong Kong, which is what tells Taiwan and Hong Kong text apart.
+    /// The phrases counted are returned as evidence.  Dictionaries not included in the build are
+    /// not consulted.
+    pub fn detect_script(&self, input: &str) -> Result<ScriptDetection> {
+        let mut evidence = Vec::new();
+        for (word, copied) in self.segments(input) {
+            if copied {
+                continue;
+            }
+            for (key, script) in EVIDENCE {
+                if let Ok(dict) = self.dictionary(key) {
+                    evidence.extend(
+                        changed_phrases(dict, word)
+                            .into_iter()
+                            .map(|phrase| (script, phrase.to_owned())),
+                    );
+                }
+            }
+        }
+        let count = |script| {
+            evidence
+                .iter()
+                .filter(|(found, _)| *found == script)
+                .count()
+        };
+        let (cn, st, tw, hk) = (
+            count(Script::CN),
+            count(Script::ST),
+            count(Script::TW),
+            count(Script::HK),
+        );
+        let script = if cn + st + tw + hk == 0 {
+            None
+        } else if cn > st {
+            Some(Script::CN)
+        } else if tw > hk {
+            Some(Script::TW)
+        } else if hk > tw {
+            Some(Script::HK)
+        } else {
+            Some(Script::ST)
+        };
+        Ok(ScriptDetection { script, evidence })
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::detect_script;
+
+    #[test]
+    fn test_detect_script() -> Result<()> {
+        assert_eq!(Some(Script::CN), detect_script("他们的软件")?.script);
+        let detection = detect_script("他們的軟體")?;
+        assert_eq!(Some(Script::TW), detection.script);
+        assert!(detection
+            .evidence
+            .contains(&(Script::TW, "軟體".to_owned())));
+        assert_eq!(Some(Script::HK), detect_script("他們的軟件")?.script);
+        assert_eq!(None, detect_script("hello")?.script);
+
+        Ok(())
+    }
+}
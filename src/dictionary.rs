@@ -0,0 +1,339 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context, Result};
+use fst::raw::Output;
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+
+#[cfg(feature = "unstable")]
+use crate::{replace_pass, Engine};
+
+/// The header at the start of OpenCC's compiled `.ocd2` dictionaries.
+const OCD2_HEADER: &[u8] = b"OPENCC_MARISA";
+
+/// Parse the entries of a dictionary in OpenCC's text format, taking the first candidate of each.
+pub(crate) fn opencc_pairs(text: &str) -> Result<Vec<(&str, &str)>> {
+    let mut pairs = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let (from, rest) = line
+            .split_once('\t')
+            .ok_or(anyhow!(format!("no tab on line {}", index + 1)))?;
+        if let Some(to) = rest.split_ascii_whitespace().next() {
+            pairs.push((from, to));
+        }
+    }
+    Ok(pairs)
+}
+
+/// Read a file containing a dictionary in OpenCC's text format.
+pub(crate) fn read_opencc_text(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).context(format!("reading dictionary {}", path.display()))?;
+    if bytes.starts_with(OCD2_HEADER) {
+        return Err(anyhow!(format!(
+            "{} is a compiled OpenCC dictionary; convert it to text with opencc_dict",
+            path.display()
+        )));
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| anyhow!(format!("dictionary {} is not UTF-8", path.display())))
+}
+
+/// Pack the location of a value in a dictionary's values into an FST output.  The build script
+/// packs the embedded dictionaries the same way.
+fn pack_value(offset: usize, len: usize) -> u64 {
+    ((offset as u64) << 32) | len as u64
+}
+
+/// A table of phrases and their replacements, applied by longest match.  The built-in
+/// dictionaries are embedded at build time; others can be loaded at runtime and used with
+/// [crate::Engine::convert_with].  Requires the `unstable` feature.
+pub struct Dictionary {
+    /// Each phrase, mapped to the location of its replacement in `values` (see [pack_value]).
+    map: Map<Cow<'static, [u8]>>,
+    /// The replacements, concatenated.
+    values: Cow<'static, str>,
+    /// Whether any phrase is longer than one character, found the first time it is needed.
+    phrases: OnceLock<bool>,
+}
+
+impl Dictionary {
+    /// Use a dictionary embedded at build time in place, without copying or deserializing it.
+    #[cfg(not(feature = "external-dicts"))]
+    pub(crate) fn from_static(map: &'static [u8], values: &'static [u8]) -> Result<Self> {
+        Ok(Dictionary {
+            map: Map::new(Cow::Borrowed(map))?,
+            values: Cow::Borrowed(std::str::from_utf8(values)?),
+            phrases: OnceLock::new(),
+        })
+    }
+
+    /// Use a dictionary from a map and values loaded at runtime, checking that the map is intact
+    /// and that every replacement lies within the values (see [Dictionary::verify]).
+    #[cfg(feature = "external-dicts")]
+    pub(crate) fn from_parts(map: Vec<u8>, values: String) -> Result<Self> {
+        let dictionary = Dictionary {
+            map: Map::new(Cow::Owned(map))?,
+            values: Cow::Owned(values),
+            phrases: OnceLock::new(),
+        };
+        dictionary.verify()?;
+        Ok(dictionary)
+    }
+
+    /// The replacement stored at the location packed into an FST output.
+    fn value(&self, output: Output) -> &str {
+        let packed = output.value();
+        let offset = (packed >> 32) as usize;
+        let len = (packed & 0xFFFF_FFFF) as usize;
+        &self.values[offset..offset + len]
+    }
+
+    /// Check that the map is intact and that every replacement lies within the values, returning
+    /// the number of entries.
+    pub(crate) fn verify(&self) -> Result<usize> {
+        self.map.as_fst().verify()?;
+        let mut stream = self.map.stream();
+        let mut entries = 0;
+        while let Some((phrase, packed)) = stream.next() {
+            let offset = (packed >> 32) as usize;
+            let len = (packed & 0xFFFF_FFFF) as usize;
+            if self.values.get(offset..offset + len).is_none() {
+                return Err(anyhow!(format!(
+                    "the replacement of {:?} lies outside the values",
+                    String::from_utf8_lossy(phrase)
+                )));
+            }
+            entries += 1;
+        }
+        Ok(entries)
+    }
+
+    /// The replacement of exactly `phrase`, if it is in the dictionary.
+    pub(crate) fn get(&self, phrase: &str) -> Option<&str> {
+        let packed = self.map.get(phrase)?;
+        Some(self.value(Output::new(packed)))
+    }
+
+    /// The number of entries in the dictionary.
+    pub(crate) fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether any phrase in the dictionary is longer than one character.
+    pub(crate) fn has_phrases(&self) -> bool {
+        *self.phrases.get_or_init(|| {
+            let mut stream = self.map.stream();
+            while let Some((phrase, _)) = stream.next() {
+                if String::from_utf8_lossy(phrase).chars().nth(1).is_some() {
+                    return true;
+                }
+            }
+            false
+        })
+    }
+
+    /// Every entry whose phrase starts with `prefix`, in order, with its replacement.
+    pub(crate) fn with_prefix(&self, prefix: &str) -> Vec<(String, &str)> {
+        let mut stream = self.map.range().ge(prefix).into_stream();
+        let mut entries = Vec::new();
+        while let Some((phrase, packed)) = stream.next() {
+            if !phrase.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            let phrase = String::from_utf8_lossy(phrase).into_owned();
+            entries.push((phrase, self.value(Output::new(packed))));
+        }
+        entries
+    }
+
+    /// Split `text` into pieces by longest match, from the start.
+    pub(crate) fn pieces<'t>(&self, text: &'t str) -> Pieces<'t, '_> {
+        Pieces {
+            dict: self,
+            text,
+            offset: 0,
+        }
+    }
+
+    /// Find the longest phrase that starts `text`, returning its length in bytes and its
+    /// replacement.
+    pub(crate) fn longest_prefix(&self, text: &str) -> Option<(usize, &str)> {
+        let fst = self.map.as_fst();
+        let mut node = fst.root();
+        let mut output = Output::zero();
+        let mut found = None;
+        for (i, byte) in text.bytes().enumerate() {
+            let Some(index) = node.find_input(byte) else {
+                break;
+            };
+            let transition = node.transition(index);
+            output = output.cat(transition.out);
+            node = fst.node(transition.addr);
+            if node.is_final() {
+                found = Some((i + 1, output.cat(node.final_output())));
+            }
+        }
+        found.map(|(len, output)| (len, self.value(output)))
+    }
+
+    /// Build a dictionary from pairs of phrases and replacements.  If a phrase appears more than
+    /// once, its first replacement is used.
+    pub fn from_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut entries = BTreeMap::new();
+        for (from, to) in pairs {
+            entries.entry(from).or_insert(to);
+        }
+        let mut builder = MapBuilder::memory();
+        let mut values = String::new();
+        for (from, to) in entries {
+            builder
+                .insert(from, pack_value(values.len(), to.len()))
+                .expect("keys are inserted in order");
+            values.push_str(to);
+        }
+        let bytes = builder
+            .into_inner()
+            .expect("building a map in memory cannot fail");
+        Dictionary {
+            map: Map::new(Cow::Owned(bytes)).expect("a newly built map is valid"),
+            values: Cow::Owned(values),
+            phrases: OnceLock::new(),
+        }
+    }
+
+    /// Parse a dictionary in OpenCC's text format, where each line is a phrase, a tab, and one or
+    /// more candidates separated by spaces.  The first candidate is used.
+    #[cfg(feature = "unstable")]
+    pub fn parse_opencc_text(text: &str) -> Result<Self> {
+        Ok(Self::from_pairs(opencc_pairs(text)?))
+    }
+
+    /// Read a dictionary in OpenCC's text format from a file.  Compiled `.ocd2` dictionaries are
+    /// not supported; convert them to text first with `opencc_dict -f ocd2 -t text`.
+    #[cfg(feature = "unstable")]
+    pub fn from_opencc_text(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse_opencc_text(&read_opencc_text(path)?)
+            .context(format!("parsing dictionary {}", path.display()))
+    }
+}
+
+/// A piece of text split off by longest match against a dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Piece<'t, 'd> {
+    /// The byte offset of the piece in the text.
+    pub(crate) offset: usize,
+    /// The piece itself: a phrase in the dictionary, or else a single character.
+    pub(crate) text: &'t str,
+    /// The dictionary's replacement for the phrase, or `None` for a character not in it.
+    pub(crate) replacement: Option<&'d str>,
+}
+
+impl Piece<'_, '_> {
+    /// The byte offset of the end of the piece in the text.
+    pub(crate) fn end(&self) -> usize {
+        self.offset + self.text.len()
+    }
+}
+
+/// Splits text into pieces by longest match against a dictionary.  Every piece is at least one
+/// whole character, so the text is never sliced in the middle of a character.
+pub(crate) struct Pieces<'t, 'd> {
+    dict: &'d Dictionary,
+    text: &'t str,
+    offset: usize,
+}
+
+impl<'t, 'd> Iterator for Pieces<'t, 'd> {
+    type Item = Piece<'t, 'd>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.text[self.offset..];
+        let ch = rest.chars().next()?;
+        let (len, replacement) = match self.dict.longest_prefix(rest) {
+            Some((len, value)) => (len, Some(value)),
+            None => (ch.len_utf8(), None),
+        };
+        // Phrases are whole strings, so a match cannot end inside a character.
+        debug_assert!(rest.is_char_boundary(len));
+        let piece = Piece {
+            offset: self.offset,
+            text: &rest[..len],
+            replacement,
+        };
+        self.offset += len;
+        Some(piece)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Engine {
+    /// Convert a string by applying the given dictionaries to each segment in order, instead of
+    /// the built-in ones for a pair of scripts.  Phrases that the segmenter does not know may be
+    /// split across segments, and then are not matched.
+    pub fn convert_with(&self, dictionaries: &[&Dictionary], input: &str) -> Vec<String> {
+        self.segments(input)
+            .into_iter()
+            .map(|(word, copied)| {
+                if copied {
+                    return word.to_owned();
+                }
+                let mut converted = Cow::Borrowed(word);
+                for dict in dictionaries {
+                    if let Some(replaced) = replace_pass(dict, &converted) {
+                        converted = Cow::Owned(replaced);
+                    }
+                }
+                converted.into_owned()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(all(feature = "global", feature = "unstable"))]
+    use crate::convert_with;
+
+    #[test]
+    fn test_pieces() {
+        let dict = Dictionary::from_pairs([("软件", "軟體"), ("软", "軟")]);
+        let pieces: Vec<_> = dict
+            .pieces("a软件软")
+            .map(|piece| (piece.offset, piece.text, piece.replacement))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, "a", None),
+                (1, "软件", Some("軟體")),
+                (7, "软", Some("軟"))
+            ],
+            pieces
+        );
+        assert_eq!(0, dict.pieces("").count());
+    }
+
+    #[test]
+    #[cfg(all(feature = "global", feature = "unstable"))]
+    fn test_parse_opencc_text() -> Result<()> {
+        let dict = Dictionary::parse_opencc_text("软件\t軟體 軟件\n他们\t他們\n")?;
+        assert_eq!("他們的軟體", convert_with(&[&dict], "他们的软件").join(""));
+        assert!(Dictionary::parse_opencc_text("软件 軟體\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "global", feature = "unstable"))]
+    fn test_from_pairs_first_wins() {
+        let dict = Dictionary::from_pairs([("软件", "軟體"), ("软件", "軟件")]);
+        assert_eq!(vec!["軟體"], convert_with(&[&dict], "软件"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Evaluation of conversion quality against reference data.
+
+use std::collections::HashMap;
+use std::{fs, io, path};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::{Engine, Script};
+
+/// A line whose conversion did not match the expected output.
+#[derive(Serialize, Debug, Clone)]
+pub struct LineMismatch {
+    /// The line number, starting from 1.
+    pub line: usize,
+    pub input: String,
+    pub expected: String,
+    pub actual: String,
+    /// The edit distance between the expected and actual lines, in characters.
+    pub character_errors: usize,
+}
+
+/// A segment whose conversion did not match the expected output, and how often that happened.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PhraseMismatch {
+    pub input: String,
+    pub expected: String,
+    pub actual: String,
+    pub count: usize,
+}
+
+/// The evaluation results for one pair of input and expected files.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FileReport {
+    pub name: String,
+    pub lines: usize,
+    pub exact_lines: usize,
+    /// The number of characters in the expected output.
+    pub characters: usize,
+    /// The total edit distance between the expected and actual output, in characters.
+    pub character_errors: usize,
+    pub mismatches: Vec<LineMismatch>,
+    pub phrase_mismatches: Vec<PhraseMismatch>,
+}
+
+/// The evaluation results for a whole corpus.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct EvalReport {
+    pub files: Vec<FileReport>,
+    pub lines: usize,
+    pub exact_lines: usize,
+    pub characters: usize,
+    pub character_errors: usize,
+    /// Mismatched segments across all files, most frequent first.
+    pub phrase_mismatches: Vec<PhraseMismatch>,
+}
+
+/// The OpenCC test cases, each named after its input and expected files, with the scripts it
+/// converts between.
+pub const OPENCC_CASES: [(&str, Script, Script); 11] = [
+    ("hk2s", Script::HK, Script::CN),
+    ("hk2t", Script::HK, Script::ST),
+    ("s2hk", Script::CN, Script::HK),
+    ("s2t", Script::CN, Script::ST),
+    ("s2tw", Script::CN, Script::TW),
+    ("s2twp", Script::CN, Script::TW),
+    ("t2hk", Script::ST, Script::HK),
+    ("t2s", Script::ST, Script::CN),
+    ("tw2s", Script::TW, Script::CN),
+    ("tw2sp", Script::TW, Script::CN),
+    ("tw2t", Script::TW, Script::ST),
+];
+
+/// Compute the edit distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Sort phrase mismatches by frequency, then by content so the order is stable.
+fn sort_phrase_mismatches(counts: HashMap<(String, String, String), usize>) -> Vec<PhraseMismatch> {
+    let mut mismatches: Vec<_> = counts
+        .into_iter()
+        .map(|((input, expected, actual), count)| PhraseMismatch {
+            input,
+            expected,
+            actual,
+            count,
+        })
+        .collect();
+    mismatches.sort_by(|a, b| {
+        b.count.cmp(&a.count).then_with(|| {
+            (&a.input, &a.expected, &a.actual).cmp(&(&b.input, &b.expected, &b.actual))
+        })
+    });
+    mismatches
+}
+
+impl FileReport {
+    /// The fraction of lines that were converted exactly as expected.
+    pub fn line_accuracy(&self) -> f64 {
+        self.exact_lines as f64 / self.lines.max(1) as f64
+    }
+
+    /// The character error rate: the edit distance divided by the expected length.
+    pub fn character_error_rate(&self) -> f64 {
+        self.character_errors as f64 / self.characters.max(1) as f64
+    }
+}
+
+impl EvalReport {
+    /// Combine the reports for several files.
+    pub fn from_files(files: Vec<FileReport>) -> Self {
+        let mut counts = HashMap::new();
+        for file in &files {
+            for mismatch in &file.phrase_mismatches {
+                let key = (
+                    mismatch.input.clone(),
+                    mismatch.expected.clone(),
+                    mismatch.actual.clone(),
+                );
+                *counts.entry(key).or_default() += mismatch.count;
+            }
+        }
+        EvalReport {
+            lines: files.iter().map(|f| f.lines).sum(),
+            exact_lines: files.iter().map(|f| f.exact_lines).sum(),
+            characters: files.iter().map(|f| f.characters).sum(),
+            character_errors: files.iter().map(|f| f.character_errors).sum(),
+            phrase_mismatches: sort_phrase_mismatches(counts),
+            files,
+        }
+    }
+
+    /// The fraction of lines that were converted exactly as expected.
+    pub fn line_accuracy(&self) -> f64 {
+        self.exact_lines as f64 / self.lines.max(1) as f64
+    }
+
+    /// The character error rate: the edit distance divided by the expected length.
+    pub fn character_error_rate(&self) -> f64 {
+        self.character_errors as f64 / self.characters.max(1) as f64
+    }
+
+    /// Serialize the report as JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl Engine {
+    /// Evaluate the conversion of `input` against `expected`, line by line.
+    pub fn evaluate_text(
+        &self,
+        from: Script,
+        to: Script,
+        name: &str,
+        input: &str,
+        expected: &str,
+    ) -> Result<FileReport> {
+        let mut report = FileReport {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+        let mut phrase_counts = HashMap::new();
+        let mut expected_lines = expected.lines();
+        for (index, input_line) in input.lines().enumerate() {
+            let expected_line = expected_lines.next().unwrap_or("");
+            let segments = self.convert_aligned(from, to, input_line)?;
+            let actual: String = segments
+                .iter()
+                .map(|(_, converted)| converted.as_str())
+                .collect();
+            report.lines += 1;
+            report.characters += expected_line.chars().count();
+            if actual == expected_line {
+                report.exact_lines += 1;
+                continue;
+            }
+            let character_errors = edit_distance(expected_line, &actual);
+            report.character_errors += character_errors;
+
+            // Compare segment by segment, assuming the expected line lines up with the actual
+            // conversion; this holds as long as the mismatches do not change the length.
+            let mut expected_rest = expected_line;
+            for (original, converted) in &segments {
+                let length = converted.chars().count();
+                let split = expected_rest
+                    .char_indices()
+                    .nth(length)
+                    .map_or(expected_rest.len(), |(i, _)| i);
+                let (expected_segment, rest) = expected_rest.split_at(split);
+                if expected_segment != converted {
+                    let key = (
+                        original.to_string(),
+                        expected_segment.to_owned(),
+                        converted.to_owned(),
+                    );
+                    *phrase_counts.entry(key).or_default() += 1;
+                }
+                expected_rest = rest;
+            }
+
+            report.mismatches.push(LineMismatch {
+                line: index + 1,
+                input: input_line.to_owned(),
+                expected: expected_line.to_owned(),
+                actual,
+                character_errors,
+            });
+        }
+        report.phrase_mismatches = sort_phrase_mismatches(phrase_counts);
+        Ok(report)
+    }
+
+    /// Evaluate every `NAME.in` file in a directory against the matching `NAME.ans` file, in the
+    /// layout used by the OpenCC test cases.  Files are evaluated in name order.
+    pub fn evaluate_dir(&self, from: Script, to: Script, dir: &path::Path) -> Result<EvalReport> {
+        let mut input_paths: Vec<_> = fs::read_dir(dir)
+            .context(format!("reading corpus directory {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "in"))
+            .collect();
+        input_paths.sort();
+        if input_paths.is_empty() {
+            return Err(anyhow!(format!("no .in files in {}", dir.display())));
+        }
+        let files = input_paths
+            .iter()
+            .map(|input_path| {
+                let expected_path = input_path.with_extension("ans");
+                let input = fs::read_to_string(input_path)
+                    .context(format!("reading {}", input_path.display()))?;
+                let expected = fs::read_to_string(&expected_path)
+                    .context(format!("reading {}", expected_path.display()))?;
+                let name = input_path
+                    .file_stem()
+                    .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+                self.evaluate_text(from, to, &name, &input, &expected)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EvalReport::from_files(files))
+    }
+
+    /// Run the [OPENCC_CASES] found in `dir`, which is laid out like OpenCC's `test/testcases`
+    /// directory, so that a build can be checked against the reference data on the platform it
+    /// runs on.  A conversion matches OpenCC if the report has no mismatches.
+    pub fn run_opencc_cases(&self, dir: &path::Path) -> Result<EvalReport> {
+        let files = OPENCC_CASES
+            .iter()
+            .map(|(name, from, to)| {
+                let input_path = dir.join(format!("{}.in", name));
+                let expected_path = dir.join(format!("{}.ans", name));
+                let input = fs::read_to_string(&input_path)
+                    .context(format!("reading {}", input_path.display()))?;
+                let expected = fs::read_to_string(&expected_path)
+                    .context(format!("reading {}", expected_path.display()))?;
+                self.evaluate_text(*from, *to, name, &input, &expected)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EvalReport::from_files(files))
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("軟體", "軟體"));
+        assert_eq!(1, edit_distance("軟體", "軟件"));
+        assert_eq!(3, edit_distance("", "最佳化"));
+    }
+
+    #[test]
+    fn test_evaluate_text() -> Result<()> {
+        let engine = Engine::new();
+        let report = engine.evaluate_text(
+            Script::CN,
+            Script::TW,
+            "test",
+            "他们是勇敢的士兵\n软件\n",
+            "他們是勇敢的士兵\n軟件\n",
+        )?;
+        assert_eq!(2, report.lines);
+        assert_eq!(1, report.exact_lines);
+        assert_eq!(1, report.character_errors);
+        assert_eq!(
+            vec![PhraseMismatch {
+                input: "软件".to_owned(),
+                expected: "軟件".to_owned(),
+                actual: "軟體".to_owned(),
+                count: 1,
+            }],
+            report.phrase_mismatches
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_opencc_cases() -> Result<()> {
+        let dir = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("opencc/test/testcases");
+        let report = Engine::new().run_opencc_cases(&dir)?;
+        assert_eq!(OPENCC_CASES.len(), report.files.len());
+        assert_eq!(report.lines, report.exact_lines);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+
+use crate::lookup::dictionary_key;
+use crate::{Ambiguities, Dictionary, Engine};
+
+impl Engine {
+    /// Register a dictionary written as an asset by the build with the `external-dicts`
+    /// feature, such as `FromChina.bin` (see [Engine::dictionary_chain] for the dictionaries a
+    /// conversion needs).  Returns the name of the dictionary.  A dictionary that was already
+    /// registered is kept as it was.
+    pub fn register_dictionary(&self, asset: &[u8]) -> Result<String> {
+        let (name, map, values, ambiguities): (String, Vec<u8>, String, Ambiguities) =
+            postcard::from_bytes(asset).context("reading dictionary asset")?;
+        let key = dictionary_key(&name)?;
+        let dictionary =
+            Dictionary::from_parts(map, values).context(format!("loading dictionary {}", name))?;
+        let _ = self.dictionaries[key].set(Some(dictionary));
+        let _ = self.ambiguities[key].set(Some(ambiguities));
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use fst::MapBuilder;
+
+    #[test]
+    fn test_register_corrupted_dictionary() -> Result<()> {
+        let name = Engine::new().dictionary_chain(Script::CN, Script::TW)[0].clone();
+        let mut builder = MapBuilder::memory();
+        // "軟體" at offset 0 of the values, 6 bytes long.
+        builder.insert("软件", 6)?;
+        let map = builder.into_inner()?;
+        let asset = |map: &[u8], values: &str| {
+            postcard::to_stdvec(&(&name, map, values, Ambiguities::new()))
+        };
+        assert_eq!(
+            name,
+            Engine::new().register_dictionary(&asset(&map, "軟體")?)?
+        );
+
+        // The replacement lies past the end of the values.
+        assert!(Engine::new()
+            .register_dictionary(&asset(&map, "軟")?)
+            .is_err());
+        // The map fails its checksum.
+        let mut corrupted = map.clone();
+        corrupted[map.len() / 2] ^= 0xFF;
+        assert!(Engine::new()
+            .register_dictionary(&asset(&corrupted, "軟體")?)
+            .is_err());
+
+        Ok(())
+    }
+}
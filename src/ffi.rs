@@ -0,0 +1,128 @@
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::catch_unwind;
+use std::ptr;
+use std::slice;
+
+use anyhow::{anyhow, Result};
+
+use crate::convert_named;
+
+/// The conversion succeeded.
+pub const ZTARCC_OK: c_int = 0;
+/// An argument was null, or was not valid UTF-8.
+pub const ZTARCC_INVALID_ARGUMENT: c_int = 1;
+/// The conversion failed, for example because a script name was unknown.
+pub const ZTARCC_CONVERSION_FAILED: c_int = 2;
+/// The library panicked; this is a bug.
+pub const ZTARCC_PANIC: c_int = 3;
+
+/// Read a NUL-terminated script name.
+unsafe fn script_name<'a>(name: *const c_char) -> Option<&'a str> {
+    if name.is_null() {
+        return None;
+    }
+    CStr::from_ptr(name).to_str().ok()
+}
+
+/// Convert `len` bytes of UTF-8 text at `input` between two scripts given by name (`"cn"`, `"tw"`,
+/// `"hk"`, `"st"`, or a registered custom script).  On success, `*out` is set to a newly allocated
+/// NUL-terminated UTF-8 string, which must be released with [ztarcc_free]; on failure, `*out` is
+/// set to null.  Returns one of the `ZTARCC_` status codes.
+///
+/// # Safety
+///
+/// `from` and `to` must be valid NUL-terminated strings, `input` must point to `len` readable
+/// bytes (it may be null if `len` is zero), and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ztarcc_convert(
+    from: *const c_char,
+    to: *const c_char,
+    input: *const u8,
+    len: usize,
+    out: *mut *mut c_char,
+) -> c_int {
+    if out.is_null() {
+        return ZTARCC_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    let (Some(from), Some(to)) = (script_name(from), script_name(to)) else {
+        return ZTARCC_INVALID_ARGUMENT;
+    };
+    let input = match (input.is_null(), len) {
+        (_, 0) => &[][..],
+        (true, _) => return ZTARCC_INVALID_ARGUMENT,
+        (false, _) => slice::from_raw_parts(input, len),
+    };
+    let Ok(input) = std::str::from_utf8(input) else {
+        return ZTARCC_INVALID_ARGUMENT;
+    };
+
+    let result = catch_unwind(|| -> Result<CString> {
+        let converted = convert_named(from, to, input)?.join("");
+        CString::new(converted).map_err(|_| anyhow!("output contains a NUL character"))
+    });
+    match result {
+        Ok(Ok(converted)) => {
+            *out = converted.into_raw();
+            ZTARCC_OK
+        }
+        Ok(Err(_)) => ZTARCC_CONVERSION_FAILED,
+        Err(_) => ZTARCC_PANIC,
+    }
+}
+
+/// Release a string returned by [ztarcc_convert].  Passing null does nothing.
+///
+/// # Safety
+///
+/// `converted` must be null or a string returned by [ztarcc_convert] that has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ztarcc_free(converted: *mut c_char) {
+    if !converted.is_null() {
+        drop(CString::from_raw(converted));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_convert() {
+        let input = "我能吞下玻璃而不伤身体。";
+        let mut out = ptr::null_mut();
+        let status = unsafe {
+            ztarcc_convert(
+                c"cn".as_ptr(),
+                c"tw".as_ptr(),
+                input.as_ptr(),
+                input.len(),
+                &mut out,
+            )
+        };
+        assert_eq!(ZTARCC_OK, status);
+        let result = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_owned();
+        unsafe { ztarcc_free(out) };
+        assert_eq!("我能吞下玻璃而不傷身體。", result);
+    }
+
+    #[test]
+    fn test_ffi_invalid() {
+        let input = [0xFFu8];
+        let mut out = ptr::null_mut();
+        let status = unsafe {
+            ztarcc_convert(
+                c"cn".as_ptr(),
+                c"tw".as_ptr(),
+                input.as_ptr(),
+                input.len(),
+                &mut out,
+            )
+        };
+        assert_eq!(ZTARCC_INVALID_ARGUMENT, status);
+        assert!(out.is_null());
+        let status =
+            unsafe { ztarcc_convert(c"cn".as_ptr(), c"xx".as_ptr(), ptr::null(), 0, &mut out) };
+        assert_eq!(ZTARCC_CONVERSION_FAILED, status);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::Script;
+
+/// A sample text with its expected conversion, for smoke-testing bindings and integrations.
+/// Requires the `fixtures` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Fixture {
+    /// The kind of text: `news`, `dialogue`, or `technical`.
+    pub name: &'static str,
+    pub from: Script,
+    pub to: Script,
+    pub input: &'static str,
+    /// What converting `input` from `from` to `to` gives.
+    pub expected: &'static str,
+}
+
+/// Embed a sample text by kind and script.
+macro_rules! sample {
+    ($name:literal, $script:literal) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/",
+            $name,
+            ".",
+            $script,
+            ".txt"
+        ))
+    };
+}
+
+/// Define a fixture converting a sample text between two scripts.
+macro_rules! fixture {
+    ($name:literal, $from:ident: $from_name:literal, $to:ident: $to_name:literal) => {
+        Fixture {
+            name: $name,
+            from: Script::$from,
+            to: Script::$to,
+            input: sample!($name, $from_name),
+            expected: sample!($name, $to_name),
+        }
+    };
+}
+
+/// The embedded fixtures.  The sample texts were written for this crate, and are under its
+/// license.
+const FIXTURES: [Fixture; 8] = [
+    fixture!("news", CN: "cn", TW: "tw"),
+    fixture!("news", CN: "cn", HK: "hk"),
+    fixture!("news", TW: "tw", CN: "cn"),
+    fixture!("dialogue", CN: "cn", TW: "tw"),
+    fixture!("dialogue", TW: "tw", CN: "cn"),
+    fixture!("technical", CN: "cn", TW: "tw"),
+    fixture!("technical", CN: "cn", HK: "hk"),
+    fixture!("technical", TW: "tw", CN: "cn"),
+];
+
+/// The embedded sample texts and their expected conversions: short news, dialogue, and
+/// technical texts, each converted between several pairs of scripts.
+pub fn fixtures() -> &'static [Fixture] {
+    &FIXTURES
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert;
+    use anyhow::Result;
+
+    #[test]
+    fn test_fixtures() -> Result<()> {
+        for fixture in fixtures() {
+            assert_eq!(
+                fixture.expected,
+                convert(fixture.from, fixture.to, fixture.input)?.join(""),
+                "Mismatch with fixture {} {:?}→{:?}",
+                fixture.name,
+                fixture.from,
+                fixture.to
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+
+use crate::{Engine, Script};
+
+/// Attributes whose values are human-readable text, and are therefore converted.
+const TEXT_ATTRIBUTES: [&str; 3] = ["title", "alt", "aria-label"];
+
+/// Elements whose contents are never converted.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Converts a run of text.
+type ConvertText<'a> = dyn Fn(&str) -> Result<String> + 'a;
+
+/// Convert a run of text, unless it is empty.
+fn convert_text(convert: &ConvertText, text: &str) -> Result<String> {
+    if text.is_empty() {
+        return Ok(String::new());
+    }
+    convert(text)
+}
+
+/// Copy a start tag (which must begin at the start of `input`), converting the values of
+/// [TEXT_ATTRIBUTES].  Returns the lower-cased tag name and the number of bytes consumed.
+fn copy_start_tag(
+    convert: &ConvertText,
+    input: &str,
+    output: &mut String,
+) -> Result<(String, usize)> {
+    let is_name_end = |c: char| c.is_ascii_whitespace() || c == '/' || c == '>';
+    let name_end = input[1..].find(is_name_end).map_or(input.len(), |i| i + 1);
+    let name = input[1..name_end].to_ascii_lowercase();
+    output.push_str(&input[..name_end]);
+
+    let mut offset = name_end;
+    while let Some(ch) = input[offset..].chars().next() {
+        if ch == '>' {
+            output.push(ch);
+            return Ok((name, offset + 1));
+        }
+        if ch.is_ascii_whitespace() || ch == '/' {
+            output.push(ch);
+            offset += ch.len_utf8();
+            continue;
+        }
+
+        let rest = &input[offset..];
+        let attr_len = rest
+            .find(|c: char| is_name_end(c) || c == '=')
+            .unwrap_or(rest.len())
+            .max(ch.len_utf8());
+        let attr = rest[..attr_len].to_ascii_lowercase();
+        output.push_str(&rest[..attr_len]);
+        offset += attr_len;
+
+        let rest = &input[offset..];
+        let Some(after_equals) = rest
+            .trim_start_matches(|c: char| c.is_ascii_whitespace())
+            .strip_prefix('=')
+        else {
+            continue;
+        };
+        let value_start = rest.len()
+            - after_equals
+                .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                .len();
+        output.push_str(&rest[..value_start]);
+        offset += value_start;
+
+        let rest = &input[offset..];
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let (value_start, value_end) = match quote {
+            Some(quote) => (1, rest[1..].find(quote).map_or(rest.len(), |i| i + 1)),
+            None => (
+                0,
+                rest.find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(rest.len()),
+            ),
+        };
+        let value = &rest[value_start..value_end];
+        output.push_str(&rest[..value_start]);
+        if TEXT_ATTRIBUTES.contains(&attr.as_str()) {
+            output.push_str(&convert_text(convert, value)?);
+        } else {
+            output.push_str(value);
+        }
+        offset += value_end;
+        if let Some(quote) = quote {
+            if input[offset..].starts_with(quote) {
+                output.push(quote);
+                offset += quote.len_utf8();
+            }
+        }
+    }
+    Ok((name, offset))
+}
+
+/// Copy the markup at the start of `input` (which must begin with `<`), converting only the
+/// human-readable parts.  Returns the number of bytes consumed.
+fn copy_markup(convert: &ConvertText, input: &str, output: &mut String) -> Result<usize> {
+    if let Some(comment) = input.strip_prefix("<!--") {
+        let end = comment
+            .find("-->")
+            .map_or(input.len(), |i| i + "<!---->".len());
+        output.push_str(&input[..end]);
+        return Ok(end);
+    }
+    match input[1..].chars().next() {
+        Some(ch) if ch.is_ascii_alphabetic() => {
+            let (name, mut offset) = copy_start_tag(convert, input, output)?;
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                // Lower-casing ASCII does not move any byte offsets.
+                let raw_end = input[offset..]
+                    .to_ascii_lowercase()
+                    .find(&format!("</{}", name))
+                    .map_or(input.len(), |i| offset + i);
+                output.push_str(&input[offset..raw_end]);
+                offset = raw_end;
+            }
+            Ok(offset)
+        }
+        Some('/' | '!' | '?') => {
+            let end = input.find('>').map_or(input.len(), |i| i + 1);
+            output.push_str(&input[..end]);
+            Ok(end)
+        }
+        _ => {
+            output.push('<');
+            Ok(1)
+        }
+    }
+}
+
+/// Convert the human-readable parts of an HTML document with `convert`.
+fn convert_document(convert: &ConvertText, input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&convert_text(convert, &rest[..start])?);
+        let consumed = copy_markup(convert, &rest[start..], &mut output)?;
+        rest = &rest[start + consumed..];
+    }
+    output.push_str(&convert_text(convert, rest)?);
+    Ok(output)
+}
+
+/// Convert the human-readable parts of an HTML document with `convert`, such as a conversion
+/// that also records what it changed.  The parts converted are those of [Engine::convert_html].
+pub fn convert_html_with(input: &str, convert: impl Fn(&str) -> Result<String>) -> Result<String> {
+    convert_document(&convert, input)
+}
+
+impl Engine {
+    /// Convert an HTML document from an input variant to an output variant.  Only text nodes and
+    /// the values of human-readable attributes (`title`, `alt`, `aria-label`) are converted; tag
+    /// names, other attributes (including URLs), comments, scripts, and styles are left untouched.
+    pub fn convert_html(&self, from: Script, to: Script, input: &str) -> Result<String> {
+        convert_document(&|text| Ok(self.convert(from, to, text)?.join("")), input)
+    }
+
+    /// Convert an HTML document between two scripts given by name, which may be registered
+    /// custom scripts.  See [Engine::convert_html] and [Engine::convert_named] for details.
+    pub fn convert_html_named(&self, from: &str, to: &str, input: &str) -> Result<String> {
+        self.resolve_script(from)?;
+        self.resolve_script(to)?;
+        convert_document(
+            &|text| Ok(self.convert_named(from, to, text)?.join("")),
+            input,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_html;
+
+    #[test]
+    fn test_convert_html_text_and_attributes() -> Result<()> {
+        let input = r#"<p class="软件" title="软件">软件</p>"#;
+        let result = convert_html(Script::CN, Script::TW, input)?;
+        assert_eq!(r#"<p class="软件" title="軟體">軟體</p>"#, result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_html_named() -> Result<()> {
+        let engine = Engine::new();
+        engine.register_script(
+            crate::CustomScript::new("sg", Script::CN).with_overrides([("出租车", "德士")]),
+        )?;
+        let input = r#"<p title="出租车">坐出租车</p>"#;
+        let result = engine.convert_html_named("cn", "sg", input)?;
+        assert_eq!(r#"<p title="德士">坐德士</p>"#, result);
+        assert!(engine.convert_html_named("cn", "unknown", "").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_html_raw_text() -> Result<()> {
+        let input = "<script>var s = '软件';</script><!-- 软件 --><b>软件</b>";
+        let result = convert_html(Script::CN, Script::TW, input)?;
+        assert_eq!(
+            "<script>var s = '软件';</script><!-- 软件 --><b>軟體</b>",
+            result
+        );
+
+        Ok(())
+    }
+}
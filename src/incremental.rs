@@ -0,0 +1,177 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use crate::{Engine, Script, Span, SpanSink};
+
+/// Whether a character ends a sentence.  Phrases and segments do not run across these.
+pub(crate) fn ends_sentence(ch: char) -> bool {
+    matches!(ch, '。' | '！' | '？' | '；' | '!' | '?' | ';' | '\n')
+}
+
+/// A conversion kept along with which part of the source each part of the output came from, so
+/// that edits to the source can be converted without converting all of it again; see
+/// [Engine::convert_incremental].
+#[derive(Debug, Clone)]
+pub struct IncrementalConversion {
+    from: Script,
+    to: Script,
+    source: String,
+    spans: Vec<Span>,
+    output: String,
+}
+
+impl IncrementalConversion {
+    /// The source text, with every edit so far applied.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The converted text.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, keeping what is needed to
+    /// convert later edits to it with [Engine::reconvert].
+    pub fn convert_incremental(
+        &self,
+        from: Script,
+        to: Script,
+        source: &str,
+    ) -> Result<IncrementalConversion> {
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, source, &mut sink)?;
+        let output = sink
+            .spans
+            .iter()
+            .map(|span| span.converted.as_str())
+            .collect();
+        Ok(IncrementalConversion {
+            from,
+            to,
+            source: source.to_owned(),
+            spans: sink.spans,
+            output,
+        })
+    }
+
+    /// Replace the bytes `edited` of a conversion's source with `replacement`, and convert only
+    /// the sentences the edit touches, splicing them into the output.  Returns the byte range of
+    /// the output that changed.  The result is the same as converting the whole new source,
+    /// except that the language guard and punctuation rewriting only see the sentences around
+    /// the edit.
+    pub fn reconvert(
+        &self,
+        conversion: &mut IncrementalConversion,
+        edited: Range<usize>,
+        replacement: &str,
+    ) -> Result<Range<usize>> {
+        let source = &conversion.source;
+        if edited.start > edited.end
+            || edited.end > source.len()
+            || !source.is_char_boundary(edited.start)
+            || !source.is_char_boundary(edited.end)
+        {
+            return Err(anyhow!(format!(
+                "edited range {:?} is not within the source",
+                edited
+            )));
+        }
+        // Widen the edit to whole sentences, then to whole segments of the old conversion.
+        let sentence_start = source[..edited.start].rfind(ends_sentence).map_or(0, |i| {
+            i + source[i..].chars().next().map_or(0, char::len_utf8)
+        });
+        let sentence_end = source[edited.end..]
+            .find(ends_sentence)
+            .map_or(source.len(), |i| {
+                edited.end
+                    + i
+                    + source[edited.end + i..]
+                        .chars()
+                        .next()
+                        .map_or(0, char::len_utf8)
+            });
+        let spans = &conversion.spans;
+        let first = spans.partition_point(|span| span.end <= sentence_start);
+        let last = spans
+            .partition_point(|span| span.start < sentence_end)
+            .max(first);
+        let region_start = spans.get(first).map_or(source.len(), |span| span.start);
+        let region_end = match last > first {
+            true => spans[last - 1].end,
+            false => region_start,
+        };
+        let output_start: usize = spans[..first].iter().map(|span| span.converted.len()).sum();
+        let output_end = output_start
+            + spans[first..last]
+                .iter()
+                .map(|span| span.converted.len())
+                .sum::<usize>();
+
+        let mut new_source = String::with_capacity(source.len() + replacement.len());
+        new_source.push_str(&source[..edited.start]);
+        new_source.push_str(replacement);
+        new_source.push_str(&source[edited.end..]);
+        let new_region_end = region_end - edited.len() + replacement.len();
+        let mut sink = SpanSink::default();
+        self.convert_into(
+            conversion.from,
+            conversion.to,
+            &new_source[region_start..new_region_end],
+            &mut sink,
+        )?;
+
+        let converted: String = sink
+            .spans
+            .iter()
+            .map(|span| span.converted.as_str())
+            .collect();
+        let new_spans = sink.spans.into_iter().map(|span| Span {
+            start: span.start + region_start,
+            end: span.end + region_start,
+            converted: span.converted,
+        });
+        let later = conversion.spans.split_off(last);
+        conversion.spans.truncate(first);
+        conversion.spans.extend(new_spans);
+        conversion.spans.extend(later.into_iter().map(|span| Span {
+            start: span.start - region_end + new_region_end,
+            end: span.end - region_end + new_region_end,
+            converted: span.converted,
+        }));
+        conversion
+            .output
+            .replace_range(output_start..output_end, &converted);
+        conversion.source = new_source;
+        Ok(output_start..output_start + converted.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconvert() -> Result<()> {
+        let engine = Engine::new();
+        let mut conversion =
+            engine.convert_incremental(Script::CN, Script::TW, "他们的软件。我们的软件。")?;
+        assert_eq!("他們的軟體。我們的軟體。", conversion.output());
+
+        let start = "他们的软件。".len();
+        let changed = engine.reconvert(&mut conversion, start..start + "我们".len(), "你们")?;
+        assert_eq!("他们的软件。你们的软件。", conversion.source());
+        assert_eq!("他們的軟體。你們的軟體。", conversion.output());
+        assert_eq!("你們的軟體。", &conversion.output()[changed]);
+
+        let end = conversion.source().len();
+        engine.reconvert(&mut conversion, end..end, "软件")?;
+        assert_eq!("他們的軟體。你們的軟體。軟體", conversion.output());
+        assert!(engine.reconvert(&mut conversion, 1..2, "").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use crate::{Engine, Script};
+
+/// Converts a string value.
+type ConvertText<'a> = dyn Fn(&str) -> Result<String> + 'a;
+
+/// Escape an object key for use as a JSON pointer reference token.
+fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Whether the value at `pointer` is selected by the allowlist: it is, or lies within, one of
+/// the listed values.  An empty allowlist selects everything.
+fn selected(pointer: &str, allowlist: &[&str]) -> bool {
+    allowlist.is_empty()
+        || allowlist
+            .iter()
+            .any(|allowed| match pointer.strip_prefix(allowed) {
+                Some(rest) => rest.is_empty() || rest.starts_with('/'),
+                None => false,
+            })
+}
+
+/// Whether any value within the one at `pointer` may be selected by the allowlist.
+fn may_contain_selected(pointer: &str, allowlist: &[&str]) -> bool {
+    allowlist.is_empty()
+        || allowlist.iter().any(|allowed| {
+            allowed
+                .strip_prefix(pointer)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        || selected(pointer, allowlist)
+}
+
+/// Convert the string values at and below `pointer` with `convert`, in place.
+fn convert_json_at(
+    convert: &ConvertText,
+    value: &mut Value,
+    pointer: &mut String,
+    allowlist: &[&str],
+) -> Result<()> {
+    if !may_contain_selected(pointer, allowlist) {
+        return Ok(());
+    }
+    match value {
+        Value::String(text) if selected(pointer, allowlist) => {
+            *text = convert(text)?;
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = pointer.len();
+                pointer.push_str(&format!("/{}", index));
+                convert_json_at(convert, item, pointer, allowlist)?;
+                pointer.truncate(len);
+            }
+        }
+        Value::Object(members) => {
+            for (key, member) in members.iter_mut() {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&escape_token(key));
+                convert_json_at(convert, member, pointer, allowlist)?;
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Convert the string values of a parsed JSON value with `convert`, in place, checking the
+/// allowlist first.
+fn convert_value(convert: &ConvertText, value: &mut Value, allowlist: &[&str]) -> Result<()> {
+    for allowed in allowlist {
+        if !allowed.is_empty() && !allowed.starts_with('/') {
+            return Err(anyhow!(format!("Invalid JSON pointer {:?}", allowed)));
+        }
+    }
+    convert_json_at(convert, value, &mut String::new(), allowlist)
+}
+
+/// Convert the string values of a JSON document with `convert`, keeping its layout.
+fn convert_document(convert: &ConvertText, input: &str, allowlist: &[&str]) -> Result<String> {
+    let mut value: Value = serde_json::from_str(input).context("Failed to parse JSON")?;
+    convert_value(convert, &mut value, allowlist)?;
+    let mut output = match input.trim_end().contains('\n') {
+        true => serde_json::to_string_pretty(&value)?,
+        false => serde_json::to_string(&value)?,
+    };
+    if input.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Convert the string values of a JSON document with `convert`, such as a conversion that also
+/// records what it changed.  The allowlist and layout are as for [Engine::convert_json].
+pub fn convert_json_with(
+    input: &str,
+    allowlist: &[&str],
+    convert: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    convert_document(&convert, input, allowlist)
+}
+
+impl Engine {
+    /// Convert the string values of a parsed JSON value in place, such as an API payload about to
+    /// be sent, from an input variant to an output variant.  Object keys are left as they were.
+    /// If `allowlist` is not empty, only strings at or below one of the given JSON pointers are
+    /// converted, as with [Engine::convert_json].
+    pub fn convert_json_value(
+        &self,
+        from: Script,
+        to: Script,
+        value: &mut Value,
+        allowlist: &[&str],
+    ) -> Result<()> {
+        convert_value(
+            &|text| Ok(self.convert(from, to, text)?.join("")),
+            value,
+            allowlist,
+        )
+    }
+
+    /// Convert the string values of a JSON document, such as an i18n resource file, from an
+    /// input variant to an output variant.  Object keys, numbers, and the order of keys are
+    /// left as they were.  If `allowlist` is not empty, only strings at or below one of the
+    /// given JSON pointers (such as `/messages` or `/title`) are converted.  The output is
+    /// pretty-printed if the input spans several lines, and compact otherwise.
+    pub fn convert_json(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        allowlist: &[&str],
+    ) -> Result<String> {
+        convert_document(
+            &|text| Ok(self.convert(from, to, text)?.join("")),
+            input,
+            allowlist,
+        )
+    }
+
+    /// Convert the string values of a JSON document between two scripts given by name, which
+    /// may be registered custom scripts.  See [Engine::convert_json] and
+    /// [Engine::convert_named] for details.
+    pub fn convert_json_named(
+        &self,
+        from: &str,
+        to: &str,
+        input: &str,
+        allowlist: &[&str],
+    ) -> Result<String> {
+        self.resolve_script(from)?;
+        self.resolve_script(to)?;
+        convert_document(
+            &|text| Ok(self.convert_named(from, to, text)?.join("")),
+            input,
+            allowlist,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::{convert_json, convert_json_value};
+    use serde_json::json;
+
+    #[test]
+    fn test_convert_json() -> Result<()> {
+        let input = r#"{"z":"软件","a":["软件",1],"软件":{"x/y":"软件"}}"#;
+        assert_eq!(
+            r#"{"z":"軟體","a":["軟體",1],"软件":{"x/y":"軟體"}}"#,
+            convert_json(Script::CN, Script::TW, input, &[])?
+        );
+        assert_eq!(
+            r#"{"z":"软件","a":["軟體",1],"软件":{"x/y":"軟體"}}"#,
+            convert_json(Script::CN, Script::TW, input, &["/a/0", "/软件/x~1y"])?
+        );
+        assert!(convert_json(Script::CN, Script::TW, "{", &[]).is_err());
+        assert!(convert_json(Script::CN, Script::TW, input, &["a"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_json_value() -> Result<()> {
+        let mut value = json!({"name": "软件", "id": "软件", "tags": ["软件"]});
+        convert_json_value(Script::CN, Script::TW, &mut value, &["/name", "/tags"])?;
+        assert_eq!(
+            json!({"name": "軟體", "id": "软件", "tags": ["軟體"]}),
+            value
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use crate::Engine;
+
+/// A language other than Chinese that is written partly in Chinese characters.  Converting it
+/// with the Chinese phrase dictionaries gives nonsense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Japanese,
+    Korean,
+}
+
+/// Whether a character is a Chinese character (including Japanese kanji and Korean hanja).
+pub(crate) fn is_han(ch: char) -> bool {
+    matches!(ch, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{3134F}')
+}
+
+/// Whether a character is Japanese hiragana or katakana.
+fn is_kana(ch: char) -> bool {
+    matches!(ch, '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9D}')
+}
+
+/// Whether a character is Korean hangul.
+fn is_hangul(ch: char) -> bool {
+    matches!(ch, '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+/// Guess whether text is predominantly Japanese or Korean rather than Chinese, from the mix of
+/// scripts it uses.  Text is taken to be Japanese if at least a fifth of its CJK characters are
+/// kana, and Korean if most of them are hangul.  Returns `None` for Chinese text, and for text
+/// with no CJK characters at all.
+pub fn detect_language(text: &str) -> Option<Language> {
+    let (mut han, mut kana, mut hangul) = (0usize, 0usize, 0usize);
+    for ch in text.chars() {
+        if is_han(ch) {
+            han += 1;
+        } else if is_kana(ch) {
+            kana += 1;
+        } else if is_hangul(ch) {
+            hangul += 1;
+        }
+    }
+    let total = han + kana + hangul;
+    if total == 0 {
+        None
+    } else if hangul * 2 > total {
+        Some(Language::Korean)
+    } else if kana * 5 >= total {
+        Some(Language::Japanese)
+    } else {
+        None
+    }
+}
+
+impl Engine {
+    /// Set whether inputs that look like Japanese or Korean (see [detect_language]) are copied to
+    /// the output unconverted.  By default, every input is converted;
+    /// [Engine::convert_with_warnings] reports such inputs either way.
+    pub fn with_language_guard(mut self, language_guard: bool) -> Self {
+        self.language_guard = language_guard;
+        self
+    }
+
+    /// Whether the language guard keeps an input from being converted.
+    pub(crate) fn guards(&self, input: &str) -> bool {
+        self.language_guard && detect_language(input).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(Some(Language::Japanese), detect_language("学校に行きます"));
+        assert_eq!(Some(Language::Korean), detect_language("학교에 갑니다"));
+        assert_eq!(None, detect_language("他们的软件"));
+        assert_eq!(None, detect_language("hello"));
+    }
+
+    #[test]
+    fn test_language_guard() -> Result<()> {
+        let engine = Engine::new();
+        assert_eq!(
+            "學校に行きます",
+            engine
+                .convert(Script::CN, Script::TW, "学校に行きます")?
+                .join("")
+        );
+        let engine = engine.with_language_guard(true);
+        assert_eq!(
+            "学校に行きます",
+            engine
+                .convert(Script::CN, Script::TW, "学校に行きます")?
+                .join("")
+        );
+        assert_eq!(
+            "他們的軟體",
+            engine
+                .convert(Script::CN, Script::TW, "他们的软件")?
+                .join("")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::{chain, Engine, Script};
+
+/// A part of the input that could not be converted, and was copied to the output unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConversionError {
+    /// The byte range of the input.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bytes {}..{} were not converted: {}",
+            self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant like [Engine::convert], but
+    /// never fail: a segment that cannot be converted (for example, because a dictionary it
+    /// needs was left out of the build) is copied to the output as it was, and reported.
+    /// Adjacent segments that failed for the same reason are reported together.
+    pub fn convert_lenient(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> (Vec<String>, Vec<ConversionError>) {
+        if self.guards(input) {
+            return (vec![input.to_owned()], Vec::new());
+        }
+        let keys = chain(from, to);
+        let mut segments = Vec::new();
+        let mut errors: Vec<ConversionError> = Vec::new();
+        let mut offset = 0;
+        for (word, copied) in self.segments(input) {
+            let span = offset..offset + word.len();
+            offset = span.end;
+            if copied {
+                segments.push(word.to_owned());
+                continue;
+            }
+            match self.convert_word(&keys, word) {
+                Ok(converted) => segments.push(converted.into_owned()),
+                Err(error) => {
+                    segments.push(word.to_owned());
+                    let message = error.to_string();
+                    match errors.last_mut() {
+                        Some(last) if last.span.end == span.start && last.message == message => {
+                            last.span.end = span.end
+                        }
+                        _ => errors.push(ConversionError { span, message }),
+                    }
+                }
+            }
+        }
+        (segments, errors)
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_lenient;
+
+    #[test]
+    fn test_convert_lenient() {
+        let (result, errors) = convert_lenient(Script::CN, Script::TW, "他们的软件");
+        assert_eq!("他們的軟體", result.join(""));
+        assert!(errors.is_empty());
+    }
+
+    #[cfg(not(feature = "dict-hk"))]
+    #[test]
+    fn test_convert_lenient_missing_dictionary() {
+        let (result, errors) = convert_lenient(Script::CN, Script::HK, "他们的软件");
+        assert_eq!("他们的软件", result.join(""));
+        assert_eq!(1, errors.len());
+        assert_eq!(0..15, errors[0].span);
+    }
+}
@@ -1,21 +1,219 @@
+//! Convert between Chinese scripts.
+//!
+//! # Stability
+//!
+//! The conversion functions, [Engine], [Script], and the option types passed to the `with_*`
+//! builder methods (such as [Passthrough] and [Punctuation]) follow semantic versioning.  Option
+//! structs and reported enums are `#[non_exhaustive]`, so that fields and variants can be added
+//! in minor releases; start from `Default::default()` and set the fields you need.
+//!
+//! The `unstable` feature exposes the machinery underneath: [Dictionary] tables and
+//! [Engine::convert_with], OpenCC configuration pipelines, and conversion tracing.  These may
+//! change in any release.
+
+#![cfg_attr(
+    not(any(
+        feature = "ffi",
+        feature = "napi",
+        feature = "python",
+        feature = "uniffi",
+        feature = "wasm"
+    )),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(
+    any(
+        feature = "ffi",
+        feature = "napi",
+        feature = "python",
+        feature = "uniffi",
+        feature = "wasm"
+    ),
+    deny(unsafe_code)
+)]
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+#[cfg(feature = "jieba")]
 use std::io::BufReader;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
 
-use anyhow::Result;
-use enum_map::{enum_map, Enum, EnumMap};
+use anyhow::{anyhow, Result};
+use enum_map::{Enum, EnumMap};
+#[cfg(feature = "jieba")]
 use jieba_rs::Jieba;
+#[cfg(not(feature = "uncompressed"))]
 use miniz_oxide::inflate::decompress_to_vec;
+#[cfg(feature = "global")]
 use once_cell::sync::Lazy;
-use trie_rs::map::Trie;
 
+mod ambiguity;
+#[cfg(feature = "arena")]
+mod arena;
+#[cfg(not(target_arch = "wasm32"))]
+mod bounded;
+mod buffer;
+mod cache;
+mod candidates;
+mod characters;
+mod chunks;
+#[cfg(feature = "opencc-config")]
+mod config;
+mod custom;
+mod detect;
+mod dictionary;
+#[cfg(feature = "eval")]
+pub mod eval;
+#[cfg(feature = "external-dicts")]
+mod external;
+#[cfg(feature = "ffi")]
+#[allow(unsafe_code)]
+pub mod ffi;
+#[cfg(feature = "fixtures")]
+mod fixtures;
+mod html;
+mod incremental;
+#[cfg(feature = "json")]
+mod json;
+mod language;
+mod lenient;
+mod lines;
+mod lookup;
+#[cfg(feature = "uniffi")]
+#[allow(unsafe_code)]
+mod mobile;
+// napi only registers its exports outside of tests, so they are unused there.
+#[cfg(all(feature = "napi", not(test)))]
+#[allow(unsafe_code)]
+mod node;
+mod normalize;
+mod offsets;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod passthrough;
+#[cfg(feature = "unstable")]
+mod pipeline;
+mod prewarm;
+mod protect;
+mod punctuation;
+#[cfg(feature = "python")]
+#[allow(unsafe_code)]
+mod python;
+mod ranges;
+mod ranker;
+mod regional;
+mod segmenter;
+#[cfg(all(feature = "jieba", not(target_arch = "wasm32")))]
+mod segmenter_cache;
+mod selftest;
+mod sink;
+mod stats;
+mod suggest;
+#[cfg(feature = "unstable")]
+mod trace;
+mod variants;
+mod verify;
+mod warning;
 #[cfg(feature = "wasm")]
-use wasm_bindgen::prelude::*;
+#[allow(unsafe_code)]
+mod wasm;
+mod width;
+mod word_cache;
+mod words;
+
+pub use ambiguity::Ambiguity;
+#[cfg(not(target_arch = "wasm32"))]
+pub use bounded::{BoundedConversion, Continuation};
+pub use cache::{CacheOptions, CacheStats};
+#[cfg(not(feature = "jieba"))]
+use characters::CharacterSegmenter;
+pub use chunks::ConvertChunks;
+pub use custom::CustomScript;
+pub use detect::ScriptDetection;
+#[cfg(not(feature = "unstable"))]
+use dictionary::Dictionary;
+#[cfg(feature = "unstable")]
+pub use dictionary::Dictionary;
+#[cfg(feature = "fixtures")]
+pub use fixtures::{fixtures, Fixture};
+pub use html::convert_html_with;
+pub use incremental::IncrementalConversion;
+#[cfg(feature = "json")]
+pub use json::convert_json_with;
+pub use language::{detect_language, Language};
+pub use lenient::ConversionError;
+pub use lines::ConvertLines;
+pub use lookup::DictionaryEntry;
+pub use normalize::Normalization;
+pub use offsets::OffsetMap;
+pub use options::ConvertOptions;
+pub use passthrough::Passthrough;
+#[cfg(feature = "unstable")]
+pub use pipeline::Pipeline;
+pub use protect::PERSON_NAME_TAGS;
+pub use punctuation::Punctuation;
+pub use ranker::{CandidateRanker, FirstCandidate, FrequencyRanker};
+#[cfg(feature = "jieba")]
+use segmenter::JiebaSegmenter;
+pub use segmenter::{CutOptions, Segmenter};
+pub use selftest::SelfTestReport;
+pub use sink::{ConversionSink, DiffSink, RubySink, Span, SpanSink, WriteSink, RUBY_TEMPLATE};
+pub use stats::ConversionStats;
+pub use suggest::Suggestion;
+#[cfg(feature = "unstable")]
+pub use trace::TraceRecord;
+pub use verify::{LossySpan, VerifyReport};
+pub use warning::Warning;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+pub use width::{convert_width, Width};
+
+/// Dictionaries are each loaded on first use, and at most once; `None` marks a dictionary that
+/// is not included in the build.
+type Dictionaries = EnumMap<DictionaryKeys, OnceLock<Option<Dictionary>>>;
 
-type Dictionary = Trie<u8, String>;
+/// The alternative candidates for the ambiguous entries of a dictionary, the first of which is
+/// the one used for conversion.
+type Ambiguities = HashMap<String, Vec<String>>;
 
 include!(concat!(env!("OUT_DIR"), "/dicts.rs"));
 
+/// Decompress data embedded at build time, or `None` if it is corrupt.
+#[cfg(not(feature = "uncompressed"))]
+fn decompress(bytes: &'static [u8]) -> Option<Cow<'static, [u8]>> {
+    decompress_to_vec(bytes).ok().map(Cow::Owned)
+}
+
+/// Use data embedded at build time in place, since the `uncompressed` feature stored it as it is.
+#[cfg(feature = "uncompressed")]
+fn decompress(bytes: &'static [u8]) -> Option<Cow<'static, [u8]>> {
+    Some(Cow::Borrowed(bytes))
+}
+
+/// The multi-character keys of the embedded dictionaries, compressed, which are added to the
+/// segmentation dictionary.
+#[cfg(feature = "jieba")]
+const EXTRA_WORDS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/keys.zpostcard"));
+
+/// Build the segmenter from Jieba's dictionary, adding every multi-character dictionary key so
+/// that phrases are kept whole.  `added` is called with each key and the frequency it was given.
+#[cfg(feature = "jieba")]
+fn build_jieba(jieba_dict: &[u8], mut added: impl FnMut(&str, usize)) -> Jieba {
+    let mut jieba = Jieba::with_dict(&mut BufReader::new(jieba_dict)).unwrap();
+    let key_bytes = decompress(EXTRA_WORDS).expect("failed to decompress keys");
+    let keys: Vec<String> = postcard::from_bytes(&key_bytes).expect("failed to load extra words");
+    for key in keys {
+        let frequency = jieba.add_word(key.as_str(), None, None);
+        added(&key, frequency);
+    }
+    jieba
+}
+
 /// Variant is a source or destination dialect.
-#[derive(PartialEq, Eq, Hash, Enum, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Enum, Clone, Copy)]
 pub enum Script {
     /// OpenCC Standard.
     ST,
@@ -27,121 +225,1014 @@ pub enum Script {
     HK,
 }
 
-static CONFIGS_TO_STANDARD: Lazy<EnumMap<Script, DictionaryKeys>> = Lazy::new(|| {
-    enum_map! {
-        Script::ST => DictionaryKeys::FromStandard,
-        Script::CN => DictionaryKeys::FromChina,
-        Script::TW => DictionaryKeys::FromTaiwan,
-        Script::HK => DictionaryKeys::FromHongKong,
+/// The short names of the built-in scripts.
+const SCRIPT_NAMES: [(&str, Script); 4] = [
+    ("st", Script::ST),
+    ("cn", Script::CN),
+    ("tw", Script::TW),
+    ("hk", Script::HK),
+];
+
+/// Look up a built-in script by its short name.
+fn builtin_script(name: &str) -> Option<Script> {
+    SCRIPT_NAMES
+        .iter()
+        .find(|(script_name, _)| *script_name == name)
+        .map(|(_, script)| *script)
+}
+
+impl Script {
+    /// Every built-in script, for listing them without naming each one.
+    pub fn all() -> &'static [Script] {
+        &[Script::ST, Script::CN, Script::TW, Script::HK]
     }
-});
 
-static CONFIGS_FROM_STANDARD: Lazy<EnumMap<Script, DictionaryKeys>> = Lazy::new(|| {
-    enum_map! {
-        Script::ST => DictionaryKeys::ToStandard,
-        Script::CN => DictionaryKeys::ToChina,
-        Script::TW => DictionaryKeys::ToTaiwan,
-        Script::HK => DictionaryKeys::ToHongKong,
+    /// The short name of the script: `st`, `cn`, `tw`, or `hk`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Script::ST => "st",
+            Script::CN => "cn",
+            Script::TW => "tw",
+            Script::HK => "hk",
+        }
     }
-});
 
-static JIEBA: Lazy<Jieba> = Lazy::new(|| {
-    let mut jieba_dict_buf = BufReader::new(JIEBA_DICT.as_slice());
-    let mut jieba = Jieba::with_dict(&mut jieba_dict_buf).unwrap();
-    let key_bytes = decompress_to_vec(include_bytes!(concat!(env!("OUT_DIR"), "/keys.zpostcard")))
-        .expect("failed to decompress keys");
-    let keys: Vec<String> = postcard::from_bytes(&key_bytes).expect("failed to load extra words");
-    for key in keys {
-        jieba.add_word(key.as_str(), None, None);
+    /// What the script is, in English, for showing to people, such as `Traditional Chinese
+    /// (Taiwan)`.
+    pub fn description(self) -> &'static str {
+        match self {
+            Script::ST => "Traditional Chinese (OpenCC Standard)",
+            Script::CN => "Simplified Chinese",
+            Script::TW => "Traditional Chinese (Taiwan)",
+            Script::HK => "Traditional Chinese (Hong Kong)",
+        }
     }
-    jieba
-});
 
-/// Convert a single word.
-fn convert_word<'a>(keys: impl Iterator<Item = &'a DictionaryKeys>, input: &str) -> Result<String> {
-    let mut word = input.to_owned();
-    for key in keys {
-        let mut parts = Vec::new();
-        let dict = &DICTIONARIES[*key];
-        let mut offset = 0;
-        while offset < word.len() {
-            let result: Option<(String, &String)> =
-                dict.common_prefix_search(&word[offset..]).last();
-            match result {
-                Some((matched, value)) => {
-                    parts.push(value.to_owned());
-                    offset += matched.len();
+    /// The BCP 47 language tag for Chinese written in this script, such as `zh-TW`, for
+    /// documents that declare their language.  OpenCC Standard is `zh-Hant`.
+    pub fn language_tag(self) -> &'static str {
+        match self {
+            Script::ST => "zh-Hant",
+            Script::CN => "zh-CN",
+            Script::TW => "zh-TW",
+            Script::HK => "zh-HK",
+        }
+    }
+
+    /// The script for a BCP 47 language tag for Chinese, such as `zh-Hant-TW`, `zh-Hans`, or
+    /// `zh-HK`, ignoring case and any subtags after the script and region.  A `Hans` script
+    /// subtag means Simplified Chinese whatever the region; otherwise Taiwan, Hong Kong, and
+    /// Macau have their own scripts, `zh-Hant` alone or with another region is OpenCC Standard,
+    /// and `zh` alone or with another region is Simplified Chinese.  Returns `None` for tags of
+    /// other languages.
+    pub fn from_language_tag(tag: &str) -> Option<Self> {
+        let subtags: Vec<String> = tag
+            .split(['-', '_'])
+            .map(|subtag| subtag.to_ascii_lowercase())
+            .collect();
+        let (language, rest) = subtags.split_first()?;
+        if !matches!(language.as_str(), "zh" | "cmn" | "yue") {
+            return None;
+        }
+        let script = rest
+            .first()
+            .filter(|subtag| subtag.len() == 4)
+            .map(String::as_str);
+        let region = rest
+            .get(usize::from(script.is_some()))
+            .filter(|subtag| {
+                subtag.len() == 2
+                    || (subtag.len() == 3 && subtag.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .map(String::as_str);
+        Some(match (script, region) {
+            (Some("hans"), _) => Script::CN,
+            (_, Some("tw")) => Script::TW,
+            (_, Some("hk" | "mo")) => Script::HK,
+            (Some("hant"), _) => Script::ST,
+            (None, _) if language == "yue" => Script::HK,
+            (None, _) => Script::CN,
+            (Some(_), _) => return None,
+        })
+    }
+
+    /// The dictionary converting from this script to OpenCC Standard.
+    fn dictionary_to_standard(self) -> DictionaryKeys {
+        match self {
+            Script::ST => DictionaryKeys::FromStandard,
+            Script::CN => DictionaryKeys::FromChina,
+            Script::TW => DictionaryKeys::FromTaiwan,
+            Script::HK => DictionaryKeys::FromHongKong,
+        }
+    }
+
+    /// The dictionary converting from OpenCC Standard to this script.
+    fn dictionary_from_standard(self) -> DictionaryKeys {
+        match self {
+            Script::ST => DictionaryKeys::ToStandard,
+            Script::CN => DictionaryKeys::ToChina,
+            Script::TW => DictionaryKeys::ToTaiwan,
+            Script::HK => DictionaryKeys::ToHongKong,
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Script {
+    type Err = anyhow::Error;
+
+    /// Parse a script from its short name, a common alias such as `traditional` (for OpenCC
+    /// Standard), or a language tag such as `zh-TW` or `zh-Hans` (see
+    /// [Script::from_language_tag]), ignoring case.
+    fn from_str(name: &str) -> Result<Self> {
+        let alias = name.to_ascii_lowercase().replace('_', "-");
+        let script = match alias.as_str() {
+            "simplified" => Some(Script::CN),
+            "taiwan" => Some(Script::TW),
+            "hongkong" | "hong-kong" => Some(Script::HK),
+            "traditional" | "standard" | "opencc" => Some(Script::ST),
+            _ => builtin_script(&alias).or_else(|| Script::from_language_tag(&alias)),
+        };
+        script.ok_or(anyhow!(format!("unknown script {}", name)))
+    }
+}
+
+/// Scripts are serialized as their short names, and deserialized from any name [Script::from_str]
+/// accepts.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Script {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Script {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Every pair of distinct built-in scripts that can be converted between, as `(from, to)`.
+pub fn supported_pairs() -> impl Iterator<Item = (Script, Script)> {
+    Script::all().iter().flat_map(|&from| {
+        Script::all()
+            .iter()
+            .filter(move |&&to| to != from)
+            .map(move |&to| (from, to))
+    })
+}
+
+/// The dictionaries to apply, in order, to convert between two scripts.  Conversions between Hong
+/// Kong and Taiwan use a single dictionary composed at build time, rather than a round trip
+/// through OpenCC Standard.
+fn chain(from: Script, to: Script) -> Chain {
+    match (from, to) {
+        (Script::HK, Script::TW) => Chain::one(DictionaryKeys::HongKongToTaiwan),
+        (Script::TW, Script::HK) => Chain::one(DictionaryKeys::TaiwanToHongKong),
+        _ => Chain::new(&[from.dictionary_to_standard(), to.dictionary_from_standard()]),
+    }
+}
+
+/// A sequence of one to three dictionaries, kept inline so that looking it up does not allocate.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Chain {
+    keys: [DictionaryKeys; 3],
+    len: usize,
+}
+
+impl Chain {
+    fn one(key: DictionaryKeys) -> Self {
+        Chain::new(&[key])
+    }
+
+    /// A chain of the given dictionaries, of which there must be one to three.
+    fn new(keys: &[DictionaryKeys]) -> Self {
+        // The unused places repeat the first dictionary, so that equal chains compare equal.
+        let mut chain = Chain {
+            keys: [keys[0]; 3],
+            len: keys.len(),
+        };
+        chain.keys[..keys.len()].copy_from_slice(keys);
+        chain
+    }
+}
+
+impl std::ops::Deref for Chain {
+    type Target = [DictionaryKeys];
+
+    fn deref(&self) -> &[DictionaryKeys] {
+        &self.keys[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a Chain {
+    type Item = &'a DictionaryKeys;
+    type IntoIter = std::slice::Iter<'a, DictionaryKeys>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Apply a single dictionary to a word, replacing the longest matches first.  Returns `None` if
+/// nothing was replaced, so that callers can keep the word without copying it.
+fn replace_pass(dict: &Dictionary, word: &str) -> Option<String> {
+    let mut output = String::new();
+    replace_pass_into(dict, word, |_, value| value, &mut output).then_some(output)
+}
+
+/// Apply a single dictionary to a word like [replace_pass], replacing each match with what
+/// `choose` returns given the matched key and the dictionary's value, and writing the result
+/// over `output` so that its allocation can be reused.  Returns whether anything was replaced;
+/// if not, `output` is left cleared or partly written and should be ignored.
+fn replace_pass_into<'d>(
+    dict: &'d Dictionary,
+    word: &str,
+    choose: impl Fn(&str, &'d str) -> &'d str,
+    output: &mut String,
+) -> bool {
+    output.clear();
+    let mut replaced = false;
+    for piece in dict.pieces(word) {
+        let replacement = piece
+            .replacement
+            .map(|value| choose(piece.text, value))
+            .filter(|chosen| *chosen != piece.text);
+        if let Some(value) = replacement {
+            if !replaced {
+                output.reserve(word.len());
+                output.push_str(&word[..piece.offset]);
+                replaced = true;
+            }
+            output.push_str(value);
+        } else if replaced {
+            output.push_str(piece.text);
+        }
+    }
+    replaced
+}
+
+/// Make the output of a pass, written into `spare`, the word, keeping the word's old buffer as
+/// the spare for the next pass.
+fn swap_buffers(word: &mut Cow<'_, str>, spare: &mut String) {
+    let previous = std::mem::replace(word, Cow::Owned(std::mem::take(spare)));
+    if let Cow::Owned(previous) = previous {
+        *spare = previous;
+    }
+}
+
+/// Apply a single dictionary to a word, replacing the longest matches first, and borrowing the
+/// word if nothing was replaced.
+fn convert_pass<'a>(dict: &Dictionary, word: &'a str) -> Cow<'a, str> {
+    replace_pass(dict, word).map_or(Cow::Borrowed(word), Cow::Owned)
+}
+
+/// Engine holds the dictionaries and segmenter needed for conversion.  The segmenter is loaded
+/// when the engine is created, and each dictionary the first time it is needed; everything is
+/// released when the engine is dropped.
+///
+/// An engine is `Send + Sync`, so one engine can be shared by many threads (for example in an
+/// `Arc`) and used for conversions at the same time.  Conversion never takes a lock, except to
+/// load a dictionary the first time it is used, to look up custom scripts in
+/// [Engine::convert_named], and to use the cache set with [Engine::with_cache]; call
+/// [Engine::load] ahead of time to keep the first conversions from waiting on each other.
+pub struct Engine {
+    dictionaries: Dictionaries,
+    ambiguities: EnumMap<DictionaryKeys, OnceLock<Option<Ambiguities>>>,
+    segmenter: Box<dyn Segmenter>,
+    passthrough: Passthrough,
+    punctuation: Punctuation,
+    normalization: Normalization,
+    width: Width,
+    ranker: Option<Box<dyn CandidateRanker>>,
+    language_guard: bool,
+    protected: Option<Dictionary>,
+    skip_rules: Vec<protect::SkipRule>,
+    protected_tags: HashSet<String>,
+    regional: EnumMap<DictionaryKeys, Option<Dictionary>>,
+    custom_scripts: RwLock<HashMap<String, Arc<CustomScript>>>,
+    cache: Option<cache::Cache>,
+    word_cache: Option<word_cache::WordCache>,
+}
+
+// Fails to compile if the engine stops being shareable between threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Engine>();
+};
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    /// Create a new engine from the embedded data, segmenting with Jieba.
+    pub fn new() -> Self {
+        Self::with_cut_options(CutOptions::default())
+    }
+
+    /// Create a new engine from the embedded data, segmenting with Jieba using the given options.
+    #[cfg(feature = "jieba")]
+    pub fn with_cut_options(options: CutOptions) -> Self {
+        let jieba = build_jieba(&load_jieba_dict(), |_, _| {});
+        Self::with_segmenter(JiebaSegmenter { jieba, options })
+    }
+
+    /// Create a new engine from the embedded data, splitting text into characters.  Without the
+    /// `jieba` feature there is no segmenter to configure, so the options are ignored.
+    #[cfg(not(feature = "jieba"))]
+    pub fn with_cut_options(options: CutOptions) -> Self {
+        let _ = options;
+        Self::with_segmenter(CharacterSegmenter)
+    }
+
+    /// Create a new engine from the embedded data, segmenting with the given segmenter instead of
+    /// Jieba.
+    pub fn with_segmenter(segmenter: impl Segmenter + 'static) -> Self {
+        Engine {
+            dictionaries: Dictionaries::default(),
+            ambiguities: EnumMap::default(),
+            segmenter: Box::new(segmenter),
+            passthrough: Passthrough::default(),
+            punctuation: Punctuation::default(),
+            normalization: Normalization::default(),
+            width: Width::default(),
+            ranker: None,
+            language_guard: false,
+            protected: None,
+            skip_rules: Vec::new(),
+            protected_tags: HashSet::new(),
+            regional: EnumMap::default(),
+            custom_scripts: RwLock::new(HashMap::new()),
+            cache: None,
+            word_cache: None,
+        }
+    }
+
+    /// Set which kinds of segments are passed through without looking them up.
+    pub fn with_passthrough(mut self, passthrough: Passthrough) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Get a dictionary, loading it if necessary.  Fails if the dictionary's region feature was
+    /// disabled at build time.
+    fn dictionary(&self, key: DictionaryKeys) -> Result<&Dictionary> {
+        #[cfg(not(feature = "external-dicts"))]
+        let dictionary = self.dictionaries[key].get_or_init(|| load_dictionary(key));
+        #[cfg(feature = "external-dicts")]
+        let dictionary = self.dictionaries[key].get().ok_or(anyhow!(format!(
+            "dictionary {:?} has not been registered",
+            key
+        )))?;
+        dictionary.as_ref().ok_or(anyhow!(format!(
+            "dictionary {:?} is not included in this build",
+            key
+        )))
+    }
+
+    /// Get the ambiguous entries of a dictionary, loading them if necessary.
+    fn ambiguities(&self, key: DictionaryKeys) -> Result<&Ambiguities> {
+        #[cfg(not(feature = "external-dicts"))]
+        let ambiguities = self.ambiguities[key].get_or_init(|| load_ambiguities(key));
+        #[cfg(feature = "external-dicts")]
+        let ambiguities = self.ambiguities[key].get().ok_or(anyhow!(format!(
+            "dictionary {:?} has not been registered",
+            key
+        )))?;
+        ambiguities.as_ref().ok_or(anyhow!(format!(
+            "dictionary {:?} is not included in this build",
+            key
+        )))
+    }
+
+    /// Load the dictionaries needed to convert between two scripts ahead of time, so that the
+    /// first conversion does not need to.
+    pub fn load(&self, from: Script, to: Script) -> Result<()> {
+        for key in &chain(from, to) {
+            self.dictionary(*key)?;
+        }
+        Ok(())
+    }
+
+    /// Convert a single word, borrowing it if no dictionary changed it.
+    fn convert_word<'a>(&self, keys: &Chain, input: &'a str) -> Result<Cow<'a, str>> {
+        let Some(cache) = &self.word_cache else {
+            return self.convert_word_uncached(keys, input);
+        };
+        if let Some(cached) = cache.get(keys, input) {
+            return Ok(cached.map_or(Cow::Borrowed(input), Cow::Owned));
+        }
+        let converted = self.convert_word_uncached(keys, input)?;
+        let changed = (converted != input).then(|| converted.clone().into_owned());
+        cache.insert(keys, input, changed);
+        Ok(converted)
+    }
+
+    /// Convert a single word without the word cache, borrowing it if no dictionary changed it.
+    fn convert_word_uncached<'a>(&self, keys: &Chain, input: &'a str) -> Result<Cow<'a, str>> {
+        let mut word = self.normalization.normalize(input);
+        // Each pass writes into the spare buffer, which then swaps with the word, so that a
+        // chain of passes allocates at most two strings.
+        let mut spare = String::new();
+        for key in keys {
+            if let Some(regional) = &self.regional[*key] {
+                if replace_pass_into(regional, &word, |_, value| value, &mut spare) {
+                    swap_buffers(&mut word, &mut spare);
                 }
-                None => {
-                    match word[offset..].chars().next() {
-                        Some(ch) => {
-                            let len = ch.len_utf8();
-                            parts.push(word[offset..offset + len].to_owned());
-                            offset += len;
-                        }
-                        None => {
-                            parts.push(word[offset..].to_owned());
-                            offset += word[offset..].len();
-                        }
-                    };
+            }
+            let dict = self.dictionary(*key)?;
+            let replaced = match &self.ranker {
+                None => replace_pass_into(dict, &word, |_, value| value, &mut spare),
+                Some(ranker) => {
+                    let ambiguities = self.ambiguities(*key)?;
+                    replace_pass_into(
+                        dict,
+                        &word,
+                        |matched, value| match ambiguities.get(matched) {
+                            Some(candidates) => candidates
+                                .get(ranker.rank(matched, candidates, &word))
+                                .map_or(value, String::as_str),
+                            None => value,
+                        },
+                        &mut spare,
+                    )
                 }
+            };
+            if replaced {
+                swap_buffers(&mut word, &mut spare);
+            }
+        }
+        Ok(word)
+    }
+
+    /// Convert a string from an input variant to an output variant, passing each segment to
+    /// `sink` as it is converted.
+    pub fn convert_into<S: ConversionSink + ?Sized>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        sink: &mut S,
+    ) -> Result<()> {
+        if self.guards(input) {
+            return sink.unchanged(input);
+        }
+        let segments = self.segments_for(&chain(from, to), input);
+        self.convert_segments_into(from, to, input, segments, sink)
+    }
+
+    /// Convert the segments of an input, passing each to `sink` as it is converted.
+    fn convert_segments_into<S: ConversionSink + ?Sized>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        segments: Vec<(&str, bool)>,
+        sink: &mut S,
+    ) -> Result<()> {
+        let keys = chain(from, to);
+        let segments = self.punctuation.merge_ellipses(input, segments);
+        let mut previous = None;
+        for (word, copied) in segments {
+            if copied {
+                sink.unchanged(word)?;
+                previous = word.chars().last().or(previous);
+                continue;
+            }
+            let converted = self.width.apply(self.convert_word(&keys, word)?);
+            let converted = self.punctuation.apply(to, converted, previous);
+            previous = converted.chars().last().or(previous);
+            if converted == word {
+                sink.unchanged(word)?;
+            } else {
+                sink.replaced(word, &converted)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a string from an input variant to an output variant.
+    pub fn convert(&self, from: Script, to: Script, input: &str) -> Result<Vec<String>> {
+        if let Some(segments) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(from, to, input))
+        {
+            return Ok(segments);
+        }
+        let mut segments = Vec::new();
+        self.convert_into(from, to, input, &mut segments)?;
+        if let Some(cache) = &self.cache {
+            cache.insert(from, to, input, &segments);
+        }
+        Ok(segments)
+    }
+
+    /// Convert a string from an input variant to an output variant, borrowing the segments that
+    /// were not changed from the input instead of copying them.
+    pub fn convert_cow<'a>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &'a str,
+    ) -> Result<Vec<Cow<'a, str>>> {
+        if self.guards(input) {
+            return Ok(vec![Cow::Borrowed(input)]);
+        }
+        let keys = chain(from, to);
+        self.segments_for(&keys, input)
+            .into_iter()
+            .map(|(word, copied)| match copied {
+                true => Ok(Cow::Borrowed(word)),
+                false => self.convert_word(&keys, word),
+            })
+            .collect()
+    }
+
+    /// Convert a string from an input variant to an output variant, pairing each segment of the
+    /// input with its converted form.
+    pub fn convert_aligned<'a>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &'a str,
+    ) -> Result<Vec<(&'a str, String)>> {
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        Ok(sink
+            .spans
+            .into_iter()
+            .map(|span| (&input[span.start..span.end], span.converted))
+            .collect())
+    }
+
+    /// Convert a string from an input variant to an output variant by greedy longest matching
+    /// over the whole input, without segmenting it first.  This is much faster than
+    /// [Engine::convert] on large inputs, but cannot use segmentation to pick between
+    /// overlapping phrases.
+    pub fn convert_greedy(&self, from: Script, to: Script, input: &str) -> Result<String> {
+        if self.guards(input) {
+            return Ok(input.to_owned());
+        }
+        let keys = chain(from, to);
+        if !self.protects() {
+            return Ok(self
+                .width
+                .apply(self.convert_word(&keys, input)?)
+                .into_owned());
+        }
+        let mut output = String::with_capacity(input.len());
+        for (run, protected) in self.protected_runs(input) {
+            match protected {
+                true => output.push_str(run),
+                false => output.push_str(&self.width.apply(self.convert_word(&keys, run)?)),
             }
         }
-        word = parts.join("");
+        Ok(output)
+    }
+
+    /// Convert already-segmented tokens from an input variant to an output variant.
+    /// Unlike [Engine::convert], the input is not re-segmented; each token is converted as a unit.
+    pub fn convert_tokens(&self, from: Script, to: Script, tokens: &[&str]) -> Result<Vec<String>> {
+        let keys = chain(from, to);
+        tokens
+            .iter()
+            .map(|token| Ok(self.convert_word(&keys, token)?.into_owned()))
+            .collect()
     }
-    Ok(word)
+
+    /// Convert a single character by its single-character dictionary entries, without
+    /// segmenting or allocating, for rendering layers that substitute one code point at a time.
+    /// Returns `None` if the character converts to more than one character.  Phrase entries and
+    /// any [CandidateRanker] are not used, so this can differ from converting the character as
+    /// part of some text.
+    pub fn convert_char(&self, from: Script, to: Script, ch: char) -> Result<Option<char>> {
+        let mut ch = self.normalization.fold_char(ch);
+        for key in &chain(from, to) {
+            let mut buffer = [0; 4];
+            let Some((_, value)) = self
+                .dictionary(*key)?
+                .longest_prefix(ch.encode_utf8(&mut buffer))
+            else {
+                continue;
+            };
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(converted), None) => ch = converted,
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(ch))
+    }
+}
+
+/// The options [ENGINE] is created with, set by [configure].
+#[cfg(feature = "global")]
+static OPTIONS: OnceLock<ConvertOptions> = OnceLock::new();
+
+/// The engine used by the free conversion functions, loaded on first use.
+#[cfg(feature = "global")]
+static ENGINE: Lazy<Engine> =
+    Lazy::new(|| Engine::from_options(OPTIONS.get_or_init(ConvertOptions::default)));
+
+/// Set the options the free conversion functions convert with.  Fails once the first conversion
+/// has loaded their engine, or if they were already configured.
+#[cfg(feature = "global")]
+pub fn configure(options: ConvertOptions) -> Result<()> {
+    OPTIONS
+        .set(options)
+        .map_err(|_| anyhow!("conversion options must be set before the first conversion"))
 }
 
+// The UniFFI scaffolding for the Swift and Kotlin bindings in `mobile`.
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
 /// Convert a string from an input variant to an output variant.
+#[cfg(feature = "global")]
 pub fn convert(from: Script, to: Script, input: &str) -> Result<Vec<String>> {
-    let all_words = JIEBA.cut(input, true);
-    let words = all_words.iter().cloned();
-    let keys = [CONFIGS_TO_STANDARD[from], CONFIGS_FROM_STANDARD[to]];
-    let result = words.filter_map(move |word| convert_word(keys.iter(), word).ok());
+    ENGINE.convert(from, to, input)
+}
 
-    Ok(result.collect())
+/// List every conversion of a word or phrase.  See [Engine::candidates] for details.
+#[cfg(feature = "global")]
+pub fn candidates(from: Script, to: Script, word: &str) -> Result<Vec<String>> {
+    ENGINE.candidates(from, to, word)
 }
 
-#[cfg(feature = "wasm")]
-pub struct JSError {
-    val: String,
+/// Convert a single character.  See [Engine::convert_char] for details.
+#[cfg(feature = "global")]
+pub fn convert_char(from: Script, to: Script, ch: char) -> Result<Option<char>> {
+    ENGINE.convert_char(from, to, ch)
 }
 
-#[cfg(feature = "wasm")]
-impl Into<JsValue> for JSError {
-    fn into(self) -> JsValue {
-        JsValue::from_str(self.val.as_str())
-    }
+/// Guess which script some text is written in.  See [Engine::detect_script] for details.
+#[cfg(feature = "global")]
+pub fn detect_script(input: &str) -> Result<ScriptDetection> {
+    ENGINE.detect_script(input)
 }
 
-#[cfg(feature = "wasm")]
-impl<T: ToString> From<T> for JSError {
-    fn from(value: T) -> Self {
-        JSError {
-            val: value.to_string(),
-        }
-    }
+/// Load the global engine and the dictionaries for `pair`, or every dictionary if `None`, ahead
+/// of time.  See [Engine::prewarm] for details.
+#[cfg(feature = "global")]
+pub fn prewarm(pair: Option<(Script, Script)>) -> Result<()> {
+    ENGINE.prewarm(pair)
 }
 
-#[cfg(feature = "wasm")]
-#[wasm_bindgen(js_name = convert)]
-pub fn convert_export(from: &str, to: &str, input: &str) -> std::result::Result<String, JSError> {
-    let from_script = match from {
-        "cn" => Script::CN,
-        "tw" => Script::TW,
-        "hk" => Script::HK,
-        _ => return Err(format!("invalid from script {}", from).into()),
-    };
-    let to_script = match to {
-        "cn" => Script::CN,
-        "tw" => Script::TW,
-        "hk" => Script::HK,
-        _ => return Err(format!("invalid to script {}", to).into()),
-    };
-    Ok(convert(from_script, to_script, input)?.join(""))
-}
-
-#[cfg(test)]
+/// Whether the global engine is loaded, with the dictionaries for converting between two scripts.
+#[cfg(feature = "global")]
+pub fn is_loaded(from: Script, to: Script) -> bool {
+    Lazy::get(&ENGINE).is_some_and(|engine| engine.is_loaded(from, to))
+}
+
+/// Check that the embedded data is intact.  See [Engine::self_test] for details.
+#[cfg(feature = "global")]
+pub fn self_test() -> Result<SelfTestReport> {
+    ENGINE.self_test()
+}
+
+/// Convert a string from an input variant to an output variant, reporting ambiguous phrases.
+/// See [Engine::convert_with_ambiguities] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_ambiguities(
+    from: Script,
+    to: Script,
+    input: &str,
+) -> Result<(Vec<String>, Vec<Ambiguity>)> {
+    ENGINE.convert_with_ambiguities(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, counting what was converted.
+/// See [Engine::convert_with_stats] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_stats(
+    from: Script,
+    to: Script,
+    input: &str,
+) -> Result<(Vec<String>, ConversionStats)> {
+    ENGINE.convert_with_stats(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, passing each segment to `sink`.
+/// See [Engine::convert_into] for details.
+#[cfg(feature = "global")]
+pub fn convert_into<S: ConversionSink + ?Sized>(
+    from: Script,
+    to: Script,
+    input: &str,
+    sink: &mut S,
+) -> Result<()> {
+    ENGINE.convert_into(from, to, input, sink)
+}
+
+/// Convert a string from an input variant to an output variant, writing each segment to `writer`.
+/// See [Engine::convert_to_writer] for details.
+#[cfg(feature = "global")]
+pub fn convert_to_writer<W: std::io::Write + ?Sized>(
+    from: Script,
+    to: Script,
+    input: &str,
+    writer: &mut W,
+) -> Result<()> {
+    ENGINE.convert_to_writer(from, to, input, writer)
+}
+
+/// Convert a string from an input variant to an output variant, rendering it as HTML with each
+/// changed segment annotated with its original form.  See [Engine::convert_ruby] for details.
+#[cfg(feature = "global")]
+pub fn convert_ruby(from: Script, to: Script, input: &str) -> Result<String> {
+    ENGINE.convert_ruby(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, borrowing unchanged segments.
+/// See [Engine::convert_cow] for details.
+#[cfg(feature = "global")]
+pub fn convert_cow(from: Script, to: Script, input: &str) -> Result<Vec<Cow<'_, str>>> {
+    ENGINE.convert_cow(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, recording which dictionary
+/// entries fired.  See [Engine::convert_traced] for details.
+#[cfg(all(feature = "global", feature = "unstable"))]
+pub fn convert_traced(from: Script, to: Script, input: &str) -> Result<(String, Vec<TraceRecord>)> {
+    ENGINE.convert_traced(from, to, input)
+}
+
+/// Convert a string by applying the given dictionaries in order.  See [Engine::convert_with]
+/// for details.
+#[cfg(all(feature = "global", feature = "unstable"))]
+pub fn convert_with(dictionaries: &[&Dictionary], input: &str) -> Vec<String> {
+    ENGINE.convert_with(dictionaries, input)
+}
+
+/// Register a dictionary asset.  See [Engine::register_dictionary] for details.
+#[cfg(all(feature = "global", feature = "external-dicts"))]
+pub fn register_dictionary(asset: &[u8]) -> Result<String> {
+    ENGINE.register_dictionary(asset)
+}
+
+/// Convert a string with a pipeline.  See [Engine::convert_pipeline] for details.
+#[cfg(all(feature = "global", feature = "unstable"))]
+pub fn convert_pipeline(pipeline: &Pipeline, input: &str) -> Vec<String> {
+    ENGINE.convert_pipeline(pipeline, input)
+}
+
+/// Convert a string from an input variant to an output variant within a time budget.
+/// See [Engine::convert_bounded] for details.
+#[cfg(all(feature = "global", not(target_arch = "wasm32")))]
+pub fn convert_bounded(
+    from: Script,
+    to: Script,
+    input: &str,
+    budget: std::time::Duration,
+    resume: Option<Continuation>,
+) -> Result<BoundedConversion<'_>> {
+    ENGINE.convert_bounded(from, to, input, budget, resume)
+}
+
+/// Convert a string from an input variant to an output variant with extra segmentation words.
+/// See [Engine::convert_with_words] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_words(
+    from: Script,
+    to: Script,
+    input: &str,
+    words: &[(&str, u64)],
+) -> Result<Vec<String>> {
+    ENGINE.convert_with_words(from, to, input, words)
+}
+
+/// Convert a string from an input variant to an output variant, remapping byte ranges of the
+/// input onto the output.  See [Engine::convert_with_ranges] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_ranges(
+    from: Script,
+    to: Script,
+    input: &str,
+    ranges: &[std::ops::Range<usize>],
+) -> Result<(String, Vec<std::ops::Range<usize>>)> {
+    ENGINE.convert_with_ranges(from, to, input, ranges)
+}
+
+/// Run the OpenCC test cases in a directory.  See [Engine::run_opencc_cases] for details.
+#[cfg(all(feature = "global", feature = "eval"))]
+pub fn run_opencc_cases(dir: &std::path::Path) -> Result<eval::EvalReport> {
+    ENGINE.run_opencc_cases(dir)
+}
+
+/// Convert a string from an input variant to an output variant, pairing each segment of the
+/// input with its converted form.
+#[cfg(feature = "global")]
+pub fn convert_aligned(from: Script, to: Script, input: &str) -> Result<Vec<(&str, String)>> {
+    ENGINE.convert_aligned(from, to, input)
+}
+
+/// Convert lines from an input variant to an output variant one at a time, copying the
+/// whitespace around each exactly.  See [Engine::convert_lines] for details.
+#[cfg(feature = "global")]
+pub fn convert_lines<'a, I: IntoIterator<Item = &'a str>>(
+    from: Script,
+    to: Script,
+    lines: I,
+) -> ConvertLines<'static, I::IntoIter> {
+    ENGINE.convert_lines(from, to, lines)
+}
+
+/// Convert a string from an input variant to an output variant, also returning a map between
+/// byte offsets in the input and the output.  See [Engine::convert_with_offsets] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_offsets(from: Script, to: Script, input: &str) -> Result<(String, OffsetMap)> {
+    ENGINE.convert_with_offsets(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant a chunk at a time.  See
+/// [Engine::convert_chunks] for details.
+#[cfg(feature = "global")]
+pub fn convert_chunks(
+    from: Script,
+    to: Script,
+    input: &str,
+    max_chunk_bytes: usize,
+) -> ConvertChunks<'static, '_> {
+    ENGINE.convert_chunks(from, to, input, max_chunk_bytes)
+}
+
+/// Split text into the segments it is converted in.  See [Engine::segment] for details.
+#[cfg(feature = "global")]
+pub fn segment(input: &str) -> Vec<&str> {
+    ENGINE.segment(input)
+}
+
+/// Check whether a conversion is reversible and idempotent.  See [Engine::verify] for details.
+#[cfg(feature = "global")]
+pub fn verify(from: Script, to: Script, input: &str) -> Result<VerifyReport> {
+    ENGINE.verify(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant on all cores.
+/// See [Engine::convert_parallel] for details.
+#[cfg(all(feature = "global", feature = "parallel"))]
+pub fn convert_parallel(from: Script, to: Script, input: &str) -> Result<Vec<String>> {
+    ENGINE.convert_parallel(from, to, input)
+}
+
+/// Convert many independent strings on all cores, with a result for each.
+/// See [Engine::convert_many] for details.
+#[cfg(all(feature = "global", feature = "parallel"))]
+pub fn convert_many<S: AsRef<str> + Sync>(
+    from: Script,
+    to: Script,
+    inputs: &[S],
+) -> Vec<Result<String>> {
+    ENGINE.convert_many(from, to, inputs)
+}
+
+/// Convert a string from an input variant to an output variant without segmenting it.
+/// See [Engine::convert_greedy] for details.
+#[cfg(feature = "global")]
+pub fn convert_greedy(from: Script, to: Script, input: &str) -> Result<String> {
+    ENGINE.convert_greedy(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant without segmenting it, appending
+/// to `out`.  See [Engine::convert_greedy_into] for details.
+#[cfg(feature = "global")]
+pub fn convert_greedy_into(from: Script, to: Script, input: &str, out: &mut String) -> Result<()> {
+    ENGINE.convert_greedy_into(from, to, input, out)
+}
+
+/// Convert a string from an input variant to an output variant, copying any segments that fail
+/// to convert.  See [Engine::convert_lenient] for details.
+#[cfg(feature = "global")]
+pub fn convert_lenient(
+    from: Script,
+    to: Script,
+    input: &str,
+) -> (Vec<String>, Vec<ConversionError>) {
+    ENGINE.convert_lenient(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, also returning warnings.
+/// See [Engine::convert_with_warnings] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_warnings(
+    from: Script,
+    to: Script,
+    input: &str,
+) -> Result<(Vec<String>, Vec<Warning>)> {
+    ENGINE.convert_with_warnings(from, to, input)
+}
+
+/// Convert a string from an input variant to an output variant, passing each segment to `sink`
+/// and returning warnings.  See [Engine::convert_with_warnings_into] for details.
+#[cfg(feature = "global")]
+pub fn convert_with_warnings_into<S: ConversionSink + ?Sized>(
+    from: Script,
+    to: Script,
+    input: &str,
+    sink: &mut S,
+) -> Result<Vec<Warning>> {
+    ENGINE.convert_with_warnings_into(from, to, input, sink)
+}
+
+/// Convert already-segmented tokens from an input variant to an output variant.
+/// Unlike [convert], the input is not re-segmented; each token is converted as a unit.
+#[cfg(feature = "global")]
+pub fn convert_tokens(from: Script, to: Script, tokens: &[&str]) -> Result<Vec<String>> {
+    ENGINE.convert_tokens(from, to, tokens)
+}
+
+/// Register a custom script with the global engine.
+/// See [Engine::register_script] for details.
+#[cfg(feature = "global")]
+pub fn register_script(script: CustomScript) -> Result<()> {
+    ENGINE.register_script(script)
+}
+
+/// Convert a string between two scripts given by name.
+/// See [Engine::convert_named] for details.
+#[cfg(feature = "global")]
+pub fn convert_named(from: &str, to: &str, input: &str) -> Result<Vec<String>> {
+    ENGINE.convert_named(from, to, input)
+}
+
+/// Convert a string between two scripts given by name, passing each segment to `sink`.
+/// See [Engine::convert_named_into] for details.
+#[cfg(feature = "global")]
+pub fn convert_named_into<S: ConversionSink + ?Sized>(
+    from: &str,
+    to: &str,
+    input: &str,
+    sink: &mut S,
+) -> Result<()> {
+    ENGINE.convert_named_into(from, to, input, sink)
+}
+
+/// Convert an HTML document between two scripts given by name.
+/// See [Engine::convert_html_named] for details.
+#[cfg(feature = "global")]
+pub fn convert_html_named(from: &str, to: &str, input: &str) -> Result<String> {
+    ENGINE.convert_html_named(from, to, input)
+}
+
+/// Convert the string values of a JSON document between two scripts given by name.
+/// See [Engine::convert_json_named] for details.
+#[cfg(all(feature = "global", feature = "json"))]
+pub fn convert_json_named(from: &str, to: &str, input: &str, allowlist: &[&str]) -> Result<String> {
+    ENGINE.convert_json_named(from, to, input, allowlist)
+}
+
+/// Convert an HTML document from an input variant to an output variant.
+/// See [Engine::convert_html] for details.
+#[cfg(feature = "global")]
+pub fn convert_html(from: Script, to: Script, input: &str) -> Result<String> {
+    ENGINE.convert_html(from, to, input)
+}
+
+/// Convert the string values of a JSON document from an input variant to an output variant.
+/// See [Engine::convert_json] for details.
+#[cfg(all(feature = "global", feature = "json"))]
+pub fn convert_json(from: Script, to: Script, input: &str, allowlist: &[&str]) -> Result<String> {
+    ENGINE.convert_json(from, to, input, allowlist)
+}
+
+/// Convert the string values of a parsed JSON value in place.
+/// See [Engine::convert_json_value] for details.
+#[cfg(all(feature = "global", feature = "json"))]
+pub fn convert_json_value(
+    from: Script,
+    to: Script,
+    value: &mut serde_json::Value,
+    allowlist: &[&str],
+) -> Result<()> {
+    ENGINE.convert_json_value(from, to, value, allowlist)
+}
+
+/// Normalize traditional text to a consistent set of character variants.
+/// See [Engine::normalize_variants] for details.
+#[cfg(feature = "global")]
+pub fn normalize_variants(to: Script, input: &str) -> Result<String> {
+    ENGINE.normalize_variants(to, input)
+}
+
+/// Find segments that are not written in the expected script and suggest replacements.
+/// See [Engine::suggest_corrections] for details.
+#[cfg(feature = "global")]
+pub fn suggest_corrections(expected: Script, input: &str) -> Result<Vec<Suggestion>> {
+    ENGINE.suggest_corrections(expected, input)
+}
+
+#[cfg(all(test, feature = "global"))]
 mod tests {
     use std::{env, fs, path};
 
@@ -149,8 +1240,8 @@ mod tests {
 
     #[test]
     fn test_convert_word() -> Result<()> {
-        let keys = vec![DictionaryKeys::FromChina];
-        let result = convert_word(keys.iter(), "㐷")?;
+        let keys = Chain::one(DictionaryKeys::FromChina);
+        let result = ENGINE.convert_word(&keys, "㐷")?;
         assert_eq!("傌", result);
 
         Ok(())
@@ -158,13 +1249,151 @@ mod tests {
 
     #[test]
     fn test_convert_word_hk_rev() -> Result<()> {
-        let keys = vec![DictionaryKeys::FromHongKong];
-        let result = convert_word(keys.iter(), "吃")?;
+        let keys = Chain::one(DictionaryKeys::FromHongKong);
+        let result = ENGINE.convert_word(&keys, "吃")?;
         assert_eq!("喫", result);
 
         Ok(())
     }
 
+    #[test]
+    fn test_convert_char() -> Result<()> {
+        assert_eq!(Some('們'), convert_char(Script::CN, Script::TW, '们')?);
+        assert_eq!(Some('他'), convert_char(Script::CN, Script::TW, '他')?);
+        assert_eq!(Some('a'), convert_char(Script::CN, Script::TW, 'a')?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_names() -> Result<()> {
+        for (name, script) in SCRIPT_NAMES {
+            assert_eq!(name, script.to_string());
+            assert_eq!(script, name.parse()?);
+            assert_eq!(script, script.language_tag().parse()?);
+        }
+        assert_eq!(Script::TW, "zh-TW".parse()?);
+        assert_eq!(Script::CN, "zh_Hans".parse()?);
+        assert_eq!(Script::ST, "traditional".parse()?);
+        assert!("xx".parse::<Script>().is_err());
+
+        assert_eq!(Some(Script::TW), Script::from_language_tag("zh-Hant-TW"));
+        assert_eq!(Some(Script::HK), Script::from_language_tag("zh-Hant-MO"));
+        assert_eq!(Some(Script::CN), Script::from_language_tag("zh-Hans-HK"));
+        assert_eq!(Some(Script::CN), Script::from_language_tag("zh-SG"));
+        assert_eq!(Some(Script::CN), Script::from_language_tag("zh"));
+        assert_eq!(Some(Script::ST), Script::from_language_tag("zh-Hant"));
+        assert_eq!(
+            Some(Script::TW),
+            Script::from_language_tag("zh-TW-u-ca-roc")
+        );
+        assert_eq!(Some(Script::HK), Script::from_language_tag("yue"));
+        assert_eq!(None, Script::from_language_tag("ja-JP"));
+        assert_eq!(None, Script::from_language_tag("zh-Latn"));
+
+        assert_eq!(SCRIPT_NAMES.len(), Script::all().len());
+        let pairs: Vec<_> = supported_pairs().collect();
+        assert_eq!(Script::all().len() * (Script::all().len() - 1), pairs.len());
+        assert!(pairs.contains(&(Script::HK, Script::TW)));
+        assert!(!pairs.contains(&(Script::CN, Script::CN)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_instance() -> Result<()> {
+        let engine = Engine::new();
+        let result = engine.convert(Script::CN, Script::TW, "他们是勇敢的士兵")?;
+        assert_eq!("他們是勇敢的士兵", result.join(""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_loads_only_needed_dictionaries() -> Result<()> {
+        let engine = Engine::new();
+        engine.convert(Script::CN, Script::TW, "他们是勇敢的士兵")?;
+        let loaded: Vec<_> = engine
+            .dictionaries
+            .iter()
+            .filter(|(_, dict)| dict.get().is_some())
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(
+            vec![DictionaryKeys::FromChina, DictionaryKeys::ToTaiwan],
+            loaded
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_greedy() -> Result<()> {
+        let result = convert_greedy(Script::CN, Script::TW, "我能吞下玻璃而不伤身体。")?;
+        assert_eq!("我能吞下玻璃而不傷身體。", result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_cow() -> Result<()> {
+        let result = convert_cow(Script::CN, Script::TW, "软件 and 他们")?;
+        assert_eq!("軟體 and 他們", result.concat());
+        assert!(result
+            .iter()
+            .filter(|segment| !segment.chars().any(|ch| "软件他们".contains(ch)))
+            .all(|segment| matches!(segment, Cow::Borrowed(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_tokens() -> Result<()> {
+        let result = convert_tokens(Script::CN, Script::TW, &["他们", "是", "士兵"])?;
+        assert_eq!(vec!["他們", "是", "士兵"], result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_aligned() -> Result<()> {
+        let result = convert_aligned(Script::CN, Script::TW, "他们是勇敢的士兵")?;
+        let original: String = result.iter().map(|(original, _)| *original).collect();
+        let converted: String = result
+            .iter()
+            .map(|(_, converted)| converted.as_str())
+            .collect();
+        assert_eq!("他们是勇敢的士兵", original);
+        assert_eq!("他們是勇敢的士兵", converted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_conversion() {
+        let engine = Engine::new();
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..8)
+                .map(|i| {
+                    let engine = &engine;
+                    scope.spawn(move || {
+                        let (from, to, input, expected) = match i % 2 {
+                            0 => (Script::CN, Script::TW, "他们的软件", "他們的軟體"),
+                            _ => (Script::TW, Script::CN, "他們的軟體", "他们的软件"),
+                        };
+                        for _ in 0..50 {
+                            assert_eq!(expected, engine.convert(from, to, input)?.join(""));
+                        }
+                        Ok::<_, anyhow::Error>(())
+                    })
+                })
+                .collect();
+            for thread in threads {
+                thread.join().expect("conversion thread panicked").unwrap();
+            }
+        });
+    }
+
     mod phrase_tests {
         use super::*;
 
@@ -190,6 +1419,10 @@ mod tests {
             "他们是勇敢的士兵",
             "他們是勇敢的士兵"
         );
+        parameterized_test!(hk2tw_variant, Script::HK, Script::TW, "看着他", "看著他");
+        parameterized_test!(tw2hk_variant, Script::TW, Script::HK, "看著他", "看着他");
+        parameterized_test!(hk2tw_phrase, Script::HK, Script::TW, "軟件", "軟體");
+        parameterized_test!(tw2hk_phrase, Script::TW, Script::HK, "軟體", "軟件");
     }
 
     mod opencc_tests {
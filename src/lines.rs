@@ -0,0 +1,84 @@
+use anyhow::Result;
+
+use crate::{Engine, Script};
+
+/// An iterator over converted lines; see [Engine::convert_lines].
+#[derive(Clone)]
+pub struct ConvertLines<'e, I> {
+    engine: &'e Engine,
+    from: Script,
+    to: Script,
+    lines: I,
+}
+
+impl<'e, 'a, I: Iterator<Item = &'a str>> Iterator for ConvertLines<'e, I> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        Some(self.engine.convert_line(self.from, self.to, line))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.lines.size_hint()
+    }
+}
+
+impl Engine {
+    /// Convert lines from an input variant to an output variant one at a time, as the returned
+    /// iterator is advanced.  The whitespace at the start and end of each line, including any
+    /// line ending left on it, is copied exactly, and lines with nothing else are returned as
+    /// they are; [Width] and [Punctuation] only apply between them.
+    ///
+    /// [Width]: crate::Width
+    /// [Punctuation]: crate::Punctuation
+    pub fn convert_lines<'a, I: IntoIterator<Item = &'a str>>(
+        &self,
+        from: Script,
+        to: Script,
+        lines: I,
+    ) -> ConvertLines<'_, I::IntoIter> {
+        ConvertLines {
+            engine: self,
+            from,
+            to,
+            lines: lines.into_iter(),
+        }
+    }
+
+    /// Convert one line, copying the whitespace around it.
+    fn convert_line(&self, from: Script, to: Script, line: &str) -> Result<String> {
+        let start = line.len() - line.trim_start().len();
+        let end = line.trim_end().len().max(start);
+        if start == end {
+            return Ok(line.to_owned());
+        }
+        let mut output = String::with_capacity(line.len());
+        output.push_str(&line[..start]);
+        for segment in self.convert(from, to, &line[start..end])? {
+            output.push_str(&segment);
+        }
+        output.push_str(&line[end..]);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Width;
+
+    #[test]
+    fn test_convert_lines() -> Result<()> {
+        let engine = Engine::new().with_width(Width::Fullwidth);
+        let lines = ["  软件 a\t", "", "\t", "头发\r\n", " "];
+        let converted = engine
+            .convert_lines(Script::CN, Script::TW, lines)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec!["  軟體\u{3000}ａ\t", "", "\t", "頭髮\r\n", " "],
+            converted
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Result};
+use enum_map::Enum;
+
+use crate::{chain, DictionaryKeys, Engine, Script};
+
+/// Find an embedded dictionary by name, whether or not it is included in this build.
+pub(crate) fn dictionary_key(name: &str) -> Result<DictionaryKeys> {
+    (0..DictionaryKeys::LENGTH)
+        .map(DictionaryKeys::from_usize)
+        .find(|key| format!("{:?}", key) == name)
+        .ok_or(anyhow!(format!("unknown dictionary {}", name)))
+}
+
+/// An entry of one of the embedded dictionaries; see [Engine::lookup].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DictionaryEntry {
+    /// The name of the dictionary, such as `FromChina`.
+    pub dictionary: String,
+    /// The phrase the entry replaces.
+    pub phrase: String,
+    /// The replacements, in dictionary order; the first is the one used by default.
+    pub candidates: Vec<String>,
+}
+
+impl Engine {
+    /// Build the entry for a phrase found in a dictionary, with all of its candidates.
+    fn entry(&self, key: DictionaryKeys, phrase: &str, value: &str) -> Result<DictionaryEntry> {
+        let candidates = match self.ambiguities(key)?.get(phrase) {
+            Some(candidates) => candidates.clone(),
+            None => vec![value.to_owned()],
+        };
+        Ok(DictionaryEntry {
+            dictionary: format!("{:?}", key),
+            phrase: phrase.to_owned(),
+            candidates,
+        })
+    }
+
+    /// The names of the embedded dictionaries included in this build, for [Engine::lookup].
+    pub fn dictionary_names(&self) -> Vec<String> {
+        self.dictionaries
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| self.dictionary(*key).is_ok())
+            .map(|key| format!("{:?}", key))
+            .collect()
+    }
+
+    /// The names of the dictionaries that convert between two scripts, in the order they are
+    /// applied.
+    pub fn dictionary_chain(&self, from: Script, to: Script) -> Vec<String> {
+        chain(from, to)
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .collect()
+    }
+
+    /// Look up exactly `phrase` in the named dictionary.
+    pub fn lookup(&self, dictionary: &str, phrase: &str) -> Result<Option<DictionaryEntry>> {
+        let key = dictionary_key(dictionary)?;
+        match self.dictionary(key)?.get(phrase) {
+            Some(value) => Ok(Some(self.entry(key, phrase, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Look up a phrase in each dictionary that converts between two scripts, following it
+    /// through the conversion: each dictionary after the first is given the default replacement
+    /// from the one before.  Dictionaries without an entry for the phrase are left out.
+    pub fn lookup_chain(
+        &self,
+        from: Script,
+        to: Script,
+        phrase: &str,
+    ) -> Result<Vec<DictionaryEntry>> {
+        let mut phrase = phrase.to_owned();
+        let mut entries = Vec::new();
+        for key in &chain(from, to) {
+            if let Some(value) = self.dictionary(*key)?.get(&phrase) {
+                let entry = self.entry(*key, &phrase, value)?;
+                phrase = value.to_owned();
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Every entry of the named dictionary whose phrase starts with `prefix`, in order.
+    pub fn entries_with_prefix(
+        &self,
+        dictionary: &str,
+        prefix: &str,
+    ) -> Result<Vec<DictionaryEntry>> {
+        let key = dictionary_key(dictionary)?;
+        self.dictionary(key)?
+            .with_prefix(prefix)
+            .into_iter()
+            .map(|(phrase, value)| self.entry(key, &phrase, value))
+            .collect()
+    }
+
+    /// The number of entries in the named dictionary.
+    pub fn entry_count(&self, dictionary: &str) -> Result<usize> {
+        Ok(self.dictionary(dictionary_key(dictionary)?)?.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() -> Result<()> {
+        let engine = Engine::new();
+        assert!(engine.dictionary_names().contains(&"FromChina".to_owned()));
+        assert_eq!(
+            vec!["FromChina", "ToTaiwan"],
+            engine.dictionary_chain(Script::CN, Script::TW)
+        );
+
+        let entry = engine
+            .lookup("FromChina", "干")?
+            .expect("干 is in FromChina");
+        assert_eq!("幹", entry.candidates[0]);
+        assert!(entry.candidates.len() > 1);
+        assert_eq!(None, engine.lookup("FromChina", "abc")?);
+        assert!(engine.lookup("NoSuchDictionary", "干").is_err());
+
+        let entries = engine.lookup_chain(Script::CN, Script::TW, "们")?;
+        assert_eq!(1, entries.len());
+        assert_eq!("FromChina", entries[0].dictionary);
+        assert_eq!("們", entries[0].candidates[0]);
+
+        let entries = engine.entries_with_prefix("ToTaiwan", "軟")?;
+        assert!(entries.iter().all(|entry| entry.phrase.starts_with('軟')));
+        assert!(entries.iter().any(|entry| entry.phrase == "軟件"));
+        assert!(engine.entry_count("FromChina")? > 0);
+
+        Ok(())
+    }
+}
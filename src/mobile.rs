@@ -0,0 +1,87 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{convert_named, detect_script, ENGINE, SCRIPT_NAMES};
+
+/// An error reported to Swift and Kotlin as a thrown exception, with the message of the
+/// underlying error.
+#[derive(Debug, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum ZtarccError {
+    /// An unknown script, or a conversion that failed.
+    Failed(String),
+}
+
+impl fmt::Display for ZtarccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZtarccError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ZtarccError {
+    fn from(error: anyhow::Error) -> Self {
+        ZtarccError::Failed(format!("{:#}", error))
+    }
+}
+
+/// Convert a string between two scripts, which may be built-in or custom.
+#[uniffi::export]
+pub fn convert(from: String, to: String, text: String) -> Result<String, ZtarccError> {
+    Ok(convert_named(&from, &to, &text)?.join(""))
+}
+
+/// Guess which built-in script some text is written in, returning its short name, or `None` if
+/// nothing in the text tells the scripts apart.
+#[uniffi::export]
+pub fn detect(text: String) -> Result<Option<String>, ZtarccError> {
+    Ok(detect_script(&text)?
+        .script
+        .map(|script| script.name().to_owned()))
+}
+
+/// List the names of the built-in scripts.
+#[uniffi::export]
+pub fn scripts() -> Vec<String> {
+    SCRIPT_NAMES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// A converter between two scripts, for converting many strings such as the paragraphs of a
+/// book.  It can be shared between threads.
+#[derive(uniffi::Object)]
+pub struct Converter {
+    from: String,
+    to: String,
+}
+
+#[uniffi::export]
+impl Converter {
+    /// Create a converter; throws if either script is unknown.
+    #[uniffi::constructor]
+    pub fn new(from: String, to: String) -> Result<Arc<Self>, ZtarccError> {
+        ENGINE.resolve_script(&from)?;
+        ENGINE.resolve_script(&to)?;
+        Ok(Arc::new(Converter { from, to }))
+    }
+
+    /// The name of the input script.
+    #[uniffi::method(name = "from_script")]
+    pub fn input_script(&self) -> String {
+        self.from.clone()
+    }
+
+    /// The name of the output script.
+    #[uniffi::method(name = "to_script")]
+    pub fn output_script(&self) -> String {
+        self.to.clone()
+    }
+
+    /// Convert a string.
+    pub fn convert(&self, text: String) -> Result<String, ZtarccError> {
+        Ok(convert_named(&self.from, &self.to, &text)?.join(""))
+    }
+}
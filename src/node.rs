@@ -0,0 +1,57 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use napi_derive::napi;
+
+use crate::{convert_named, SCRIPT_NAMES};
+
+/// Report an error to JavaScript.
+fn to_napi_err(error: anyhow::Error) -> napi::Error {
+    napi::Error::from_reason(format!("{:#}", error))
+}
+
+/// Convert a string between two scripts, which may be built-in or custom, blocking the calling
+/// thread.
+#[napi(js_name = "convert")]
+pub fn convert_export(from: String, to: String, input: String) -> napi::Result<String> {
+    Ok(convert_named(&from, &to, &input)
+        .map_err(to_napi_err)?
+        .join(""))
+}
+
+/// A conversion run on the libuv thread pool.
+pub struct ConvertTask {
+    from: String,
+    to: String,
+    input: String,
+}
+
+impl Task for ConvertTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<String> {
+        Ok(convert_named(&self.from, &self.to, &self.input)
+            .map_err(to_napi_err)?
+            .join(""))
+    }
+
+    fn resolve(&mut self, _env: Env, output: String) -> napi::Result<String> {
+        Ok(output)
+    }
+}
+
+/// Convert a string on the libuv thread pool, returning a promise of the converted string, so
+/// that the event loop is not blocked.
+#[napi(js_name = "convertAsync")]
+pub fn convert_async_export(from: String, to: String, input: String) -> AsyncTask<ConvertTask> {
+    AsyncTask::new(ConvertTask { from, to, input })
+}
+
+/// List the names of the built-in scripts.
+#[napi(js_name = "scripts")]
+pub fn scripts_export() -> Vec<String> {
+    SCRIPT_NAMES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
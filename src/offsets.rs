@@ -0,0 +1,117 @@
+use std::ops::Range;
+
+use anyhow::Result;
+
+use crate::{Engine, Script, SpanSink};
+
+/// Maps byte offsets between the input and output of a conversion, whose lengths differ where
+/// phrases are localized, such as 优化 to 最佳化; see [Engine::convert_with_offsets].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    spans: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    /// Every segment of the input, in order, as its byte range in the input and the byte range
+    /// of its conversion in the output.  The ranges cover both texts without gaps.
+    pub fn spans(&self) -> &[(Range<usize>, Range<usize>)] {
+        &self.spans
+    }
+
+    /// The offset in the output corresponding to an offset in the input.  Offsets inside a
+    /// segment whose length changed map to the start of its conversion, since its characters do
+    /// not correspond one to one; offsets past the end map to the end.
+    pub fn to_output(&self, offset: usize) -> usize {
+        map_offset(&self.spans, offset, |(input, output)| (input, output))
+    }
+
+    /// The offset in the input corresponding to an offset in the output, like
+    /// [OffsetMap::to_output] the other way around.
+    pub fn to_input(&self, offset: usize) -> usize {
+        map_offset(&self.spans, offset, |(input, output)| (output, input))
+    }
+
+    /// The byte range of the output that an input range was converted to, widened to whole
+    /// segments where it starts or ends inside one whose length changed, for moving highlights
+    /// and cues.
+    pub fn output_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.to_output(range.start);
+        let end = match self
+            .spans
+            .iter()
+            .find(|(input, _)| input.start < range.end && range.end < input.end)
+        {
+            Some((input, output)) if input.len() != output.len() => output.end,
+            _ => self.to_output(range.end),
+        };
+        start..end.max(start)
+    }
+}
+
+/// Map an offset through spans, with `sides` picking which range of each span it is from and
+/// which it is to.
+fn map_offset(
+    spans: &[(Range<usize>, Range<usize>)],
+    offset: usize,
+    sides: impl Fn(&(Range<usize>, Range<usize>)) -> (&Range<usize>, &Range<usize>),
+) -> usize {
+    let index = spans.partition_point(|span| sides(span).0.end <= offset);
+    let Some(span) = spans.get(index) else {
+        return spans.last().map_or(0, |span| sides(span).1.end);
+    };
+    let (from, to) = sides(span);
+    match from.len() == to.len() {
+        true => to.start + offset.saturating_sub(from.start),
+        false => to.start,
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, also returning a map
+    /// between byte offsets in the input and the output, so that editors and subtitle tools can
+    /// move cursors, highlights, and timing cues over to the converted text.
+    pub fn convert_with_offsets(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> Result<(String, OffsetMap)> {
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        let mut output = String::with_capacity(input.len());
+        let mut spans = Vec::with_capacity(sink.spans.len());
+        for span in sink.spans {
+            let start = output.len();
+            output.push_str(&span.converted);
+            spans.push((span.start..span.end, start..output.len()));
+        }
+        Ok((output, OffsetMap { spans }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets() -> Result<()> {
+        let engine = Engine::new();
+        let input = "优化了软件";
+        let (output, map) = engine.convert_with_offsets(Script::CN, Script::TW, input)?;
+        assert_eq!("最佳化了軟體", output);
+
+        // 了 is at 6..9 in the input and 9..12 in the output.
+        assert_eq!(9, map.to_output(6));
+        assert_eq!(6, map.to_input(9));
+        // Inside 优化, which became 最佳化.
+        assert_eq!(0, map.to_output(3));
+        assert_eq!(output.len(), map.to_output(input.len()));
+        assert_eq!(input.len(), map.to_input(output.len()));
+        assert_eq!(9..12, map.output_range(6..9));
+        assert_eq!(0..12, map.output_range(3..9));
+        assert_eq!(9..15, map.output_range(6..12));
+        assert_eq!(0..9, map.output_range(0..3));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{CutOptions, Engine, Normalization, Passthrough, Punctuation, Width};
+
+/// Every option that changes how an engine converts, in one place, for building an engine with
+/// [Engine::from_options] or configuring the free functions with [configure](crate::configure).
+/// The same options are accepted by the command line tool and the JavaScript bindings.  By
+/// default, every option is off, as with [Engine::new].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ConvertOptions {
+    /// How the bundled Jieba segmenter cuts text.
+    pub cut: CutOptions,
+    /// Which kinds of segments are copied without looking them up.
+    pub passthrough: Passthrough,
+    /// How punctuation is rewritten for the target script.
+    pub punctuation: Punctuation,
+    /// How the input is normalized before conversion.
+    pub normalization: Normalization,
+    /// Which width Latin letters, digits, and spaces are written in.
+    pub width: Width,
+    /// Whether inputs that look like Japanese or Korean are copied unconverted.
+    pub language_guard: bool,
+    /// Words copied to the output unconverted.
+    pub protected_words: Vec<String>,
+}
+
+impl Engine {
+    /// Create a new engine from the embedded data with the given options.
+    pub fn from_options(options: &ConvertOptions) -> Self {
+        Self::with_cut_options(options.cut).with_options(options)
+    }
+
+    /// Set every option but [ConvertOptions::cut], which is fixed when the engine is created.
+    /// Protected words are only replaced if `options` lists some.
+    pub fn with_options(self, options: &ConvertOptions) -> Self {
+        let engine = self
+            .with_passthrough(options.passthrough)
+            .with_punctuation(options.punctuation)
+            .with_normalization(options.normalization)
+            .with_width(options.width)
+            .with_language_guard(options.language_guard);
+        if options.protected_words.is_empty() {
+            return engine;
+        }
+        engine.with_protected_words(options.protected_words.iter().map(String::as_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    #[test]
+    fn test_from_options() -> Result<()> {
+        let options = ConvertOptions {
+            width: Width::Halfwidth,
+            protected_words: vec!["软件园".to_owned()],
+            ..ConvertOptions::default()
+        };
+        let engine = Engine::from_options(&options);
+        assert_eq!(
+            "他們的软件园和軟體ABC123",
+            engine
+                .convert(Script::CN, Script::TW, "他们的软件园和软件ＡＢＣ１２３")?
+                .join("")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use rayon::prelude::*;
+
+use crate::{Engine, Script};
+
+/// The approximate size of the chunks converted in parallel, in bytes.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Split the input into chunks of about [CHUNK_SIZE] bytes, each ending at a line break or at the
+/// end of the input.  Segments and dictionary phrases never span a line break, so the chunks can
+/// be converted independently.
+fn chunks(input: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = input;
+    while rest.len() > CHUNK_SIZE {
+        let end = match rest[CHUNK_SIZE..].find('\n') {
+            Some(i) => CHUNK_SIZE + i + 1,
+            None => rest.len(),
+        };
+        let (chunk, remainder) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remainder;
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant like [Engine::convert], but
+    /// split large inputs at line breaks and convert the pieces on all cores.
+    pub fn convert_parallel(&self, from: Script, to: Script, input: &str) -> Result<Vec<String>> {
+        let converted = chunks(input)
+            .par_iter()
+            .map(|chunk| self.convert(from, to, chunk))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(converted.into_iter().flatten().collect())
+    }
+
+    /// Convert many independent strings, such as product titles or comments, on all cores.  The
+    /// results are in the same order as the inputs, each with its own error, so that one bad
+    /// input does not lose the rest.
+    pub fn convert_many<S: AsRef<str> + Sync>(
+        &self,
+        from: Script,
+        to: Script,
+        inputs: &[S],
+    ) -> Vec<Result<String>> {
+        inputs
+            .par_iter()
+            .map(|input| Ok(self.convert(from, to, input.as_ref())?.concat()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        let line = "他们是勇敢的士兵\n";
+        let input = line.repeat(CHUNK_SIZE / line.len() * 3);
+        let chunks = chunks(&input);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.ends_with('\n')));
+        assert_eq!(input, chunks.concat());
+    }
+
+    #[test]
+    fn test_convert_parallel() -> Result<()> {
+        let engine = Engine::new();
+        let input = "他们是勇敢的士兵\n".repeat(4000);
+        let result = engine.convert_parallel(Script::CN, Script::TW, &input)?;
+        assert_eq!("他們是勇敢的士兵\n".repeat(4000), result.join(""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_many() -> Result<()> {
+        let engine = Engine::new();
+        let results = engine.convert_many(Script::CN, Script::TW, &["他们的软件", "", "头发"]);
+        let results = results.into_iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(vec!["他們的軟體", "", "頭髮"], results);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{Engine, Width};
+
+/// Which kinds of segments are copied to the output without looking them up in the dictionaries.
+/// Passing them through is faster, but means that dictionary entries for them are never used.
+/// By default, every segment is looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Passthrough {
+    /// Segments made up only of ASCII letters and digits.
+    pub ascii_words: bool,
+    /// Segments made up only of ASCII digits.
+    pub digits: bool,
+    /// Segments made up only of whitespace.
+    pub whitespace: bool,
+    /// Runs of ASCII text, such as the English in logs and source code, which are copied whole
+    /// without segmenting them.  A run only starts and ends at whitespace or the ends of the
+    /// input, so ASCII touching other text is still converted with it.
+    pub ascii_runs: bool,
+}
+
+impl Passthrough {
+    /// Pass all ASCII words, digits, and whitespace through.
+    pub fn all() -> Self {
+        Passthrough {
+            ascii_words: true,
+            digits: true,
+            whitespace: true,
+            ascii_runs: true,
+        }
+    }
+
+    /// Whether a segment should be copied without looking it up.
+    pub(crate) fn passes(&self, segment: &str) -> bool {
+        if segment.is_empty() {
+            return false;
+        }
+        (self.whitespace && segment.chars().all(char::is_whitespace))
+            || (self.digits && segment.bytes().all(|b| b.is_ascii_digit()))
+            || (self.ascii_words
+                && segment.bytes().all(|b| b.is_ascii_alphanumeric())
+                && !segment.bytes().all(|b| b.is_ascii_digit()))
+    }
+}
+
+/// Split text into the runs of ASCII text that start and end at whitespace or the ends of the
+/// text, marked `true`, and the text between them, marked `false`.
+fn split_ascii_runs(text: &str) -> Vec<(&str, bool)> {
+    let bytes = text.as_bytes();
+    let mut parts = Vec::new();
+    let mut copied_to = 0;
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if !bytes[offset].is_ascii() {
+            offset += 1;
+            continue;
+        }
+        let run_end = bytes[offset..]
+            .iter()
+            .position(|byte| !byte.is_ascii())
+            .map_or(bytes.len(), |len| offset + len);
+        let run = &bytes[offset..run_end];
+        let start = match offset {
+            0 => Some(0),
+            _ => run
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .map(|i| offset + i),
+        };
+        let end = match run_end == bytes.len() {
+            true => Some(run_end),
+            false => run
+                .iter()
+                .rposition(u8::is_ascii_whitespace)
+                .map(|i| offset + i + 1),
+        };
+        if let (Some(start), Some(end)) = (start, end) {
+            if start < end {
+                if copied_to < start {
+                    parts.push((&text[copied_to..start], false));
+                }
+                parts.push((&text[start..end], true));
+                copied_to = end;
+            }
+        }
+        offset = run_end;
+    }
+    if copied_to < text.len() || parts.is_empty() {
+        parts.push((&text[copied_to..], false));
+    }
+    parts
+}
+
+impl Engine {
+    /// Split a run of the input into the ASCII runs to pass through whole, marked `true`, and the
+    /// text to segment, marked `false`.  Fullwidth output needs every ASCII letter converted, so
+    /// turns this off.
+    pub(crate) fn ascii_runs<'a>(&self, text: &'a str) -> Vec<(&'a str, bool)> {
+        match self.passthrough.ascii_runs && self.width != Width::Fullwidth {
+            true => split_ascii_runs(text),
+            false => vec![(text, false)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passthrough() {
+        let policy = Passthrough {
+            ascii_words: true,
+            digits: false,
+            whitespace: true,
+            ascii_runs: false,
+        };
+        assert!(policy.passes("iPhone15"));
+        assert!(policy.passes(" \t"));
+        assert!(!policy.passes("2024"));
+        assert!(!policy.passes("软件"));
+        assert!(!Passthrough::default().passes("iPhone15"));
+    }
+
+    #[test]
+    fn test_split_ascii_runs() {
+        assert_eq!(
+            vec![
+                ("error: failed ", true),
+                ("插入U盘", false),
+                (" to load ", true),
+                ("软件", false)
+            ],
+            split_ascii_runs("error: failed 插入U盘 to load 软件")
+        );
+        assert_eq!(vec![("软件,ok", false)], split_ascii_runs("软件,ok"));
+        assert_eq!(vec![("", false)], split_ascii_runs(""));
+    }
+
+    #[test]
+    fn test_ascii_runs_passthrough() -> anyhow::Result<()> {
+        let engine = crate::Engine::new().with_passthrough(Passthrough::all());
+        assert_eq!(
+            "// 軟體 version 2, see 頭髮.txt",
+            engine
+                .convert(
+                    crate::Script::CN,
+                    crate::Script::TW,
+                    "// 软件 version 2, see 头发.txt"
+                )?
+                .join("")
+        );
+
+        Ok(())
+    }
+}
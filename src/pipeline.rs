@@ -0,0 +1,83 @@
+#[cfg(not(feature = "external-dicts"))]
+use anyhow::{anyhow, Result};
+
+#[cfg(not(feature = "external-dicts"))]
+use crate::{load_dictionary, lookup::dictionary_key};
+use crate::{Dictionary, Engine};
+
+/// A conversion pipeline: a chain of dictionaries applied to each segment in turn.  Pipelines
+/// can be built step by step from embedded and custom dictionaries, or, with the
+/// `opencc-config` feature, read from an OpenCC configuration.
+#[derive(Default)]
+pub struct Pipeline {
+    pub(crate) name: Option<String>,
+    pub(crate) dictionaries: Vec<Dictionary>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline, which leaves text unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a dictionary after the ones already in the pipeline.
+    pub fn then(mut self, dictionary: Dictionary) -> Self {
+        self.dictionaries.push(dictionary);
+        self
+    }
+
+    /// Apply one of the embedded dictionaries, such as `FromChina` or `ToTaiwan`, after the ones
+    /// already in the pipeline.  See [Engine::dictionary_names] for the names.  Fails if there
+    /// is no such dictionary, or its region feature was disabled at build time.  Not available
+    /// with the `external-dicts` feature, which embeds no dictionaries.
+    #[cfg(not(feature = "external-dicts"))]
+    pub fn then_embedded(mut self, name: &str) -> Result<Self> {
+        let dictionary = load_dictionary(dictionary_key(name)?).ok_or(anyhow!(format!(
+            "dictionary {} is not included in this build",
+            name
+        )))?;
+        self.dictionaries.push(dictionary);
+        Ok(self)
+    }
+
+    /// Give the pipeline a name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The name of the pipeline, such as the one given in an OpenCC configuration, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl Engine {
+    /// Convert a string with a pipeline, applying its dictionaries to each segment in order.
+    /// See [Engine::convert_with] for details.
+    pub fn convert_pipeline(&self, pipeline: &Pipeline, input: &str) -> Vec<String> {
+        let dictionaries: Vec<&Dictionary> = pipeline.dictionaries.iter().collect();
+        self.convert_with(&dictionaries, input)
+    }
+}
+
+#[cfg(all(test, feature = "global", not(feature = "external-dicts")))]
+mod tests {
+    use super::*;
+    use crate::convert_pipeline;
+
+    #[test]
+    fn test_pipeline() -> Result<()> {
+        let pipeline = Pipeline::new()
+            .then_embedded("FromChina")?
+            .then(Dictionary::from_pairs([("軟件", "軟體")]))
+            .with_name("house style");
+        assert_eq!(Some("house style"), pipeline.name());
+        assert_eq!(
+            "他們的軟體",
+            convert_pipeline(&pipeline, "他们的软件").join("")
+        );
+        assert!(Pipeline::new().then_embedded("FromMars").is_err());
+        Ok(())
+    }
+}
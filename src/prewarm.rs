@@ -0,0 +1,62 @@
+use anyhow::Result;
+
+use crate::{chain, Engine, Script};
+
+/// Text segmented by [Engine::prewarm], so that the segmenter sets up whatever it builds on its
+/// first use.
+const SAMPLE: &str = "他们的软件 ABC 123";
+
+impl Engine {
+    /// Load everything conversions need ahead of time, so that a latency-sensitive service pays
+    /// for it at startup rather than on its first request: the dictionaries for converting
+    /// between `pair`, or every dictionary included in the build if `None`, their ambiguous
+    /// entries if a ranker is set, and whatever the segmenter builds on first use.  Once this
+    /// returns, the engine is ready; see [Engine::is_loaded] for health checks.
+    pub fn prewarm(&self, pair: Option<(Script, Script)>) -> Result<()> {
+        match pair {
+            Some((from, to)) => {
+                for key in &chain(from, to) {
+                    self.dictionary(*key)?;
+                    if self.ranker.is_some() {
+                        self.ambiguities(*key)?;
+                    }
+                }
+            }
+            None => {
+                for (key, _) in &self.dictionaries {
+                    if self.dictionary(key).is_ok() && self.ranker.is_some() {
+                        self.ambiguities(key)?;
+                    }
+                }
+            }
+        }
+        self.segments(SAMPLE);
+        Ok(())
+    }
+
+    /// Whether the dictionaries for converting between two scripts are loaded, so that
+    /// converting between them will not wait on loading.
+    pub fn is_loaded(&self, from: Script, to: Script) -> bool {
+        chain(from, to)
+            .iter()
+            .all(|key| matches!(self.dictionaries[*key].get(), Some(Some(_))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prewarm() -> Result<()> {
+        let engine = Engine::new();
+        assert!(!engine.is_loaded(Script::CN, Script::TW));
+        engine.prewarm(Some((Script::CN, Script::TW)))?;
+        assert!(engine.is_loaded(Script::CN, Script::TW));
+        assert!(!engine.is_loaded(Script::HK, Script::CN));
+        engine.prewarm(None)?;
+        assert!(engine.is_loaded(Script::HK, Script::CN));
+
+        Ok(())
+    }
+}
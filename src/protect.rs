@@ -0,0 +1,291 @@
+use std::ops::Range;
+
+use crate::words::ExtraWords;
+use crate::{Dictionary, Engine};
+
+/// The Jieba tags for person names (`nr`) and transliterated foreign names (`nrfg` and `nrt`),
+/// for [Engine::with_protected_tags].
+pub const PERSON_NAME_TAGS: &[&str] = &["nr", "nrfg", "nrt"];
+
+/// A rule marking regions of the input that are copied to the output unconverted.
+pub(crate) enum SkipRule {
+    /// From an opening delimiter through the next closing delimiter, or the end of the input if
+    /// there is none.
+    Between(String, String),
+    /// Every match of a regular expression.
+    #[cfg(feature = "regex")]
+    Matching(regex::Regex),
+}
+
+impl SkipRule {
+    /// The regions of the input this rule skips, in order.
+    fn regions(&self, input: &str) -> Vec<Range<usize>> {
+        match self {
+            SkipRule::Between(open, close) => {
+                let mut regions = Vec::new();
+                let mut offset = 0;
+                while let Some(start) = input[offset..].find(open.as_str()) {
+                    let start = offset + start;
+                    let content = start + open.len();
+                    let end = input[content..]
+                        .find(close.as_str())
+                        .map_or(input.len(), |end| content + end + close.len());
+                    regions.push(start..end);
+                    offset = end;
+                }
+                regions
+            }
+            #[cfg(feature = "regex")]
+            SkipRule::Matching(regex) => regex
+                .find_iter(input)
+                .map(|found| found.range())
+                .filter(|range| !range.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Engine {
+    /// Set words that are never converted, such as brand names or usernames.  Each occurrence is
+    /// copied to the output unchanged as a segment of its own, even if the dictionaries have an
+    /// entry for it or the segmenter would split it; where protected words overlap, the longest
+    /// one starting first wins.
+    pub fn with_protected_words<'a>(mut self, words: impl IntoIterator<Item = &'a str>) -> Self {
+        let dict = Dictionary::from_pairs(words.into_iter().map(|word| (word, word)));
+        self.protected = Some(dict);
+        self
+    }
+
+    /// Copy the words the segmenter tags with any of `tags` to the output unconverted, such as
+    /// [PERSON_NAME_TAGS], since converting names through phrase dictionaries occasionally picks
+    /// the wrong variant.  Only segmenters that tag words, like the bundled Jieba segmenter,
+    /// protect anything, and only when segmenting; see [Segmenter::tag].
+    ///
+    /// [Segmenter::tag]: crate::Segmenter::tag
+    pub fn with_protected_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.protected_tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Copy everything from `open` through the next `close` to the output unconverted, such as
+    /// the `{{ ... }}` placeholders of a template.  An unclosed region extends to the end of the
+    /// input.  May be repeated for several kinds of delimiters.
+    pub fn with_skipped_between(mut self, open: &str, close: &str) -> Self {
+        if !open.is_empty() {
+            self.skip_rules
+                .push(SkipRule::Between(open.to_owned(), close.to_owned()));
+        }
+        self
+    }
+
+    /// Copy every match of `regex` to the output unconverted.  May be repeated.
+    #[cfg(feature = "regex")]
+    pub fn with_skipped_matches(mut self, regex: regex::Regex) -> Self {
+        self.skip_rules.push(SkipRule::Matching(regex));
+        self
+    }
+
+    /// Whether any part of an input may need to be copied unconverted.
+    pub(crate) fn protects(&self) -> bool {
+        self.protected.is_some() || !self.skip_rules.is_empty()
+    }
+
+    /// The regions skipped by all the skip rules, in order, with overlapping regions merged.
+    fn skipped_regions(&self, input: &str) -> Vec<Range<usize>> {
+        let mut regions: Vec<_> = self
+            .skip_rules
+            .iter()
+            .flat_map(|rule| rule.regions(input))
+            .collect();
+        regions.sort_by_key(|region| region.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(regions.len());
+        for region in regions {
+            match merged.last_mut() {
+                Some(last) if region.start < last.end => last.end = last.end.max(region.end),
+                _ => merged.push(region),
+            }
+        }
+        merged
+    }
+
+    /// Split text with no skipped regions into runs, marking the runs that are protected words.
+    fn push_word_runs<'a>(&self, text: &'a str, runs: &mut Vec<(&'a str, bool)>) {
+        let Some(protected) = &self.protected else {
+            if !text.is_empty() {
+                runs.push((text, false));
+            }
+            return;
+        };
+        let mut run_start = 0;
+        for piece in protected.pieces(text) {
+            if piece.replacement.is_some() {
+                if run_start < piece.offset {
+                    runs.push((&text[run_start..piece.offset], false));
+                }
+                runs.push((piece.text, true));
+                run_start = piece.end();
+            }
+        }
+        if run_start < text.len() {
+            runs.push((&text[run_start..], false));
+        }
+    }
+
+    /// Split the input into runs, marking the runs that are protected words or skipped regions.
+    pub(crate) fn protected_runs<'a>(&self, input: &'a str) -> Vec<(&'a str, bool)> {
+        let mut runs = Vec::new();
+        let mut offset = 0;
+        for region in self.skipped_regions(input) {
+            self.push_word_runs(&input[offset..region.start], &mut runs);
+            runs.push((&input[region.clone()], true));
+            offset = region.end;
+        }
+        self.push_word_runs(&input[offset..], &mut runs);
+        runs
+    }
+
+    /// Segment the input, marking the segments that are copied to the output without looking
+    /// them up: protected words and skipped regions, each kept as a segment of its own, and
+    /// segments let through by the passthrough policy.
+    pub(crate) fn segments<'a>(&self, input: &'a str) -> Vec<(&'a str, bool)> {
+        self.segments_with(input, &ExtraWords::new())
+    }
+
+    /// Segment the input like [Engine::segments], also keeping each of the extra words as a
+    /// segment of its own.
+    pub(crate) fn segments_with<'a>(
+        &self,
+        input: &'a str,
+        words: &ExtraWords,
+    ) -> Vec<(&'a str, bool)> {
+        self.segments_by(input, |run| self.cut_with(run, words))
+    }
+
+    /// Segment the input like [Engine::segments], cutting the runs between protected words and
+    /// skipped regions with `cut`.
+    pub(crate) fn segments_by<'a>(
+        &self,
+        input: &'a str,
+        cut: impl Fn(&'a str) -> Vec<&'a str>,
+    ) -> Vec<(&'a str, bool)> {
+        let mark = |segment: &'a str| (segment, self.passthrough.passes(segment));
+        let cut_run = |run: &'a str| {
+            let mut segments = Vec::new();
+            for (part, ascii) in self.ascii_runs(run) {
+                if ascii {
+                    segments.push((part, true));
+                    continue;
+                }
+                let start = segments.len();
+                segments.extend(cut(part).into_iter().map(mark));
+                self.protect_tagged(part, &mut segments[start..]);
+            }
+            segments
+        };
+        if !self.protects() {
+            return cut_run(input);
+        }
+        let mut segments = Vec::new();
+        for (run, protected) in self.protected_runs(input) {
+            match protected {
+                true => segments.push((run, true)),
+                false => segments.extend(cut_run(run)),
+            }
+        }
+        segments
+    }
+
+    /// Mark the segments of a run that lie within a word with a protected tag as copied.
+    fn protect_tagged(&self, run: &str, segments: &mut [(&str, bool)]) {
+        if self.protected_tags.is_empty() {
+            return;
+        }
+        let Some(words) = self.segmenter.tag(run) else {
+            return;
+        };
+        let base = run.as_ptr() as usize;
+        let start = |text: &str| (text.as_ptr() as usize).wrapping_sub(base);
+        let names: Vec<Range<usize>> = words
+            .into_iter()
+            .filter(|(_, tag)| self.protected_tags.contains(*tag))
+            .map(|(word, _)| start(word)..start(word) + word.len())
+            .collect();
+        for (segment, copied) in segments {
+            let range = start(segment)..start(segment) + segment.len();
+            if names
+                .iter()
+                .any(|name| name.start <= range.start && range.end <= name.end)
+            {
+                *copied = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    #[test]
+    fn test_protected_words() -> Result<()> {
+        let engine = Engine::new().with_protected_words(["软件园", "发"]);
+        assert_eq!(
+            "他們的软件园和軟體发",
+            engine
+                .convert(Script::CN, Script::TW, "他们的软件园和软件发")?
+                .join("")
+        );
+        assert_eq!(
+            "软件园的軟體",
+            engine.convert_greedy(Script::CN, Script::TW, "软件园的软件")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skipped_between() -> Result<()> {
+        let engine = Engine::new().with_skipped_between("{{", "}}");
+        assert_eq!(
+            "軟體{{ 软件 }}軟體{{软件",
+            engine
+                .convert(Script::CN, Script::TW, "软件{{ 软件 }}软件{{软件")?
+                .join("")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_protected_tags() -> Result<()> {
+        let engine = Engine::new().with_protected_tags(PERSON_NAME_TAGS.iter().copied());
+        assert_eq!(
+            "范冰冰的頭髮",
+            engine
+                .convert(Script::CN, Script::TW, "范冰冰的头发")?
+                .join("")
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_skipped_matches() -> Result<()> {
+        let engine = Engine::new().with_skipped_matches(regex::Regex::new(r"\$[^$]*\$")?);
+        assert_eq!(
+            "軟體$软件$",
+            engine
+                .convert(Script::CN, Script::TW, "软件$软件$")?
+                .join("")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::borrow::Cow;
+
+use crate::language::is_han;
+use crate::{Engine, Script};
+
+/// How punctuation is rewritten to follow the typographic conventions of the target script.
+/// By default, punctuation is left as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Punctuation {
+    /// Replace halfwidth commas, full stops, colons, semicolons, question and exclamation marks,
+    /// and parentheses that follow Chinese text with their fullwidth forms.
+    pub fullwidth: bool,
+    /// Replace ellipses that follow Chinese text, written as `...`, `。。。`, or a single `…`,
+    /// with `……`.
+    pub ellipsis: bool,
+    /// Use the target script's quotation marks: `“”` and `‘’` for Simplified Chinese, `「」` and
+    /// `『』` for Taiwan and Hong Kong.
+    pub quotes: bool,
+}
+
+/// Whether a character is part of an ellipsis.
+fn is_ellipsis_char(ch: char) -> bool {
+    matches!(ch, '.' | '。' | '…')
+}
+
+/// The fullwidth form of a halfwidth punctuation mark, if it has one.
+fn fullwidth(ch: char) -> Option<char> {
+    Some(match ch {
+        ',' => '，',
+        '.' => '。',
+        ':' => '：',
+        ';' => '；',
+        '!' => '！',
+        '?' => '？',
+        '(' => '（',
+        ')' => '）',
+        _ => return None,
+    })
+}
+
+/// Whether halfwidth punctuation after this character should be made fullwidth.
+fn is_chinese_context(ch: char) -> bool {
+    is_han(ch) || matches!(ch, '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF5E}')
+}
+
+/// The quotation mark the target script uses in place of this one, if different.
+fn quote(to: Script, ch: char) -> Option<char> {
+    match (to, ch) {
+        (Script::TW | Script::HK, '“') => Some('「'),
+        (Script::TW | Script::HK, '”') => Some('」'),
+        (Script::TW | Script::HK, '‘') => Some('『'),
+        (Script::TW | Script::HK, '’') => Some('』'),
+        (Script::CN, '「') => Some('“'),
+        (Script::CN, '」') => Some('”'),
+        (Script::CN, '『') => Some('‘'),
+        (Script::CN, '』') => Some('’'),
+        _ => None,
+    }
+}
+
+impl Punctuation {
+    /// Apply every rule.
+    pub fn all() -> Self {
+        Punctuation {
+            fullwidth: true,
+            ellipsis: true,
+            quotes: true,
+        }
+    }
+
+    /// Merge runs of segments that together make up an ellipsis, since segmenters tend to split
+    /// them into single characters.
+    pub(crate) fn merge_ellipses<'a>(
+        &self,
+        input: &'a str,
+        segments: Vec<(&'a str, bool)>,
+    ) -> Vec<(&'a str, bool)> {
+        if !self.ellipsis {
+            return segments;
+        }
+        let mut merged: Vec<(&'a str, bool)> = Vec::with_capacity(segments.len());
+        let mut offset = 0;
+        let mut previous_start = 0;
+        for (segment, copied) in segments {
+            let start = offset;
+            offset += segment.len();
+            let is_ellipsis = |text: &str| !copied && text.chars().all(is_ellipsis_char);
+            match merged.last_mut() {
+                Some((last, false)) if is_ellipsis(last) && is_ellipsis(segment) => {
+                    *last = &input[previous_start..offset];
+                }
+                _ => {
+                    merged.push((segment, copied));
+                    previous_start = start;
+                }
+            }
+        }
+        merged
+    }
+
+    /// Rewrite the punctuation in a converted segment, given the last character output before it.
+    pub(crate) fn apply<'a>(
+        &self,
+        to: Script,
+        text: Cow<'a, str>,
+        previous: Option<char>,
+    ) -> Cow<'a, str> {
+        if !(self.fullwidth || self.ellipsis || self.quotes) {
+            return text;
+        }
+        let in_context = previous.is_some_and(is_chinese_context);
+        if self.ellipsis && in_context && !text.is_empty() && text.chars().all(is_ellipsis_char) {
+            let dots = text.chars().filter(|ch| *ch == '.').count();
+            let stops = text.chars().filter(|ch| *ch == '。').count();
+            if text.contains('…') || dots >= 3 || stops >= 3 {
+                return Cow::Owned("……".to_owned());
+            }
+        }
+        let mut output: Option<String> = None;
+        let mut previous = previous;
+        for (i, ch) in text.char_indices() {
+            let replacement = match previous {
+                Some(before) if self.fullwidth && is_chinese_context(before) => fullwidth(ch),
+                _ => None,
+            }
+            .or_else(|| self.quotes.then(|| quote(to, ch)).flatten());
+            let written = replacement.unwrap_or(ch);
+            match (&mut output, replacement) {
+                (Some(output), _) => output.push(written),
+                (None, Some(_)) => {
+                    let mut changed = text[..i].to_owned();
+                    changed.push(written);
+                    output = Some(changed);
+                }
+                (None, None) => {}
+            }
+            previous = Some(written);
+        }
+        output.map_or(text, Cow::Owned)
+    }
+}
+
+impl Engine {
+    /// Set how punctuation is rewritten for the target script.  This applies to
+    /// [Engine::convert_into] and the functions built on it, such as [Engine::convert].
+    pub fn with_punctuation(mut self, punctuation: Punctuation) -> Self {
+        self.punctuation = punctuation;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_punctuation() -> Result<()> {
+        let engine = Engine::new().with_punctuation(Punctuation::all());
+        assert_eq!(
+            "「軟體」，測試……",
+            engine
+                .convert(Script::CN, Script::TW, "“软件”,测试...")?
+                .join("")
+        );
+        assert_eq!(
+            "version 1.0, OK...",
+            engine
+                .convert(Script::CN, Script::TW, "version 1.0, OK...")?
+                .join("")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{convert_named, ENGINE, SCRIPT_NAMES};
+
+/// Report an error to Python as a `ValueError`.
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{:#}", error))
+}
+
+/// Convert a string between two scripts, which may be built-in or custom.  The interpreter lock
+/// is released while converting, so other Python threads keep running.
+#[pyfunction]
+#[pyo3(name = "convert")]
+fn convert_export(
+    py: Python<'_>,
+    from_script: &str,
+    to_script: &str,
+    text: &str,
+) -> PyResult<String> {
+    py.allow_threads(|| convert_named(from_script, to_script, text))
+        .map(|segments| segments.join(""))
+        .map_err(to_py_err)
+}
+
+/// List the names of the built-in scripts.
+#[pyfunction]
+#[pyo3(name = "scripts")]
+fn scripts_export() -> Vec<&'static str> {
+    SCRIPT_NAMES.iter().map(|(name, _)| *name).collect()
+}
+
+/// A converter between two scripts, for converting many strings such as the rows of a table.
+#[pyclass(frozen)]
+struct Converter {
+    from: String,
+    to: String,
+}
+
+#[pymethods]
+impl Converter {
+    /// Create a converter; raises `ValueError` if either script is unknown.
+    #[new]
+    fn new(from_script: &str, to_script: &str) -> PyResult<Self> {
+        ENGINE.resolve_script(from_script).map_err(to_py_err)?;
+        ENGINE.resolve_script(to_script).map_err(to_py_err)?;
+        Ok(Converter {
+            from: from_script.to_owned(),
+            to: to_script.to_owned(),
+        })
+    }
+
+    /// The name of the input script.
+    #[getter(from_script)]
+    fn input_script(&self) -> &str {
+        &self.from
+    }
+
+    /// The name of the output script.
+    #[getter(to_script)]
+    fn output_script(&self) -> &str {
+        &self.to
+    }
+
+    /// Convert a string.
+    fn convert(&self, py: Python<'_>, text: &str) -> PyResult<String> {
+        Ok(self.segments(py, text)?.join(""))
+    }
+
+    /// Convert a string, returning the converted segments instead of joining them.
+    fn segments(&self, py: Python<'_>, text: &str) -> PyResult<Vec<String>> {
+        py.allow_threads(|| convert_named(&self.from, &self.to, text))
+            .map_err(to_py_err)
+    }
+}
+
+/// The `ztarcc` Python module.
+#[pymodule]
+#[pyo3(name = "ztarcc")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(convert_export, module)?)?;
+    module.add_function(wrap_pyfunction!(scripts_export, module)?)?;
+    module.add_class::<Converter>()?;
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use crate::{Engine, Script, SpanSink};
+
+/// Where a segment of the input ended up in the output, in bytes.
+struct Mapping {
+    input: Range<usize>,
+    output: Range<usize>,
+}
+
+/// Map a byte offset in the input to the corresponding offset in the output.  Offsets within a
+/// segment keep their position in characters, which is exact as long as the conversion does not
+/// change the segment's length; otherwise they are clamped to the end of the converted segment.
+fn map_offset(input: &str, output: &str, mappings: &[Mapping], offset: usize) -> usize {
+    let index = mappings.partition_point(|mapping| mapping.input.end <= offset);
+    let Some(mapping) = mappings.get(index) else {
+        return output.len();
+    };
+    let chars = input[mapping.input.start..offset].chars().count();
+    let converted = &output[mapping.output.clone()];
+    mapping.output.start
+        + converted
+            .char_indices()
+            .nth(chars)
+            .map_or(converted.len(), |(i, _)| i)
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, carrying byte ranges of the
+    /// input (such as the extent of bold text or links in a rich-text document) over to the
+    /// output.  Returns the converted text and the remapped ranges, in the order given.
+    pub fn convert_with_ranges(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        ranges: &[Range<usize>],
+    ) -> Result<(String, Vec<Range<usize>>)> {
+        for range in ranges {
+            if range.start > range.end
+                || !input.is_char_boundary(range.start)
+                || !input.is_char_boundary(range.end)
+            {
+                return Err(anyhow!(format!(
+                    "range {:?} does not lie on character boundaries of the input",
+                    range
+                )));
+            }
+        }
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        let mut output = String::with_capacity(input.len());
+        let mut mappings = Vec::with_capacity(sink.spans.len());
+        for span in &sink.spans {
+            let start = output.len();
+            output.push_str(&span.converted);
+            mappings.push(Mapping {
+                input: span.start..span.end,
+                output: start..output.len(),
+            });
+        }
+        let remapped = ranges
+            .iter()
+            .map(|range| {
+                map_offset(input, &output, &mappings, range.start)
+                    ..map_offset(input, &output, &mappings, range.end)
+            })
+            .collect();
+        Ok((output, remapped))
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_with_ranges;
+
+    #[test]
+    fn test_convert_with_ranges() -> Result<()> {
+        let (output, ranges) = convert_with_ranges(
+            Script::CN,
+            Script::TW,
+            "他们的软件",
+            &[0..6, 12..15, 15..15],
+        )?;
+        assert_eq!("他們的軟體", output);
+        assert_eq!(vec![0..6, 12..15, 15..15], ranges);
+        assert_eq!("體", &output[ranges[1].clone()]);
+        let inside_character = std::slice::from_ref(&(0..1));
+        assert!(
+            convert_with_ranges(Script::CN, Script::TW, "他们的软件", inside_character).is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::Engine;
+
+/// Chooses among the candidates when a dictionary entry has several possible conversions, such
+/// as 干 becoming 幹, 乾, or 干.  Implement this to plug in a custom disambiguation model with
+/// [Engine::with_ranker]; by default the first candidate is always used.
+pub trait CandidateRanker: Send + Sync {
+    /// Return the index of the candidate to replace `phrase` with.  The candidates are in
+    /// dictionary order, so the first is the default choice; `context` is the segment the phrase
+    /// was found in.  An index out of range selects the first candidate.
+    fn rank(&self, phrase: &str, candidates: &[String], context: &str) -> usize;
+}
+
+/// The default ranker, which always chooses the first candidate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstCandidate;
+
+impl CandidateRanker for FirstCandidate {
+    fn rank(&self, _phrase: &str, _candidates: &[String], _context: &str) -> usize {
+        0
+    }
+}
+
+/// A ranker that chooses the candidate seen most often in some corpus, preferring the earlier
+/// candidate on ties.  Candidates missing from the counts are treated as never seen.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyRanker {
+    counts: HashMap<String, u64>,
+}
+
+impl FrequencyRanker {
+    /// Build a ranker from pairs of words and how often they occur.
+    pub fn from_counts<'a>(counts: impl IntoIterator<Item = (&'a str, u64)>) -> Self {
+        FrequencyRanker {
+            counts: counts
+                .into_iter()
+                .map(|(word, count)| (word.to_owned(), count))
+                .collect(),
+        }
+    }
+}
+
+impl CandidateRanker for FrequencyRanker {
+    fn rank(&self, _phrase: &str, candidates: &[String], _context: &str) -> usize {
+        let mut best = (0, 0);
+        for (index, candidate) in candidates.iter().enumerate() {
+            let count = self.counts.get(candidate).copied().unwrap_or(0);
+            if count > best.1 {
+                best = (index, count);
+            }
+        }
+        best.0
+    }
+}
+
+impl Engine {
+    /// Choose among the candidates of ambiguous dictionary entries with the given ranker, instead
+    /// of always taking the first.  This loads the list of ambiguous entries alongside each
+    /// dictionary.  Tracing does not use the ranker.
+    pub fn with_ranker(mut self, ranker: impl CandidateRanker + 'static) -> Self {
+        self.ranker = Some(Box::new(ranker));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    #[test]
+    fn test_frequency_ranker() -> Result<()> {
+        let engine = Engine::new();
+        assert_eq!(
+            vec!["幹"],
+            engine.convert_tokens(Script::CN, Script::TW, &["干"])?
+        );
+        let engine = engine.with_ranker(FrequencyRanker::from_counts([("乾", 10), ("幹", 1)]));
+        assert_eq!(
+            vec!["乾"],
+            engine.convert_tokens(Script::CN, Script::TW, &["干"])?
+        );
+        let engine = Engine::new().with_ranker(FirstCandidate);
+        assert_eq!(
+            vec!["幹"],
+            engine.convert_tokens(Script::CN, Script::TW, &["干"])?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::dictionary::{opencc_pairs, read_opencc_text};
+use crate::{Dictionary, Engine, Script};
+
+impl Engine {
+    /// Layer phrases for a region over the built-in dictionaries, such as community Hong Kong
+    /// phrase data, which OpenCC has little of.  Each pair maps a phrase in OpenCC Standard to
+    /// the region's phrase.  Converting to `script` replaces them by longest match before the
+    /// built-in dictionary does, and converting from it replaces them the other way around; like
+    /// any dictionary, they only match within a segment.  Conversions directly between Hong Kong
+    /// and Taiwan do not use them.  Phrases given again for the same script replace these.
+    pub fn with_regional_phrases<'a>(
+        mut self,
+        script: Script,
+        phrases: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        let phrases: Vec<_> = phrases.into_iter().collect();
+        self.regional[script.dictionary_from_standard()] =
+            Some(Dictionary::from_pairs(phrases.clone()));
+        self.regional[script.dictionary_to_standard()] = Some(Dictionary::from_pairs(
+            phrases.into_iter().map(|(from, to)| (to, from)),
+        ));
+        self
+    }
+
+    /// Layer phrases for a region like [Engine::with_regional_phrases], reading them from a
+    /// dictionary in OpenCC's text format.
+    pub fn with_regional_phrases_file(
+        self,
+        script: Script,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let text = read_opencc_text(path)?;
+        let pairs =
+            opencc_pairs(&text).context(format!("parsing dictionary {}", path.display()))?;
+        Ok(self.with_regional_phrases(script, pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regional_phrases() -> Result<()> {
+        let engine = Engine::new().with_regional_phrases(Script::HK, [("出租車", "的士")]);
+        assert_eq!(
+            "搭的士",
+            engine.convert(Script::CN, Script::HK, "搭出租车")?.join("")
+        );
+        assert_eq!(
+            "搭出租车",
+            engine.convert(Script::HK, Script::CN, "搭的士")?.join("")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(feature = "jieba")]
+use jieba_rs::Jieba;
+
+use crate::Engine;
+
+/// Splits text into the segments that are each converted as a unit.  Implement this to use a
+/// different tokenizer with [crate::Engine::with_segmenter]; the bundled Jieba segmenter is used
+/// by default.
+pub trait Segmenter: Send + Sync {
+    /// Split `text` into segments which, concatenated, are exactly `text`.
+    fn cut<'a>(&self, text: &'a str) -> Vec<&'a str>;
+
+    /// Split `text` into words, each with its part-of-speech tag, for
+    /// [crate::Engine::with_protected_tags].  Returns `None` if this segmenter does not tag
+    /// words, which is the default.
+    fn tag<'a>(&'a self, text: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+        let _ = text;
+        None
+    }
+}
+
+#[cfg(feature = "jieba")]
+impl Segmenter for Jieba {
+    fn cut<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        Jieba::cut(self, text, true)
+    }
+
+    fn tag<'a>(&'a self, text: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+        let tags = Jieba::tag(self, text, true);
+        Some(tags.into_iter().map(|tag| (tag.word, tag.tag)).collect())
+    }
+}
+
+/// How the bundled Jieba segmenter cuts text; see [crate::Engine::with_cut_options].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CutOptions {
+    /// Use Jieba's hidden Markov model to find words missing from its dictionary.  These
+    /// occasionally glue characters together so that a phrase dictionary no longer matches.
+    pub hmm: bool,
+    /// Find every dictionary word, including overlapping ones, and convert the longest one
+    /// starting at each position.  This favours long phrases over the most likely segmentation.
+    pub cut_all: bool,
+}
+
+impl Default for CutOptions {
+    fn default() -> Self {
+        CutOptions {
+            hmm: true,
+            cut_all: false,
+        }
+    }
+}
+
+/// The bundled Jieba segmenter, with the options it cuts with.
+#[cfg(feature = "jieba")]
+pub(crate) struct JiebaSegmenter {
+    pub(crate) jieba: Jieba,
+    pub(crate) options: CutOptions,
+}
+
+#[cfg(feature = "jieba")]
+impl JiebaSegmenter {
+    /// Split text into the longest of the overlapping words found by [Jieba::cut_all], falling
+    /// back to single characters where no word starts.
+    fn cut_longest<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut ends = vec![0; text.len()];
+        let base = text.as_ptr() as usize;
+        for word in self.jieba.cut_all(text) {
+            let start = (word.as_ptr() as usize).wrapping_sub(base);
+            if start < text.len() {
+                ends[start] = ends[start].max(start + word.len());
+            }
+        }
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        while let Some(ch) = text[offset..].chars().next() {
+            let end = ends[offset].max(offset + ch.len_utf8());
+            debug_assert!(text.is_char_boundary(end));
+            segments.push(&text[offset..end]);
+            offset = end;
+        }
+        segments
+    }
+}
+
+#[cfg(feature = "jieba")]
+impl Segmenter for JiebaSegmenter {
+    fn cut<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        match self.options.cut_all {
+            true => self.cut_longest(text),
+            false => self.jieba.cut(text, self.options.hmm),
+        }
+    }
+
+    fn tag<'a>(&'a self, text: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+        let tags = self.jieba.tag(text, self.options.hmm);
+        Some(tags.into_iter().map(|tag| (tag.word, tag.tag)).collect())
+    }
+}
+
+impl Engine {
+    /// Split text into the segments it is converted in, without converting it: the bundled
+    /// Jieba segmenter's words, with every multi-character phrase of the conversion
+    /// dictionaries added to its dictionary, or those of the engine's own segmenter.  Protected
+    /// words, skipped regions, and passed-through ASCII runs are kept whole, as in conversion.
+    /// The segments, concatenated, are exactly the input, so they suit indexing and aligning
+    /// text with the same tokenization.
+    pub fn segment<'a>(&self, input: &'a str) -> Vec<&'a str> {
+        self.segments(input)
+            .into_iter()
+            .map(|(segment, _)| segment)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    /// A segmenter that treats each character as its own segment.
+    struct CharSegmenter;
+
+    impl Segmenter for CharSegmenter {
+        fn cut<'a>(&self, text: &'a str) -> Vec<&'a str> {
+            text.char_indices()
+                .map(|(i, ch)| &text[i..i + ch.len_utf8()])
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_custom_segmenter() -> Result<()> {
+        let engine = Engine::with_segmenter(CharSegmenter);
+        let result = engine.convert(Script::CN, Script::TW, "软件")?;
+        assert_eq!(vec!["軟", "件"], result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment() {
+        let engine = Engine::new();
+        let segments = engine.segment("他们的软件");
+        assert_eq!("他们的软件", segments.concat());
+        assert!(segments.contains(&"软件"));
+    }
+
+    #[test]
+    fn test_cut_options() -> Result<()> {
+        let without_hmm = CutOptions {
+            hmm: false,
+            ..CutOptions::default()
+        };
+        let cut_all = CutOptions {
+            cut_all: true,
+            ..CutOptions::default()
+        };
+        for options in [without_hmm, cut_all] {
+            let engine = Engine::with_cut_options(options);
+            let result = engine.convert(Script::CN, Script::TW, "他们的软件, ok")?;
+            assert_eq!("他們的軟體, ok", result.join(""));
+        }
+
+        Ok(())
+    }
+}
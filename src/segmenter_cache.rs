@@ -0,0 +1,87 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use jieba_rs::Jieba;
+
+use crate::segmenter::JiebaSegmenter;
+use crate::{build_jieba, load_jieba_dict, CutOptions, Engine, EXTRA_WORDS};
+
+/// The name of the cached segmentation dictionary, which changes with the crate version and the
+/// dictionaries built in, so that a cache shared by several builds never mixes them up.
+fn cache_name() -> String {
+    let mut hasher = DefaultHasher::new();
+    EXTRA_WORDS.hash(&mut hasher);
+    format!(
+        "segmenter-{}-{:016x}.txt",
+        env!("CARGO_PKG_VERSION"),
+        hasher.finish()
+    )
+}
+
+/// Write Jieba's dictionary followed by the added words with their frequencies, as one
+/// dictionary in Jieba's format.  It is written to a temporary file first and then renamed, so
+/// that a concurrent run never reads half of it.
+fn write_cache(path: &Path, jieba_dict: &[u8], added: &str) -> Result<()> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(jieba_dict)?;
+    if !jieba_dict.ends_with(b"\n") {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(added.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Read a cached segmentation dictionary.
+fn read_cache(path: &Path) -> Result<Jieba> {
+    let file = fs::File::open(path)?;
+    Jieba::with_dict(&mut BufReader::new(file)).context(format!("reading {}", path.display()))
+}
+
+impl Engine {
+    /// Create an engine like [Engine::with_cut_options], keeping the segmentation dictionary
+    /// (Jieba's, with every dictionary phrase added) in `cache_dir`.  Building it is most of the
+    /// time it takes to create an engine, so short-lived processes that each convert a little
+    /// text start much faster once the cache is written.  The cache is written on first use and
+    /// rebuilt if it cannot be read; the engine is created even if it cannot be written.
+    pub fn with_cache_dir(options: CutOptions, cache_dir: impl AsRef<Path>) -> Self {
+        let path = cache_dir.as_ref().join(cache_name());
+        let jieba = read_cache(&path).unwrap_or_else(|_| {
+            let jieba_dict = load_jieba_dict();
+            let mut added = String::new();
+            let jieba = build_jieba(&jieba_dict, |word, frequency| {
+                added.push_str(&format!("{} {}\n", word, frequency));
+            });
+            let _ = write_cache(&path, &jieba_dict, &added);
+            jieba
+        });
+        Self::with_segmenter(JiebaSegmenter { jieba, options })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+
+    #[test]
+    fn test_with_cache_dir() -> Result<()> {
+        let cache_dir = std::env::temp_dir().join(format!("ztarcc-cache-{}", std::process::id()));
+        for _ in 0..2 {
+            let engine = Engine::with_cache_dir(CutOptions::default(), &cache_dir);
+            let result = engine.convert(Script::CN, Script::TW, "他们的软件")?;
+            assert_eq!("他們的軟體", result.join(""));
+        }
+        assert!(cache_dir.join(cache_name()).exists());
+        fs::remove_dir_all(&cache_dir)?;
+        Ok(())
+    }
+}
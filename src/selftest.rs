@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+
+use crate::{chain, Engine, Script};
+
+/// Conversions with known results, one for each kind of dictionary chain.
+const GOLDEN: [(Script, Script, &str, &str); 7] = [
+    (Script::CN, Script::TW, "他们的软件", "他們的軟體"),
+    (Script::TW, Script::CN, "他們的軟體", "他们的软件"),
+    (
+        Script::CN,
+        Script::HK,
+        "他们是勇敢的士兵",
+        "他們是勇敢的士兵",
+    ),
+    (
+        Script::HK,
+        Script::CN,
+        "他們是勇敢的士兵",
+        "他们是勇敢的士兵",
+    ),
+    (Script::HK, Script::TW, "看着他", "看著他"),
+    (Script::TW, Script::HK, "看著他", "看着他"),
+    (Script::ST, Script::TW, "優化", "最佳化"),
+];
+
+/// The result of a successful [Engine::self_test].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SelfTestReport {
+    /// The embedded dictionaries that were verified, with how many entries each has.
+    pub dictionaries: Vec<(String, usize)>,
+    /// The number of golden conversions that gave the expected result.
+    pub conversions: usize,
+}
+
+impl Engine {
+    /// Check that the embedded data is intact, for example at service startup before taking
+    /// traffic: verify every dictionary included in the build, then run a golden conversion for
+    /// each kind of dictionary chain those dictionaries support, unless the phrase dictionaries
+    /// were left out with the `jieba` feature.  Fails with the first problem found.  Run this on
+    /// an engine with default options, since protected words, a ranker, or other options can
+    /// change the golden conversions.
+    pub fn self_test(&self) -> Result<SelfTestReport> {
+        let mut dictionaries = Vec::new();
+        for (key, _) in &self.dictionaries {
+            if let Ok(dictionary) = self.dictionary(key) {
+                let entries = dictionary
+                    .verify()
+                    .map_err(|error| anyhow!(format!("dictionary {:?}: {}", key, error)))?;
+                dictionaries.push((format!("{:?}", key), entries));
+            }
+        }
+        let mut conversions = 0;
+        // Without the `jieba` feature, the phrase dictionaries most of these rely on are left out.
+        let golden = if cfg!(feature = "jieba") {
+            &GOLDEN[..]
+        } else {
+            &[]
+        };
+        for &(from, to, input, expected) in golden {
+            let included = chain(from, to)
+                .iter()
+                .all(|key| self.dictionary(*key).is_ok());
+            if !included {
+                continue;
+            }
+            let converted = self.convert(from, to, input)?.join("");
+            if converted != expected {
+                return Err(anyhow!(format!(
+                    "converting {:?} gave {:?} instead of {:?}",
+                    input, converted, expected
+                )));
+            }
+            conversions += 1;
+        }
+        Ok(SelfTestReport {
+            dictionaries,
+            conversions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test() -> Result<()> {
+        let report = Engine::new().self_test()?;
+        assert!(report.conversions > 0);
+        assert!(report.dictionaries.iter().all(|(_, entries)| *entries > 0));
+
+        Ok(())
+    }
+}
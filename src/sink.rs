@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::{Engine, Script};
+
+/// Receives the output of a conversion one segment at a time, in input order.  Implement this to
+/// render the output in a custom way; see [crate::Engine::convert_into].
+pub trait ConversionSink {
+    /// A segment that the conversion left unchanged.
+    fn unchanged(&mut self, text: &str) -> Result<()>;
+
+    /// A segment that the conversion changed from `original` to `converted`.
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()>;
+}
+
+/// Collects the converted text.
+impl ConversionSink for String {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.push_str(text);
+        Ok(())
+    }
+
+    fn replaced(&mut self, _original: &str, converted: &str) -> Result<()> {
+        self.push_str(converted);
+        Ok(())
+    }
+}
+
+/// Collects the converted segments.
+impl ConversionSink for Vec<String> {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.push(text.to_owned());
+        Ok(())
+    }
+
+    fn replaced(&mut self, _original: &str, converted: &str) -> Result<()> {
+        self.push(converted.to_owned());
+        Ok(())
+    }
+}
+
+/// Writes the converted text to a writer as it is produced.
+pub struct WriteSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriteSink<W> {
+    pub fn new(writer: W) -> Self {
+        WriteSink { writer }
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ConversionSink for WriteSink<W> {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        Ok(self.writer.write_all(text.as_bytes())?)
+    }
+
+    fn replaced(&mut self, _original: &str, converted: &str) -> Result<()> {
+        Ok(self.writer.write_all(converted.as_bytes())?)
+    }
+}
+
+/// The template [RubySink] uses by default.
+pub const RUBY_TEMPLATE: &str = "<ruby>{converted}<rt>{original}</rt></ruby>";
+
+/// Renders the converted text as HTML, writing each changed segment through a template with
+/// `{converted}` and `{original}` in it, by default [RUBY_TEMPLATE], so that readers can see the
+/// original form above the converted one.  All text is escaped for HTML.
+pub struct RubySink {
+    template: String,
+    html: String,
+}
+
+impl Default for RubySink {
+    fn default() -> Self {
+        RubySink::with_template(RUBY_TEMPLATE)
+    }
+}
+
+impl RubySink {
+    /// Render changed segments with `template` instead of [RUBY_TEMPLATE].
+    pub fn with_template(template: &str) -> Self {
+        RubySink {
+            template: template.to_owned(),
+            html: String::new(),
+        }
+    }
+
+    /// Return the HTML rendered so far.
+    pub fn into_html(self) -> String {
+        self.html
+    }
+}
+
+/// Escape text for HTML, for element content and quoted attribute values alike.
+fn escape_html(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+impl ConversionSink for RubySink {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.html.push_str(&escape_html(text));
+        Ok(())
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        let rendered = self
+            .template
+            .replace("{converted}", &escape_html(converted))
+            .replace("{original}", &escape_html(original));
+        self.html.push_str(&rendered);
+        Ok(())
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, rendering it as HTML with
+    /// each changed segment annotated with its original form; see [RubySink].
+    pub fn convert_ruby(&self, from: Script, to: Script, input: &str) -> Result<String> {
+        let mut sink = RubySink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        Ok(sink.into_html())
+    }
+
+    /// Convert a string from an input variant to an output variant, writing each segment to
+    /// `writer` as it is converted instead of collecting the output in memory.
+    pub fn convert_to_writer<W: Write + ?Sized>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        writer: &mut W,
+    ) -> Result<()> {
+        self.convert_into(from, to, input, &mut WriteSink::new(writer))
+    }
+}
+
+/// A segment of the input, given as a byte range, and its converted form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub converted: String,
+}
+
+/// Collects the converted segments along with where they came from in the input.
+#[derive(Default)]
+pub struct SpanSink {
+    pub spans: Vec<Span>,
+    offset: usize,
+}
+
+impl SpanSink {
+    fn push(&mut self, original: &str, converted: &str) {
+        let start = self.offset;
+        self.offset += original.len();
+        self.spans.push(Span {
+            start,
+            end: self.offset,
+            converted: converted.to_owned(),
+        });
+    }
+}
+
+impl ConversionSink for SpanSink {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.push(text, text);
+        Ok(())
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        self.push(original, converted);
+        Ok(())
+    }
+}
+
+/// Counts how often each phrase was changed, ignoring unchanged text.
+#[derive(Default)]
+pub struct DiffSink {
+    counts: HashMap<(String, String), usize>,
+}
+
+impl DiffSink {
+    /// Add the counts from another sink, such as one used on a different part of the input.
+    pub fn merge(&mut self, other: DiffSink) {
+        for (pair, count) in other.counts {
+            *self.counts.entry(pair).or_default() += count;
+        }
+    }
+
+    /// The changed phrases as `(original, converted)` with their counts, most frequent first.
+    pub fn summary(&self) -> Vec<((&str, &str), usize)> {
+        let mut summary: Vec<_> = self
+            .counts
+            .iter()
+            .map(|((original, converted), count)| ((original.as_str(), converted.as_str()), *count))
+            .collect();
+        summary.sort_by(|(a_pair, a_count), (b_pair, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_pair.cmp(b_pair))
+        });
+        summary
+    }
+}
+
+impl ConversionSink for DiffSink {
+    fn unchanged(&mut self, _text: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        *self
+            .counts
+            .entry((original.to_owned(), converted.to_owned()))
+            .or_default() += 1;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::{convert_into, convert_to_writer};
+
+    #[test]
+    fn test_write_sink() -> Result<()> {
+        let mut sink = WriteSink::new(Vec::new());
+        convert_into(Script::CN, Script::TW, "他们的软件", &mut sink)?;
+        assert_eq!("他們的軟體", String::from_utf8(sink.into_inner())?);
+
+        let mut output = Vec::new();
+        convert_to_writer(Script::CN, Script::TW, "他们的软件", &mut output)?;
+        assert_eq!("他們的軟體", String::from_utf8(output)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ruby_sink() -> Result<()> {
+        let mut sink = RubySink::default();
+        convert_into(Script::CN, Script::TW, "<b>软件</b>", &mut sink)?;
+        assert_eq!(
+            "&lt;b&gt;<ruby>軟體<rt>软件</rt></ruby>&lt;/b&gt;",
+            sink.into_html()
+        );
+
+        let mut sink = RubySink::with_template("{converted}({original})");
+        convert_into(Script::CN, Script::TW, "他们的软件", &mut sink)?;
+        assert_eq!("他們(他们)的軟體(软件)", sink.into_html());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_sink() -> Result<()> {
+        let mut sink = DiffSink::default();
+        convert_into(Script::CN, Script::TW, "软件和软件", &mut sink)?;
+        assert_eq!(vec![(("软件", "軟體"), 2)], sink.summary());
+
+        Ok(())
+    }
+}
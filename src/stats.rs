@@ -0,0 +1,148 @@
+use anyhow::Result;
+
+use crate::language::is_han;
+use crate::{chain, Chain, ConversionSink, Engine, Script};
+
+/// Counts describing a conversion, for quality dashboards; see [Engine::convert_with_stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ConversionStats {
+    /// The number of characters in the input.
+    pub characters: usize,
+    /// The number of characters changed, comparing each converted segment with its input
+    /// character by character.
+    pub characters_changed: usize,
+    /// The number of dictionary entries, phrase or single character, that changed the text,
+    /// counted over every dictionary in the conversion.
+    pub rules_applied: usize,
+    /// The number of Chinese characters that no dictionary entry matched, which the dictionaries
+    /// may be missing.
+    pub unmatched_han: usize,
+}
+
+/// Collects the converted segments, counting as it goes.
+struct StatsSink<'e> {
+    engine: &'e Engine,
+    keys: Chain,
+    segments: Vec<String>,
+    stats: ConversionStats,
+}
+
+impl StatsSink<'_> {
+    /// Apply each dictionary to a segment in turn, counting the entries that changed it and
+    /// the Chinese characters left that no entry covered.
+    fn count_matches(&mut self, original: &str) -> Result<()> {
+        let mut text = self.engine.normalization.normalize(original).into_owned();
+        let mut covered = vec![false; text.chars().count()];
+        for key in &self.keys {
+            let dict = self.engine.dictionary(*key)?;
+            let mut next = String::with_capacity(text.len());
+            let mut next_covered = Vec::with_capacity(covered.len());
+            let mut index = 0;
+            for piece in dict.pieces(&text) {
+                let count = piece.text.chars().count();
+                match piece.replacement {
+                    Some(replacement) => {
+                        if replacement != piece.text {
+                            self.stats.rules_applied += 1;
+                        }
+                        next.push_str(replacement);
+                        next_covered.extend(replacement.chars().map(|_| true));
+                    }
+                    None => {
+                        next.push_str(piece.text);
+                        next_covered.extend_from_slice(&covered[index..index + count]);
+                    }
+                }
+                index += count;
+            }
+            text = next;
+            covered = next_covered;
+        }
+        self.stats.unmatched_han += text
+            .chars()
+            .zip(covered)
+            .filter(|&(ch, covered)| is_han(ch) && !covered)
+            .count();
+        Ok(())
+    }
+}
+
+impl ConversionSink for StatsSink<'_> {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.stats.characters += text.chars().count();
+        self.count_matches(text)?;
+        self.segments.push(text.to_owned());
+        Ok(())
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        let (before, after) = (original.chars().count(), converted.chars().count());
+        self.stats.characters += before;
+        self.stats.characters_changed += original
+            .chars()
+            .zip(converted.chars())
+            .filter(|(a, b)| a != b)
+            .count()
+            + before.abs_diff(after);
+        self.count_matches(original)?;
+        self.segments.push(converted.to_owned());
+        Ok(())
+    }
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant like [Engine::convert], also
+    /// counting the characters examined and changed, the dictionary entries applied, and the
+    /// Chinese characters that no entry matched.  Input stopped by the language guard is not
+    /// looked up, so all of its Chinese characters count as unmatched.
+    pub fn convert_with_stats(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> Result<(Vec<String>, ConversionStats)> {
+        if self.guards(input) {
+            let stats = ConversionStats {
+                characters: input.chars().count(),
+                unmatched_han: input.chars().filter(|&ch| is_han(ch)).count(),
+                ..Default::default()
+            };
+            return Ok((vec![input.to_owned()], stats));
+        }
+        let mut sink = StatsSink {
+            engine: self,
+            keys: chain(from, to),
+            segments: Vec::new(),
+            stats: ConversionStats::default(),
+        };
+        self.convert_into(from, to, input, &mut sink)?;
+        Ok((sink.segments, sink.stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_with_stats() -> Result<()> {
+        let engine = Engine::new();
+        let (segments, stats) = engine.convert_with_stats(Script::CN, Script::TW, "他们的软件")?;
+        assert_eq!("他們的軟體", segments.join(""));
+        assert_eq!(5, stats.characters);
+        assert_eq!(3, stats.characters_changed);
+        assert!(stats.rules_applied >= 2);
+
+        let (_, stats) = engine.convert_with_stats(Script::CN, Script::TW, "abc")?;
+        assert_eq!(
+            ConversionStats {
+                characters: 3,
+                ..Default::default()
+            },
+            stats
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+use crate::{Engine, Script, SpanSink};
+
+/// A segment of the input that appears to be in the wrong script, with its suggested replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The byte offset of the start of the segment in the input.
+    pub start: usize,
+    /// The byte offset of the end of the segment in the input.
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+/// The script to convert from when looking for text that does not belong in `expected`: text
+/// that is already in the expected script passes through that conversion unchanged.
+fn opposite(expected: Script) -> Script {
+    match expected {
+        Script::CN => Script::TW,
+        Script::ST | Script::TW | Script::HK => Script::CN,
+    }
+}
+
+impl Engine {
+    /// Find the segments of `input` that are not written in the `expected` script, and suggest
+    /// replacements for them, in input order.  This is meant for interactive tooling, such as
+    /// highlighting wrong-script phrases in an editor.
+    pub fn suggest_corrections(&self, expected: Script, input: &str) -> Result<Vec<Suggestion>> {
+        let mut sink = SpanSink::default();
+        self.convert_into(opposite(expected), expected, input, &mut sink)?;
+        Ok(sink
+            .spans
+            .into_iter()
+            .filter(|span| input[span.start..span.end] != span.converted)
+            .map(|span| Suggestion {
+                start: span.start,
+                end: span.end,
+                original: input[span.start..span.end].to_owned(),
+                replacement: span.converted,
+            })
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::suggest_corrections;
+
+    #[test]
+    fn test_suggest_corrections() -> Result<()> {
+        assert_eq!(
+            vec![Suggestion {
+                start: 0,
+                end: 6,
+                original: "软件".to_owned(),
+                replacement: "軟體".to_owned(),
+            }],
+            suggest_corrections(Script::TW, "软件")?
+        );
+        assert!(suggest_corrections(Script::TW, "他們是勇敢的士兵")?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+use anyhow::Result;
+
+use crate::{chain, Dictionary, Engine, Script};
+
+/// A rewrite made during a traced conversion.  Requires the `unstable` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// The byte range of the input that the rewritten text came from.
+    pub input_span: Range<usize>,
+    /// The text that the dictionary entry produced.
+    pub output_text: String,
+    /// The name of the dictionary the entry belongs to, such as `FromChina`.
+    pub dictionary: String,
+    /// The key of the dictionary entry that matched.
+    pub matched_key: String,
+}
+
+/// A piece of partially converted text, with the byte range of the input it came from.
+struct Piece {
+    source: Range<usize>,
+    text: String,
+}
+
+/// Apply a single dictionary to a sequence of pieces like [crate::convert_pass], recording every
+/// entry that changed the text.
+fn trace_pass(
+    dict: &Dictionary,
+    dictionary: &str,
+    pieces: Vec<Piece>,
+    records: &mut Vec<TraceRecord>,
+) -> Vec<Piece> {
+    let mut starts = Vec::with_capacity(pieces.len());
+    let mut text = String::new();
+    for piece in &pieces {
+        starts.push(text.len());
+        text.push_str(&piece.text);
+    }
+    // The input range covered by the text in `start..end`.
+    let source = |start: usize, end: usize| {
+        let first = starts.partition_point(|s| *s <= start) - 1;
+        let last = starts.partition_point(|s| *s < end) - 1;
+        pieces[first].source.start..pieces[last].source.end
+    };
+
+    let mut output = Vec::new();
+    for matched in dict.pieces(&text) {
+        let piece = Piece {
+            source: source(matched.offset, matched.end()),
+            text: matched.replacement.unwrap_or(matched.text).to_owned(),
+        };
+        if let Some(value) = matched.replacement {
+            if matched.text != value {
+                records.push(TraceRecord {
+                    input_span: piece.source.clone(),
+                    output_text: value.to_owned(),
+                    dictionary: dictionary.to_owned(),
+                    matched_key: matched.text.to_owned(),
+                });
+            }
+        }
+        output.push(piece);
+    }
+    output
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant, also returning a record of
+    /// every dictionary entry that rewrote part of it, in the order they were applied.
+    pub fn convert_traced(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> Result<(String, Vec<TraceRecord>)> {
+        if self.guards(input) {
+            return Ok((input.to_owned(), Vec::new()));
+        }
+        let keys = chain(from, to);
+        let mut output = String::with_capacity(input.len());
+        let mut records = Vec::new();
+        let mut segment_start = 0;
+        for (word, copied) in self.segments(input) {
+            let start = segment_start;
+            segment_start += word.len();
+            if copied {
+                output.push_str(word);
+                continue;
+            }
+            let mut pieces: Vec<Piece> = word
+                .char_indices()
+                .map(|(i, ch)| Piece {
+                    source: start + i..start + i + ch.len_utf8(),
+                    text: ch.to_string(),
+                })
+                .collect();
+            for key in &keys {
+                let name = format!("{:?}", key);
+                pieces = trace_pass(self.dictionary(*key)?, &name, pieces, &mut records);
+            }
+            pieces.iter().for_each(|piece| output.push_str(&piece.text));
+        }
+        Ok((output, records))
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_traced;
+
+    #[test]
+    fn test_convert_traced() -> Result<()> {
+        let (result, records) = convert_traced(Script::CN, Script::TW, "他们的软件")?;
+        assert_eq!("他們的軟體", result);
+        assert!(records.contains(&TraceRecord {
+            input_span: 9..15,
+            output_text: "軟體".to_owned(),
+            dictionary: "ToTaiwan".to_owned(),
+            matched_key: "軟件".to_owned(),
+        }));
+
+        Ok(())
+    }
+}
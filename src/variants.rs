@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+
+use crate::{Chain, DictionaryKeys, Engine, Script};
+
+impl Engine {
+    /// Normalize traditional text to the character variants of `to`, without converting between
+    /// simplified and traditional characters or rewriting regional phrases: `TW` or `HK` for
+    /// their regional variants, or `ST` for OpenCC Standard.  Text that differs only in variants
+    /// then normalizes to the same string, for search and deduplication.
+    pub fn normalize_variants(&self, to: Script, input: &str) -> Result<String> {
+        let keys: &[DictionaryKeys] = match to {
+            Script::ST => &[
+                DictionaryKeys::FromHongKong,
+                DictionaryKeys::VariantsFromTaiwan,
+            ],
+            Script::TW => &[
+                DictionaryKeys::FromHongKong,
+                DictionaryKeys::VariantsFromTaiwan,
+                DictionaryKeys::VariantsToTaiwan,
+            ],
+            Script::HK => &[
+                DictionaryKeys::VariantsFromTaiwan,
+                DictionaryKeys::FromHongKong,
+                DictionaryKeys::ToHongKong,
+            ],
+            Script::CN => {
+                return Err(anyhow!(
+                    "simplified Chinese is not a set of traditional variants"
+                ))
+            }
+        };
+        let keys = Chain::new(keys);
+        let mut output = String::with_capacity(input.len());
+        for (word, copied) in self.segments(input) {
+            match copied {
+                true => output.push_str(word),
+                false => output.push_str(&self.convert_word(&keys, word)?),
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::normalize_variants;
+
+    #[test]
+    fn test_normalize_variants() -> Result<()> {
+        assert_eq!("裡面", normalize_variants(Script::TW, "裏面")?);
+        assert_eq!("裏面", normalize_variants(Script::ST, "裡面")?);
+        assert_eq!("裏面", normalize_variants(Script::HK, "裡面")?);
+        assert!(normalize_variants(Script::CN, "裡面").is_err());
+
+        Ok(())
+    }
+}
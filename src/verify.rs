@@ -0,0 +1,93 @@
+use anyhow::Result;
+
+use crate::{Engine, Script, SpanSink};
+
+/// A segment of the input that does not survive a round trip: converting it and converting the
+/// result back gives something else, such as 後 in simplified text, which converts back as 后.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LossySpan {
+    /// The byte offset of the segment in the input.
+    pub start: usize,
+    /// The byte offset of the end of the segment in the input.
+    pub end: usize,
+    /// The segment as it is in the input.
+    pub original: String,
+    /// The segment converted to the output script.
+    pub forward: String,
+    /// The conversion converted back to the input script.
+    pub back: String,
+}
+
+/// The result of [Engine::verify].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VerifyReport {
+    /// The converted text.
+    pub converted: String,
+    /// The segments that do not convert back to what they were, in order.
+    pub lossy: Vec<LossySpan>,
+    /// Whether converting the converted text again leaves it as it is.
+    pub idempotent: bool,
+}
+
+impl VerifyReport {
+    /// Whether the conversion is safe to apply unreviewed: every segment converts back to what
+    /// it was, and converting again changes nothing.
+    pub fn is_safe(&self) -> bool {
+        self.lossy.is_empty() && self.idempotent
+    }
+}
+
+impl Engine {
+    /// Check whether a conversion is reversible, for pipelines that gate automated conversions:
+    /// convert the input, convert each segment of the result back, and report the segments that
+    /// come back different, along with whether converting the result again is a no-op.  Each
+    /// segment is converted back on its own, so the way back can't be affected by a
+    /// neighbouring segment.
+    pub fn verify(&self, from: Script, to: Script, input: &str) -> Result<VerifyReport> {
+        let mut sink = SpanSink::default();
+        self.convert_into(from, to, input, &mut sink)?;
+        let mut lossy = Vec::new();
+        for span in &sink.spans {
+            let original = &input[span.start..span.end];
+            let back = self.convert(to, from, &span.converted)?.join("");
+            if back != original {
+                lossy.push(LossySpan {
+                    start: span.start,
+                    end: span.end,
+                    original: original.to_owned(),
+                    forward: span.converted.clone(),
+                    back,
+                });
+            }
+        }
+        let converted: String = sink.spans.into_iter().map(|span| span.converted).collect();
+        let idempotent = self.convert(from, to, &converted)?.join("") == converted;
+        Ok(VerifyReport {
+            converted,
+            lossy,
+            idempotent,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::verify;
+
+    #[test]
+    fn test_verify() -> Result<()> {
+        let report = verify(Script::CN, Script::TW, "他们的软件")?;
+        assert_eq!("他們的軟體", report.converted);
+        assert!(report.is_safe());
+
+        let report = verify(Script::CN, Script::TW, "後来")?;
+        assert!(!report.is_safe());
+        assert_eq!(0, report.lossy[0].start);
+        assert!(report.lossy[0].original.starts_with('後'));
+        assert!(report.lossy[0].back.starts_with('后'));
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use std::{fmt, ops::Range};
+
+use anyhow::Result;
+
+use crate::{
+    chain, convert_pass, detect_language, Ambiguities, ConversionSink, Dictionary, Engine,
+    Language, Script,
+};
+
+/// Something that did not stop a conversion, but may mean the output is not what was wanted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A replacement character or private-use character was passed through unconverted.  These
+    /// usually mean that the input was decoded with the wrong encoding, or relies on a custom font.
+    UnknownCharacter { offset: usize, character: char },
+    /// The segmenter split a dictionary phrase, and converting the pieces separately gave a
+    /// different result than converting the phrase as a whole.
+    PhraseSplit { offset: usize, phrase: String },
+    /// A dictionary entry with several candidates was used, so the conversion may be wrong.  The
+    /// offset is that of the segment containing the phrase, and the first candidate is the one
+    /// that was used.
+    Ambiguous {
+        offset: usize,
+        phrase: String,
+        candidates: Vec<String>,
+    },
+    /// A custom script override was replaced by a later override of the same phrase.
+    ShadowedOverride {
+        phrase: String,
+        replacement: String,
+        shadowed_by: String,
+    },
+    /// The input looks like Japanese or Korean rather than Chinese, so converting it with the
+    /// Chinese dictionaries probably gave nonsense.  See [crate::Engine::with_language_guard].
+    ForeignLanguage { language: Language },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnknownCharacter { offset, character } => write!(
+                f,
+                "unknown character U+{:04X} at byte {} passed through",
+                *character as u32, offset
+            ),
+            Warning::PhraseSplit { offset, phrase } => write!(
+                f,
+                "phrase {} at byte {} was split by segmentation",
+                phrase, offset
+            ),
+            Warning::Ambiguous {
+                offset,
+                phrase,
+                candidates,
+            } => write!(
+                f,
+                "phrase {} at byte {} is ambiguous between {}",
+                phrase,
+                offset,
+                candidates.join(" ")
+            ),
+            Warning::ShadowedOverride {
+                phrase,
+                replacement,
+                shadowed_by,
+            } => write!(
+                f,
+                "override {}→{} is shadowed by {}→{}",
+                phrase, replacement, phrase, shadowed_by
+            ),
+            Warning::ForeignLanguage { language } => {
+                write!(f, "input looks like {:?} rather than Chinese", language)
+            }
+        }
+    }
+}
+
+impl Warning {
+    /// The byte offset in the input that the warning applies to, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Warning::UnknownCharacter { offset, .. }
+            | Warning::PhraseSplit { offset, .. }
+            | Warning::Ambiguous { offset, .. } => Some(*offset),
+            Warning::ShadowedOverride { .. } | Warning::ForeignLanguage { .. } => None,
+        }
+    }
+}
+
+/// Find the ambiguous entries that converting `word` with `dict` uses, in order.
+fn ambiguous_matches<'a>(
+    dict: &Dictionary,
+    ambiguities: &'a Ambiguities,
+    word: &str,
+) -> Vec<(String, &'a Vec<String>)> {
+    dict.pieces(word)
+        .filter(|piece| piece.replacement.is_some())
+        .filter_map(|piece| {
+            let candidates = ambiguities.get(piece.text)?;
+            Some((piece.text.to_owned(), candidates))
+        })
+        .collect()
+}
+
+/// Passes segments on to another sink, keeping the range of the input each came from.
+struct RangeSink<'a, S: ?Sized> {
+    sink: &'a mut S,
+    ranges: Vec<Range<usize>>,
+}
+
+impl<S: ?Sized> RangeSink<'_, S> {
+    fn push(&mut self, original: &str) {
+        let start = self.ranges.last().map_or(0, |range| range.end);
+        self.ranges.push(start..start + original.len());
+    }
+}
+
+impl<S: ConversionSink + ?Sized> ConversionSink for RangeSink<'_, S> {
+    fn unchanged(&mut self, text: &str) -> Result<()> {
+        self.push(text);
+        self.sink.unchanged(text)
+    }
+
+    fn replaced(&mut self, original: &str, converted: &str) -> Result<()> {
+        self.push(original);
+        self.sink.replaced(original, converted)
+    }
+}
+
+/// Whether a character is the replacement character or in a private use area.
+fn is_unknown(ch: char) -> bool {
+    matches!(ch, '\u{FFFD}' | '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{10FFFD}')
+}
+
+impl Engine {
+    /// Convert a string from an input variant to an output variant like [Engine::convert], also
+    /// returning warnings about anything suspicious, in input order.  Offsets are in bytes.
+    pub fn convert_with_warnings(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+    ) -> Result<(Vec<String>, Vec<Warning>)> {
+        let mut segments = Vec::new();
+        let warnings = self.convert_with_warnings_into(from, to, input, &mut segments)?;
+        Ok((segments, warnings))
+    }
+
+    /// Convert a string like [Engine::convert_with_warnings], passing each segment to `sink` as
+    /// it is converted and returning only the warnings.
+    pub fn convert_with_warnings_into<S: ConversionSink + ?Sized>(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        sink: &mut S,
+    ) -> Result<Vec<Warning>> {
+        let mut sink = RangeSink {
+            sink,
+            ranges: Vec::new(),
+        };
+        self.convert_into(from, to, input, &mut sink)?;
+        let mut warnings = Vec::new();
+        if let Some(language) = detect_language(input) {
+            warnings.push(Warning::ForeignLanguage { language });
+            if self.language_guard {
+                return Ok(warnings);
+            }
+        }
+        let segment_ends: Vec<usize> = sink.ranges.iter().map(|range| range.end).collect();
+        let dict = self.dictionary(chain(from, to)[0])?;
+
+        let mut phrase_end = 0;
+        for (offset, ch) in input.char_indices() {
+            if is_unknown(ch) {
+                warnings.push(Warning::UnknownCharacter {
+                    offset,
+                    character: ch,
+                });
+            }
+            if offset < phrase_end {
+                continue;
+            }
+            let Some((len, value)) = dict.longest_prefix(&input[offset..]) else {
+                continue;
+            };
+            let end = offset + len;
+            let boundaries: Vec<usize> = segment_ends
+                .iter()
+                .copied()
+                .filter(|boundary| *boundary > offset && *boundary < end)
+                .collect();
+            if boundaries.is_empty() {
+                continue;
+            }
+            let mut pieces = String::new();
+            let mut start = offset;
+            for boundary in boundaries.into_iter().chain([end]) {
+                pieces.push_str(&convert_pass(dict, &input[start..boundary]));
+                start = boundary;
+            }
+            if pieces != value {
+                warnings.push(Warning::PhraseSplit {
+                    offset,
+                    phrase: input[offset..end].to_owned(),
+                });
+                phrase_end = end;
+            }
+        }
+
+        let keys = chain(from, to);
+        for range in sink.ranges {
+            let mut word = input[range.clone()].to_owned();
+            for key in &keys {
+                let dict = self.dictionary(*key)?;
+                for (phrase, candidates) in ambiguous_matches(dict, self.ambiguities(*key)?, &word)
+                {
+                    warnings.push(Warning::Ambiguous {
+                        offset: range.start,
+                        phrase,
+                        candidates: candidates.to_owned(),
+                    });
+                }
+                word = convert_pass(dict, &word).into_owned();
+            }
+        }
+        warnings.sort_by_key(Warning::offset);
+        Ok(warnings)
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::convert_with_warnings;
+
+    #[test]
+    fn test_unknown_character_warning() -> Result<()> {
+        let (result, warnings) = convert_with_warnings(Script::CN, Script::TW, "软件\u{FFFD}")?;
+        assert_eq!("軟體\u{FFFD}", result.join(""));
+        assert_eq!(
+            vec![Warning::UnknownCharacter {
+                offset: 6,
+                character: '\u{FFFD}'
+            }],
+            warnings
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_foreign_language_warning() -> Result<()> {
+        let (_, warnings) = convert_with_warnings(Script::CN, Script::TW, "학교에 갑니다")?;
+        assert_eq!(
+            vec![Warning::ForeignLanguage {
+                language: Language::Korean
+            }],
+            warnings
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::incremental::ends_sentence;
+use crate::{
+    configure, convert_named, register_script, ConvertOptions, CustomScript, Dictionary,
+    Normalization, Passthrough, Punctuation, Width, ENGINE, SCRIPT_NAMES,
+};
+
+pub struct JSError {
+    val: String,
+}
+
+impl From<JSError> for JsValue {
+    fn from(error: JSError) -> Self {
+        JsValue::from_str(error.val.as_str())
+    }
+}
+
+impl<T: ToString> From<T> for JSError {
+    fn from(value: T) -> Self {
+        JSError {
+            val: value.to_string(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = convert)]
+pub fn convert_export(from: &str, to: &str, input: &str) -> Result<String, JSError> {
+    Ok(convert_named(from, to, input)?.join(""))
+}
+
+/// Convert, returning the converted segments instead of joining them.
+#[wasm_bindgen(js_name = convertSegments)]
+pub fn convert_segments_export(from: &str, to: &str, input: &str) -> Result<Vec<String>, JSError> {
+    Ok(convert_named(from, to, input)?)
+}
+
+/// Register a custom script from its definition; see [CustomScript::from_definition].
+#[wasm_bindgen(js_name = defineScript)]
+pub fn define_script_export(definition: &str) -> Result<(), JSError> {
+    Ok(register_script(CustomScript::from_definition(definition)?)?)
+}
+
+/// Yield to the JavaScript event loop.
+async fn yield_now() -> Result<(), JSError> {
+    JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+        .await
+        .map_err(|err| format!("{:?}", err))?;
+    Ok(())
+}
+
+/// Yield to the JavaScript event loop with a new task rather than a microtask, so that the page
+/// can render and handle input in the meantime.
+async fn yield_task() -> Result<(), JSError> {
+    let set_timeout: js_sys::Function =
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .map_err(|err| format!("{:?}", err))?
+            .dyn_into()
+            .map_err(|err| format!("setTimeout is not a function: {:?}", err))?;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+    });
+    JsFuture::from(promise)
+        .await
+        .map_err(|err| format!("{:?}", err))?;
+    Ok(())
+}
+
+/// The size, in bytes, of the pieces [convert_chunked_export] converts between yields.
+const PIECE_SIZE: usize = 64 << 10;
+
+/// The length of the next piece of `text` to convert: up to the last line break within `size`
+/// bytes, or `size` bytes (rounded down to a character boundary) if there is none.
+fn piece_len(text: &str, size: usize) -> usize {
+    if text.len() <= size {
+        return text.len();
+    }
+    let mut end = size;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    match text[..end].rfind('\n') {
+        Some(newline) => newline + 1,
+        None => end.max(text.chars().next().map_or(0, char::len_utf8)),
+    }
+}
+
+/// Convert a large string a piece at a time, yielding to the event loop between pieces so that
+/// the page stays responsive.  After each piece, `onProgress(done, total)` is called, if given,
+/// with the number of bytes of UTF-8 input converted so far and in total.  Pieces end at line
+/// breaks where possible, so segmentation is rarely affected.
+#[wasm_bindgen(js_name = convertChunked)]
+pub async fn convert_chunked_export(
+    from: String,
+    to: String,
+    input: String,
+    on_progress: Option<js_sys::Function>,
+) -> Result<String, JSError> {
+    ENGINE.resolve_script(&from)?;
+    ENGINE.resolve_script(&to)?;
+    let mut output = String::with_capacity(input.len());
+    let mut offset = 0;
+    while offset < input.len() {
+        let len = piece_len(&input[offset..], PIECE_SIZE);
+        output.push_str(&convert_named(&from, &to, &input[offset..offset + len])?.join(""));
+        offset += len;
+        if let Some(on_progress) = &on_progress {
+            on_progress
+                .call2(
+                    &JsValue::NULL,
+                    &JsValue::from(offset as f64),
+                    &JsValue::from(input.len() as f64),
+                )
+                .map_err(|err| format!("{:?}", err))?;
+        }
+        yield_task().await?;
+    }
+    Ok(output)
+}
+
+/// A property of an options object, or `undefined` if it is missing.
+fn option(options: &JsValue, name: &str) -> Result<JsValue, JSError> {
+    js_sys::Reflect::get(options, &JsValue::from_str(name))
+        .map_err(|_| format!("cannot read option {}", name).into())
+}
+
+/// A boolean property of an options object, which is false if it is missing.
+fn flag(options: &JsValue, name: &str) -> Result<bool, JSError> {
+    Ok(option(options, name)?.is_truthy())
+}
+
+/// Set the options every conversion uses, as an object whose properties mirror
+/// `ConvertOptions`: `hmm` and `cutAll` for segmentation, `passthrough`, `punctuation`, and
+/// `normalize` to turn on every rule of each, `width` as `"halfwidth"` or `"fullwidth"`,
+/// `languageGuard`, and `protectedWords` as an array of strings.  Missing properties are off,
+/// except `hmm`.  Throws unless called before anything is converted or initialized.
+#[wasm_bindgen(js_name = configure)]
+pub fn configure_export(options: JsValue) -> Result<(), JSError> {
+    let mut convert_options = ConvertOptions::default();
+    let hmm = option(&options, "hmm")?;
+    if !hmm.is_undefined() {
+        convert_options.cut.hmm = hmm.is_truthy();
+    }
+    convert_options.cut.cut_all = flag(&options, "cutAll")?;
+    if flag(&options, "passthrough")? {
+        convert_options.passthrough = Passthrough::all();
+    }
+    if flag(&options, "punctuation")? {
+        convert_options.punctuation = Punctuation::all();
+    }
+    if flag(&options, "normalize")? {
+        convert_options.normalization = Normalization::all();
+    }
+    let width = option(&options, "width")?;
+    convert_options.width = match width.as_string().as_deref() {
+        None if width.is_undefined() => Width::Unchanged,
+        Some("unchanged") => Width::Unchanged,
+        Some("halfwidth") => Width::Halfwidth,
+        Some("fullwidth") => Width::Fullwidth,
+        _ => return Err("width must be \"unchanged\", \"halfwidth\", or \"fullwidth\"".into()),
+    };
+    convert_options.language_guard = flag(&options, "languageGuard")?;
+    let protected_words = option(&options, "protectedWords")?;
+    if !protected_words.is_undefined() {
+        convert_options.protected_words = js_sys::Array::from(&protected_words)
+            .iter()
+            .map(|word| word.as_string().ok_or("protected words must be strings"))
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(configure(convert_options)?)
+}
+
+/// Load the segmenter.  This is optional; it is otherwise loaded on the first conversion.
+#[wasm_bindgen(js_name = initialize)]
+pub async fn initialize_export() -> Result<(), JSError> {
+    yield_now().await?;
+    let _ = &*ENGINE;
+    Ok(())
+}
+
+/// Load the dictionaries for the given conversion pairs, each written as `"from:to"` (for example,
+/// `"cn:tw"`).  Only the dictionaries those pairs need are decompressed; the rest are left until
+/// they are used.  This is optional; dictionaries are otherwise loaded on first use.  To avoid
+/// blocking the main thread entirely, load and convert from within a Web Worker.
+#[wasm_bindgen(js_name = loadDictionaries)]
+pub async fn load_dictionaries_export(pairs: Vec<String>) -> Result<(), JSError> {
+    initialize_export().await?;
+    for pair in pairs {
+        let (from, to) = pair
+            .split_once(':')
+            .ok_or(format!("invalid conversion pair {}", pair))?;
+        let (from_script, _) = ENGINE.resolve_script(from)?;
+        let (to_script, _) = ENGINE.resolve_script(to)?;
+        ENGINE.load(from_script, to_script)?;
+        yield_now().await?;
+    }
+    Ok(())
+}
+
+/// Register a dictionary asset written by a build with the `external-dicts` feature, returning
+/// its name.  See [crate::Engine::register_dictionary].
+#[cfg(feature = "external-dicts")]
+#[wasm_bindgen(js_name = registerDictionary)]
+pub fn register_dictionary_export(asset: &[u8]) -> Result<String, JSError> {
+    Ok(ENGINE.register_dictionary(asset)?)
+}
+
+/// Fetch a URL with the global `fetch`, returning the body of a successful response.
+#[cfg(feature = "external-dicts")]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JSError> {
+    let fetch: js_sys::Function =
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("fetch"))
+            .map_err(|err| format!("{:?}", err))?
+            .dyn_into()
+            .map_err(|err| format!("fetch is not a function: {:?}", err))?;
+    let request = fetch
+        .call1(&JsValue::NULL, &JsValue::from_str(url))
+        .map_err(|err| format!("{:?}", err))?;
+    let response = JsFuture::from(js_sys::Promise::from(request))
+        .await
+        .map_err(|err| format!("fetching {}: {:?}", url, err))?;
+    let ok = js_sys::Reflect::get(&response, &JsValue::from_str("ok"))
+        .map_err(|err| format!("{:?}", err))?;
+    if ok.as_bool() != Some(true) {
+        return Err(format!("fetching {} failed", url).into());
+    }
+    let array_buffer: js_sys::Function =
+        js_sys::Reflect::get(&response, &JsValue::from_str("arrayBuffer"))
+            .map_err(|err| format!("{:?}", err))?
+            .dyn_into()
+            .map_err(|err| format!("arrayBuffer is not a function: {:?}", err))?;
+    let body = array_buffer
+        .call0(&response)
+        .map_err(|err| format!("{:?}", err))?;
+    let buffer = JsFuture::from(js_sys::Promise::from(body))
+        .await
+        .map_err(|err| format!("reading {}: {:?}", url, err))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Fetch and register the dictionary assets needed for the given conversion pairs, written as
+/// in [load_dictionaries_export], from `NAME.bin` under `baseUrl`.  Dictionaries that are
+/// already registered are not fetched again.  Requires a build with the `external-dicts`
+/// feature.
+#[cfg(feature = "external-dicts")]
+#[wasm_bindgen(js_name = fetchDictionaries)]
+pub async fn fetch_dictionaries_export(
+    pairs: Vec<String>,
+    base_url: String,
+) -> Result<(), JSError> {
+    initialize_export().await?;
+    for pair in pairs {
+        let (from, to) = pair
+            .split_once(':')
+            .ok_or(format!("invalid conversion pair {}", pair))?;
+        let (from_script, _) = ENGINE.resolve_script(from)?;
+        let (to_script, _) = ENGINE.resolve_script(to)?;
+        for name in ENGINE.dictionary_chain(from_script, to_script) {
+            if ENGINE.dictionary_names().contains(&name) {
+                continue;
+            }
+            let url = format!("{}/{}.bin", base_url.trim_end_matches('/'), name);
+            ENGINE.register_dictionary(&fetch_bytes(&url).await?)?;
+        }
+    }
+    Ok(())
+}
+
+/// A converter between two scripts, which may be built-in or custom, with its own glossary.
+#[wasm_bindgen]
+pub struct Converter {
+    from: String,
+    to: String,
+    glossary: BTreeMap<String, String>,
+    glossary_dict: Option<Dictionary>,
+    /// Text pushed but not yet converted, because a phrase may continue in the next chunk.
+    pending: String,
+}
+
+/// The length of the part of `pending` that can be converted without waiting for more input:
+/// up to the end of the last sentence.  Past [PIECE_SIZE] bytes without one, it is cut after
+/// the last character that is not a letter or digit, or else anywhere, to keep the buffer
+/// bounded.
+fn complete_len(pending: &str) -> usize {
+    let after = |(i, ch): (usize, char)| i + ch.len_utf8();
+    let sentence_end = pending
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| ends_sentence(*ch));
+    if let Some(end) = sentence_end.map(after) {
+        return end;
+    }
+    if pending.len() <= PIECE_SIZE {
+        return 0;
+    }
+    pending
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| !ch.is_alphanumeric())
+        .map_or(pending.len(), after)
+}
+
+impl Converter {
+    /// Convert a string, replacing each glossary phrase in the input (longest first) with its
+    /// entry and converting the text between as usual.
+    fn convert_with_glossary(&self, input: &str) -> anyhow::Result<Vec<String>> {
+        let Some(dict) = &self.glossary_dict else {
+            return convert_named(&self.from, &self.to, input);
+        };
+        let mut segments = Vec::new();
+        let mut run_start = 0;
+        for piece in dict.pieces(input) {
+            if let Some(replacement) = piece.replacement {
+                if run_start < piece.offset {
+                    segments.extend(convert_named(
+                        &self.from,
+                        &self.to,
+                        &input[run_start..piece.offset],
+                    )?);
+                }
+                segments.push(replacement.to_owned());
+                run_start = piece.end();
+            }
+        }
+        if run_start < input.len() {
+            segments.extend(convert_named(&self.from, &self.to, &input[run_start..])?);
+        }
+        Ok(segments)
+    }
+}
+
+#[wasm_bindgen]
+impl Converter {
+    /// Create a converter; throws if either script is unknown.
+    #[wasm_bindgen(constructor)]
+    pub fn new(from: &str, to: &str) -> Result<Converter, JSError> {
+        ENGINE.resolve_script(from)?;
+        ENGINE.resolve_script(to)?;
+        Ok(Converter {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            glossary: BTreeMap::new(),
+            glossary_dict: None,
+            pending: String::new(),
+        })
+    }
+
+    /// Add a glossary entry: the phrase `from` in the input is always replaced with `to`, such
+    /// as a site's own terminology.  A later entry for the same phrase replaces an earlier one.
+    #[wasm_bindgen(js_name = addWord)]
+    pub fn add_word(&mut self, from: &str, to: &str) -> Result<(), JSError> {
+        if from.is_empty() {
+            return Err("glossary phrases cannot be empty".into());
+        }
+        self.glossary.insert(from.to_owned(), to.to_owned());
+        self.rebuild_glossary();
+        Ok(())
+    }
+
+    /// Add glossary entries from text in OpenCC's dictionary format: each line is a phrase, a
+    /// tab, and its replacement.  Blank lines and lines starting with `#` are skipped.  Returns
+    /// the number of entries added.
+    #[wasm_bindgen(js_name = loadUserDict)]
+    pub fn load_user_dict(&mut self, text: &str) -> Result<usize, JSError> {
+        let mut entries = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(from), Some(to)) => entries.push((from, to)),
+                _ => return Err(format!("no replacement on line {}", index + 1).into()),
+            }
+        }
+        for (from, to) in &entries {
+            self.glossary.insert(from.to_string(), to.to_string());
+        }
+        self.rebuild_glossary();
+        Ok(entries.len())
+    }
+
+    /// Remove every glossary entry.
+    #[wasm_bindgen(js_name = clearWords)]
+    pub fn clear_words(&mut self) {
+        self.glossary.clear();
+        self.glossary_dict = None;
+    }
+
+    /// Rebuild the glossary's dictionary after its entries change.
+    fn rebuild_glossary(&mut self) {
+        self.glossary_dict = Some(Dictionary::from_pairs(
+            self.glossary
+                .iter()
+                .map(|(from, to)| (from.as_str(), to.as_str())),
+        ));
+    }
+
+    /// The name of the input script.
+    #[wasm_bindgen(getter)]
+    pub fn from(&self) -> String {
+        self.from.clone()
+    }
+
+    /// The name of the output script.
+    #[wasm_bindgen(getter)]
+    pub fn to(&self) -> String {
+        self.to.clone()
+    }
+
+    /// Convert a string.
+    pub fn convert(&self, input: &str) -> Result<String, JSError> {
+        Ok(self.convert_with_glossary(input)?.join(""))
+    }
+
+    /// Convert a string, returning the converted segments instead of joining them.
+    pub fn segments(&self, input: &str) -> Result<Vec<String>, JSError> {
+        Ok(self.convert_with_glossary(input)?)
+    }
+
+    /// Convert the next chunk of a stream of text, returning the converted text that is ready.
+    /// The end of the chunk is kept back until the sentence it is in is complete, so that a
+    /// phrase split between chunks is converted as a whole; call [Converter::finish] after the
+    /// last chunk.  This fits the `transform` method of a `TransformStream`.
+    pub fn push(&mut self, chunk: &str) -> Result<String, JSError> {
+        self.pending.push_str(chunk);
+        let len = complete_len(&self.pending);
+        if len == 0 {
+            return Ok(String::new());
+        }
+        let converted = self.convert_with_glossary(&self.pending[..len])?.join("");
+        self.pending.drain(..len);
+        Ok(converted)
+    }
+
+    /// Convert whatever text is still kept back by [Converter::push], ending the stream.  The
+    /// converter can then be used for another stream.
+    pub fn finish(&mut self) -> Result<String, JSError> {
+        let pending = std::mem::take(&mut self.pending);
+        Ok(self.convert_with_glossary(&pending)?.join(""))
+    }
+}
+
+/// List the names of the built-in scripts.
+#[wasm_bindgen(js_name = scripts)]
+pub fn scripts_export() -> Vec<String> {
+    SCRIPT_NAMES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
@@ -0,0 +1,106 @@
+use std::borrow::Cow;
+
+use crate::Engine;
+
+/// Which width Latin letters, digits, and spaces are written in.  Simplified Chinese content
+/// often uses fullwidth forms such as `ＡＢＣ１２３`, which are usually written halfwidth for
+/// publishing in Taiwan.  By default, widths are left as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Width {
+    /// Leave widths as they are.
+    #[default]
+    Unchanged,
+    /// Write fullwidth letters, digits, and ideographic spaces as their ASCII forms.
+    Halfwidth,
+    /// Write ASCII letters, digits, and spaces as their fullwidth forms.
+    Fullwidth,
+}
+
+/// The distance from an ASCII character to its fullwidth form.
+const FULLWIDTH_OFFSET: u32 = 0xFEE0;
+
+impl Width {
+    /// The form of a single character in this width.
+    fn convert_char(self, ch: char) -> char {
+        match self {
+            Width::Unchanged => ch,
+            Width::Halfwidth => match ch {
+                '\u{3000}' => ' ',
+                'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９' => {
+                    char::from_u32(ch as u32 - FULLWIDTH_OFFSET).unwrap_or(ch)
+                }
+                _ => ch,
+            },
+            Width::Fullwidth => match ch {
+                ' ' => '\u{3000}',
+                'A'..='Z' | 'a'..='z' | '0'..='9' => {
+                    char::from_u32(ch as u32 + FULLWIDTH_OFFSET).unwrap_or(ch)
+                }
+                _ => ch,
+            },
+        }
+    }
+
+    /// Rewrite a converted segment in this width, keeping it borrowed if nothing changes.
+    pub(crate) fn apply<'a>(self, text: Cow<'a, str>) -> Cow<'a, str> {
+        if self == Width::Unchanged || text.chars().all(|ch| self.convert_char(ch) == ch) {
+            return text;
+        }
+        Cow::Owned(text.chars().map(|ch| self.convert_char(ch)).collect())
+    }
+}
+
+/// Rewrite the Latin letters, digits, and spaces of some text in the given width, without any
+/// other conversion.  Punctuation is left alone; see [crate::Punctuation] for that.
+pub fn convert_width(input: &str, width: Width) -> String {
+    width.apply(Cow::Borrowed(input)).into_owned()
+}
+
+impl Engine {
+    /// Set which width Latin letters, digits, and spaces are written in, whatever the scripts
+    /// converted between.  This applies to [Engine::convert_into] and the functions built on
+    /// it, [Engine::convert_greedy], and custom scripts in [Engine::convert_named], and not to
+    /// segments copied through without being looked up.
+    pub fn with_width(mut self, width: Width) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Script;
+    use anyhow::Result;
+
+    #[test]
+    fn test_width() -> Result<()> {
+        assert_eq!(
+            "iPhone 15，好",
+            convert_width("ｉＰｈｏｎｅ\u{3000}１５，好", Width::Halfwidth)
+        );
+        assert_eq!(
+            "ｉＰｈｏｎｅ\u{3000}１５，好",
+            convert_width("iPhone 15，好", Width::Fullwidth)
+        );
+        assert_eq!(
+            "ｉＰｈｏｎｅ",
+            convert_width("ｉＰｈｏｎｅ", Width::Unchanged)
+        );
+
+        let engine = Engine::new().with_width(Width::Halfwidth);
+        assert_eq!(
+            "軟體ABC123",
+            engine
+                .convert(Script::CN, Script::TW, "软件ＡＢＣ１２３")?
+                .join("")
+        );
+        assert_eq!(
+            "軟體ABC123",
+            engine.convert_greedy(Script::CN, Script::TW, "软件ＡＢＣ１２３")?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{Chain, Engine};
+
+/// The words converted with one sequence of dictionaries, each with its conversion (or `None`
+/// if it was unchanged) and when it was last used.
+type Words = HashMap<String, (Option<String>, u64)>;
+
+/// The cached words, and the order they were last used in.
+#[derive(Default)]
+struct Entries {
+    words: HashMap<Chain, Words>,
+    recency: BTreeMap<u64, (Chain, String)>,
+    clock: u64,
+}
+
+/// A cache of word conversions that drops the least recently used word when full.
+pub(crate) struct WordCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl WordCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        WordCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Look up the conversion of a word by a sequence of dictionaries: `Some(None)` if it is
+    /// known to be unchanged, and `None` if it is not cached.
+    pub(crate) fn get(&self, keys: &Chain, word: &str) -> Option<Option<String>> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let entries = &mut *entries;
+        entries.clock += 1;
+        let (converted, used) = entries.words.get_mut(keys)?.get_mut(word)?;
+        if let Some(key) = entries.recency.remove(used) {
+            entries.recency.insert(entries.clock, key);
+        }
+        *used = entries.clock;
+        Some(converted.clone())
+    }
+
+    /// Keep the conversion of a word, dropping the least recently used word if full.
+    pub(crate) fn insert(&self, keys: &Chain, word: &str, converted: Option<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let entries = &mut *entries;
+        if entries.recency.len() >= self.capacity {
+            if let Some((_, (keys, word))) = entries.recency.pop_first() {
+                if let Some(words) = entries.words.get_mut(&keys) {
+                    words.remove(&word);
+                }
+            }
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        let words = entries.words.entry(*keys).or_default();
+        if let Some((_, used)) = words.insert(word.to_owned(), (converted, clock)) {
+            entries.recency.remove(&used);
+        }
+        entries.recency.insert(clock, (*keys, word.to_owned()));
+    }
+}
+
+impl Engine {
+    /// Cache the conversions of up to `capacity` words, dropping the least recently used, so
+    /// that the words that repeat in most text skip the dictionaries.  This speeds up
+    /// conversion of repetitive text on one thread, but threads converting at the same time
+    /// with the same engine wait on each other for the cache.
+    pub fn with_word_cache(mut self, capacity: usize) -> Self {
+        self.word_cache = Some(WordCache::new(capacity));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DictionaryKeys, Script};
+    use anyhow::Result;
+
+    #[test]
+    fn test_word_cache() -> Result<()> {
+        let cache = WordCache::new(2);
+        let keys = Chain::one(DictionaryKeys::FromChina);
+        cache.insert(&keys, "软件", Some("軟件".to_owned()));
+        cache.insert(&keys, "的", None);
+        assert_eq!(Some(Some("軟件".to_owned())), cache.get(&keys, "软件"));
+        cache.insert(&keys, "他们", Some("他們".to_owned()));
+        assert_eq!(None, cache.get(&keys, "的"));
+        assert_eq!(Some(Some("軟件".to_owned())), cache.get(&keys, "软件"));
+        assert_eq!(
+            None,
+            cache.get(&Chain::one(DictionaryKeys::FromTaiwan), "软件")
+        );
+
+        let engine = Engine::new().with_word_cache(100);
+        for _ in 0..2 {
+            assert_eq!(
+                "他們的軟體",
+                engine
+                    .convert(Script::CN, Script::TW, "他们的软件")?
+                    .join("")
+            );
+        }
+
+        Ok(())
+    }
+}
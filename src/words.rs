@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::{Engine, Script};
+
+/// Extra segmentation words for a single conversion, with their frequencies.
+pub(crate) struct ExtraWords<'w> {
+    frequencies: HashMap<&'w str, u64>,
+    /// The length in bytes of the longest word.
+    longest: usize,
+}
+
+impl<'w> ExtraWords<'w> {
+    pub(crate) fn new() -> Self {
+        ExtraWords {
+            frequencies: HashMap::new(),
+            longest: 0,
+        }
+    }
+
+    fn from_words(words: &[(&'w str, u64)]) -> Self {
+        let mut extra = ExtraWords::new();
+        for &(word, frequency) in words {
+            if !word.is_empty() {
+                let entry = extra.frequencies.entry(word).or_default();
+                *entry = (*entry).max(frequency);
+                extra.longest = extra.longest.max(word.len());
+            }
+        }
+        extra
+    }
+
+    /// The length of the extra word to cut at the start of `text`, if any: the most frequent of
+    /// the words starting there, or the longest among equally frequent ones.
+    fn word_at(&self, text: &str) -> Option<usize> {
+        let mut best = None;
+        for (i, ch) in text.char_indices() {
+            let len = i + ch.len_utf8();
+            if len > self.longest {
+                break;
+            }
+            if let Some(&frequency) = self.frequencies.get(&text[..len]) {
+                best = best.max(Some((frequency, len)));
+            }
+        }
+        best.map(|(_, len)| len)
+    }
+}
+
+impl Engine {
+    /// Split text into segments with the segmenter, keeping each occurrence of an extra word as a
+    /// segment of its own.  Where extra words overlap, the one starting first wins.
+    pub(crate) fn cut_with<'a>(&self, text: &'a str, words: &ExtraWords) -> Vec<&'a str> {
+        if words.frequencies.is_empty() {
+            return self.cut_folded(text);
+        }
+        let mut segments = Vec::new();
+        let mut run_start = 0;
+        let mut offset = 0;
+        while let Some(ch) = text[offset..].chars().next() {
+            match words.word_at(&text[offset..]) {
+                Some(len) => {
+                    if run_start < offset {
+                        segments.extend(self.cut_folded(&text[run_start..offset]));
+                    }
+                    segments.push(&text[offset..offset + len]);
+                    offset += len;
+                    run_start = offset;
+                }
+                None => offset += ch.len_utf8(),
+            }
+        }
+        if run_start < text.len() {
+            segments.extend(self.cut_folded(&text[run_start..]));
+        }
+        segments
+    }
+
+    /// Convert a string from an input variant to an output variant like [Engine::convert], also
+    /// treating each of `words` as a word when segmenting, for this call only.  This lets
+    /// services with their own terminology, such as one per tenant, share an engine.  Each word
+    /// comes with a frequency; where extra words start at the same place, the most frequent is
+    /// used.
+    pub fn convert_with_words(
+        &self,
+        from: Script,
+        to: Script,
+        input: &str,
+        words: &[(&str, u64)],
+    ) -> Result<Vec<String>> {
+        let mut segments = Vec::new();
+        if self.guards(input) {
+            segments.push(input.to_owned());
+            return Ok(segments);
+        }
+        let words = ExtraWords::from_words(words);
+        let cut = self.segments_with(input, &words);
+        self.convert_segments_into(from, to, input, cut, &mut segments)?;
+        Ok(segments)
+    }
+}
+
+#[cfg(all(test, feature = "global"))]
+mod tests {
+    use super::*;
+    use crate::{convert, convert_with_words};
+
+    #[test]
+    fn test_convert_with_words() -> Result<()> {
+        let result = convert_with_words(Script::CN, Script::TW, "他们的软件", &[("他们的软", 10)])?;
+        assert_eq!(vec!["他們的軟", "件"], result);
+        assert_eq!(
+            "他們的軟體",
+            convert(Script::CN, Script::TW, "他们的软件")?.join("")
+        );
+
+        Ok(())
+    }
+}
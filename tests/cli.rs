@@ -0,0 +1,166 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+/// Run the command line tool with some arguments, feeding it `input` on standard in, and return
+/// what it prints.
+fn ztarcc(args: &[&str], input: &str) -> Result<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ztarcc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success(), "ztarcc {:?} failed", args);
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn test_width() -> Result<()> {
+    let input = "他们的软件ＡＢＣ１２３\n";
+    assert_eq!("他們的軟體ABC123\n", ztarcc(&["--halfwidth"], input)?);
+    assert_eq!(
+        "他們的軟體ABC123\n",
+        ztarcc(&["--halfwidth", "--greedy"], input)?
+    );
+    assert_eq!("ＡＢＣ　１２３\n", ztarcc(&["--fullwidth"], "ABC 123\n")?);
+    Ok(())
+}
+
+#[test]
+fn test_options() -> Result<()> {
+    assert_eq!(
+        "他說「你好」\n",
+        ztarcc(&["--punctuation"], "他说“你好”\n")?
+    );
+    assert_eq!(
+        "これは写真です\n",
+        ztarcc(&["--language-guard"], "これは写真です\n")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_copy_unchanged() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("ztarcc-cli-{}", std::process::id()));
+    let (input, out) = (dir.join("in"), dir.join("out"));
+    std::fs::create_dir_all(&input)?;
+    std::fs::write(input.join("a.txt"), "软件\n")?;
+    std::fs::write(input.join("b.bin"), b"\x89PNG\0")?;
+    let batch = |args: &[&str]| {
+        let status = Command::new(env!("CARGO_BIN_EXE_ztarcc"))
+            .args([
+                "--batch",
+                input.to_str().unwrap(),
+                "--out",
+                out.to_str().unwrap(),
+            ])
+            .args(args)
+            .status()?;
+        anyhow::ensure!(status.success(), "ztarcc --batch {:?} failed", args);
+        Ok((
+            std::fs::read_to_string(out.join("a.txt"))?,
+            std::fs::read(out.join("b.bin")).ok(),
+        ))
+    };
+    let left_out = batch(&[]);
+    std::fs::remove_dir_all(&out)?;
+    let copied = batch(&["--copy-unchanged"]);
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(("軟體\n".to_owned(), None), left_out?);
+    assert_eq!(("軟體\n".to_owned(), Some(b"\x89PNG\0".to_vec())), copied?);
+    Ok(())
+}
+
+#[test]
+fn test_batch_outputs() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("ztarcc-cli-outputs-{}", std::process::id()));
+    let (a, b) = (dir.join("a"), dir.join("b"));
+    std::fs::create_dir_all(&a)?;
+    std::fs::create_dir_all(&b)?;
+    std::fs::write(a.join("x.txt"), "软件\n")?;
+    std::fs::write(b.join("x.txt"), "软件\n")?;
+    let batch = |inputs: &[&Path], out: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_ztarcc"))
+            .arg("--batch")
+            .args(inputs)
+            .args([Path::new("--out"), out])
+            .stderr(Stdio::null())
+            .status()
+    };
+    let same_output = batch(&[&a, &b], &dir.join("out"));
+    let out_inside = batch(&[&a], &a.join("out"));
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(Some(2), same_output?.code());
+    assert_eq!(Some(2), out_inside?.code());
+    Ok(())
+}
+
+#[test]
+fn test_batch_stats() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("ztarcc-cli-stats-{}", std::process::id()));
+    let (input, out, stats) = (dir.join("in"), dir.join("out"), dir.join("stats.json"));
+    std::fs::create_dir_all(&input)?;
+    std::fs::write(input.join("a.html"), "<p class=\"软件\">软件</p>\n")?;
+    let status = Command::new(env!("CARGO_BIN_EXE_ztarcc"))
+        .args(["--batch", input.to_str().unwrap()])
+        .args(["--out", out.to_str().unwrap()])
+        .args(["--stats-file", stats.to_str().unwrap()])
+        .stderr(Stdio::null())
+        .status()?;
+    let written = std::fs::read_to_string(&stats);
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(status.success());
+    // Only the text node is converted, so the attribute is not counted.
+    assert!(written?.contains("\"changed_characters\": 2,"));
+    Ok(())
+}
+
+#[test]
+fn test_custom_scripts() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("ztarcc-cli-custom-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let script = dir.join("sg.def");
+    std::fs::write(&script, "name sg\nbase cn\noverride 坐出租车 搭德士\n")?;
+    let script = script.to_str().unwrap();
+    let to_custom = |args: &[&str], input: &str| {
+        ztarcc(
+            &[&["--script-file", script, "--to-custom", "sg"], args].concat(),
+            input,
+        )
+    };
+    let html = to_custom(&["--html"], "<p title=\"坐出租车\">坐出租车</p>\n");
+    let json = to_custom(&["--format", "json"], "{\"a\":\"坐出租车\"}\n");
+    let greedy = to_custom(&["--greedy"], "我坐出租车\n");
+    let from_custom = ztarcc(&["--script-file", script, "--from-custom", "sg"], "软件\n");
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!("<p title=\"搭德士\">搭德士</p>\n", html?);
+    assert_eq!("{\"a\":\"搭德士\"}\n", json?);
+    assert_eq!("我搭德士\n", greedy?);
+    assert_eq!("軟體\n", from_custom?);
+    Ok(())
+}
+
+#[test]
+fn test_manifest() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("ztarcc-cli-manifest-{}", std::process::id()));
+    let manifest = path.to_str().unwrap();
+    // Only the text node is converted, so the attribute is not counted.
+    let output = ztarcc(
+        &["--html", "--manifest", manifest],
+        "<p class=\"软件\">软件</p>\n",
+    );
+    let written = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!("<p class=\"软件\">軟體</p>\n", output?);
+    assert!(written?.contains("\"substitutions\": 1,"));
+    Ok(())
+}
@@ -1,9 +1,14 @@
+#[cfg(feature = "global")]
 use anyhow::Result;
 use wasm_bindgen_test::*;
+#[cfg(feature = "wasm")]
+use ztarcc_rs::Converter;
+#[cfg(feature = "global")]
 use ztarcc_rs::{convert, Script};
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+#[cfg(feature = "global")]
 #[wasm_bindgen_test]
 fn test_convert() -> Result<()> {
     let input = "我能吞下玻璃而不伤身体。";
@@ -12,3 +17,12 @@ fn test_convert() -> Result<()> {
     assert_eq!(expected, result.join(""));
     Ok(())
 }
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen_test]
+fn test_converter() {
+    let converter = Converter::new("cn", "tw").ok().unwrap();
+    let result = converter.convert("我能吞下玻璃而不伤身体。").ok().unwrap();
+    assert_eq!("我能吞下玻璃而不傷身體。", result);
+    assert!(Converter::new("cn", "xx").is_err());
+}